@@ -13,9 +13,11 @@ use shai_core::agent::LoggingConfig;
 use shai_core::config::config::ShaiConfig;
 use shai_core::config::agent::AgentConfig;
 use shai_core::agent::builder::AgentBuilder;
+use shai_core::tools::{create_mcp_client, get_mcp_tools, McpConfig, McpToolServer};
+use shai_core::tools::mcp::mcp_oauth::signin_oauth;
 use shai_core::runners::clifixer::fix::clifix;
 use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
-use shai_llm::LlmClient;
+use shai_llm::{LlmClient, ToolDescription};
 use tui::auth::AppAuth;
 use tui::theme::{apply_gradient, logo, logo_cyan, SHAI_WHITE, SHAI_YELLOW};
 use tui::App;
@@ -86,6 +88,48 @@ enum AgentAction {
     Agent(Vec<String>),
 }
 
+#[derive(Clone, clap::ValueEnum)]
+enum McpTransport {
+    Stdio,
+    Http,
+    Sse,
+}
+
+#[derive(Subcommand)]
+enum McpAction {
+    /// Add an MCP server to the global config
+    Add {
+        /// Name of the server, its tools are exposed as <name>__<tool>
+        name: String,
+        /// Transport (inferred from the target when omitted: urls are http, or sse if they end with /sse)
+        #[arg(long, value_enum)]
+        transport: Option<McpTransport>,
+        /// Attach the server to the default agent
+        #[arg(long)]
+        attach: bool,
+        /// Also attach the server to this custom agent
+        #[arg(long)]
+        agent: Option<String>,
+        /// Server url, or command and arguments for stdio
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        target: Vec<String>,
+    },
+    /// Remove an MCP server from the global config
+    Remove {
+        name: String,
+    },
+    /// List the configured MCP servers
+    List,
+    /// Run the OAuth flow of an http MCP server and store the token
+    Login {
+        name: String,
+    },
+    /// Connect to an MCP server and print its tools
+    Test {
+        name: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum Commands {
     #[cfg(unix)]
@@ -142,6 +186,40 @@ enum Commands {
         /// Use ephemeral mode (spawn new agent per request)
         #[arg(long)]
         ephemeral: bool,
+        /// Serve without API keys on a non-loopback address
+        #[arg(long)]
+        no_auth: bool,
+        /// Where the tools of each session work: shared, temp, template:<dir> or worktree[:<repo>]
+        #[arg(long, default_value = "shared")]
+        workspace: shai_http::WorkspaceMode,
+        /// Where sessions are saved: none, file[:<dir>] or sqlite[:<path>] (default: files in .shai/sessions)
+        #[arg(long)]
+        store: Option<shai_http::StoreMode>,
+        /// Seconds without activity before a session is saved and unloaded (0 = never)
+        #[arg(long, default_value = "1800")]
+        idle_timeout: u64,
+        /// Seconds after which a session is stopped and deleted (default: never)
+        #[arg(long)]
+        max_age: Option<u64>,
+    },
+    /// MCP server management commands
+    Mcp {
+        #[command(subcommand)]
+        action: McpAction,
+    },
+    /// Expose the agent tools as an MCP server (stdio by default)
+    McpServe {
+        /// Agent whose tools are exposed (defaults to the builtin tools)
+        agent: Option<String>,
+        /// Serve over streamable HTTP on this address instead of stdio (e.g. 127.0.0.1:8000)
+        #[arg(long)]
+        http: Option<String>,
+        /// Run every tool without permission checks
+        #[arg(long)]
+        sudo: bool,
+        /// Also expose a delegate_task tool that runs the whole agent on a task
+        #[arg(long)]
+        delegate: bool,
     }
 }
 
@@ -179,8 +257,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let command_str = command.join(" ");
             handle_postcmd(exit_code, command_str).await?;
         },
-        Some(Commands::Serve { host, port, agent, ephemeral }) => {
-            handle_serve(host, port, agent, ephemeral).await?;
+        Some(Commands::Serve { host, port, agent, ephemeral, no_auth, workspace, store, idle_timeout, max_age }) => {
+            let config = shai_http::ServerConfig::new(format!("{}:{}", host, port))
+                .with_ephemeral(ephemeral)
+                .with_workspace(workspace)
+                .with_store(store.unwrap_or_default())
+                .with_idle_timeout(Some(idle_timeout).filter(|secs| *secs > 0).map(Duration::from_secs))
+                .with_max_age(max_age.map(Duration::from_secs))
+                .with_allow_unauthenticated(no_auth);
+            handle_serve(config, agent).await?;
+        },
+        Some(Commands::Mcp { action }) => {
+            handle_mcp_command(action).await?;
+        },
+        Some(Commands::McpServe { agent, http, sudo, delegate }) => {
+            handle_mcp_serve(agent, http, sudo, delegate).await?;
         },
         None => {
             // Check for stdin input or trailing arguments
@@ -467,7 +558,7 @@ pub async fn handle_postcmd(exit_code: i32, command: String) -> Result<(), Box<d
     Ok(())
 }
 
-async fn handle_serve(host: String, port: u16, agent: Option<String>, ephemeral: bool) -> Result<(), Box<dyn std::error::Error>> {
+async fn handle_serve(config: shai_http::ServerConfig, agent: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
     // Initialize tracing for HTTP server logs
     tracing_subscriber::fmt()
         .with_target(false)
@@ -477,16 +568,135 @@ async fn handle_serve(host: String, port: u16, agent: Option<String>, ephemeral:
 
     println!("{}", logo_cyan());
 
-    let addr = format!("{}:{}", host, port);
-    let config = shai_http::ServerConfig::new(addr)
-        .with_ephemeral(ephemeral)
-        .with_max_sessions(Some(1));
+    let auth = shai_http::AuthConfig::load(&ShaiConfig::serve_config_path()?)?;
+    let config = config
+        .with_max_sessions(Some(1))
+        .with_auth(auth);
 
     shai_http::start_server(config).await?;
 
     Ok(())
 }
 
+async fn handle_mcp_serve(agent: Option<String>, http: Option<String>, sudo: bool, delegate: bool) -> Result<(), Box<dyn std::error::Error>> {
+    // stdout belongs to the MCP protocol in stdio mode, everything else goes to stderr
+    let mut builder = AgentBuilder::create(agent.clone()).await
+        .map_err(|e| format!("Failed to create agent: {}", e))?;
+    if sudo {
+        builder = builder.sudo();
+    }
+
+    let mut server = McpToolServer::new(builder.available_tools, builder.permissions);
+    if delegate {
+        server = server.with_delegate(agent);
+    }
+
+    let tool_names: Vec<String> = server.tools().iter().map(|t| t.name()).collect();
+    eprintln!("\x1b[2m░ mcp tools: {}\x1b[0m", tool_names.join(", "));
+
+    match http {
+        Some(address) => {
+            eprintln!("\x1b[2m░ mcp server listening on http://{}/mcp\x1b[0m", address);
+            server.serve_http(&address).await.map_err(|e| e.to_string())?;
+        }
+        None => {
+            server.serve_stdio().await.map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+async fn handle_mcp_command(action: McpAction) -> Result<(), Box<dyn std::error::Error>> {
+    // a config that exists but fails to load is reported rather than replaced by the defaults
+    let mut config = if ShaiConfig::config_path()?.exists() {
+        ShaiConfig::load()?
+    } else {
+        ShaiConfig::default()
+    };
+
+    match action {
+        McpAction::Add { name, transport, attach, agent, target } => {
+            let (first, args) = target.split_first().ok_or("missing server url or command")?;
+            let args = args.to_vec();
+            let mcp_config = match transport {
+                Some(McpTransport::Stdio) => McpConfig::Stdio { command: first.clone(), args },
+                Some(McpTransport::Http) => McpConfig::Http { url: first.clone(), auth: None },
+                Some(McpTransport::Sse) => McpConfig::Sse { url: first.clone() },
+                None => McpConfig::from_target(first, args),
+            };
+
+            if config.add_mcp_config(name.clone(), mcp_config).is_some() {
+                println!("MCP '{}' updated", name);
+            } else {
+                println!("MCP '{}' added", name);
+            }
+            if attach {
+                config.attach_mcp_to_default_agent(&name)?;
+                println!("MCP '{}' attached to the default agent", name);
+            }
+            config.save()?;
+
+            if let Some(agent_name) = agent {
+                let mut agent_config = AgentConfig::load(&agent_name)
+                    .map_err(|e| format!("Failed to load agent '{}': {}", agent_name, e))?;
+                if !agent_config.tools.global_mcp.contains(&name) {
+                    agent_config.tools.global_mcp.push(name.clone());
+                    agent_config.save()?;
+                }
+                println!("MCP '{}' attached to agent '{}'", name, agent_name);
+            }
+        }
+        McpAction::Remove { name } => {
+            if config.remove_mcp_config(&name).is_none() {
+                return Err(format!("MCP '{}' is not configured", name).into());
+            }
+            config.save()?;
+            println!("MCP '{}' removed", name);
+        }
+        McpAction::List => {
+            let mut servers = config.list_mcp_configs();
+            if servers.is_empty() {
+                println!("No MCP server configured.");
+                println!("Add one with: shai mcp add <name> <url|command>");
+                return Ok(());
+            }
+            servers.sort();
+            let max_name_len = servers.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+            for (name, description) in servers {
+                let attached = if config.default_agent_mcp.contains(&name) { " (default agent)" } else { "" };
+                println!("  \x1b[1m{:<width$}\x1b[0m \x1b[2m{}{}\x1b[0m", name, description, attached, width = max_name_len);
+            }
+        }
+        McpAction::Login { name } => {
+            let Some(McpConfig::Http { url, .. }) = config.get_mcp_config(&name).cloned() else {
+                return Err(format!("MCP '{}' is not an http server", name).into());
+            };
+            let token = signin_oauth(&url).await.map_err(|e| format!("OAuth failed for MCP '{}': {}", name, e))?;
+            config.add_mcp_config(name.clone(), McpConfig::Http { url, auth: Some(token) });
+            config.save()?;
+            println!("MCP '{}' logged in", name);
+        }
+        McpAction::Test { name } => {
+            let mcp_config = config.get_mcp_config(&name).cloned()
+                .ok_or_else(|| format!("MCP '{}' is not configured", name))?;
+            let tools = get_mcp_tools(create_mcp_client(mcp_config), &name).await
+                .map_err(|e| format!("Failed to connect to MCP '{}': {}", name, e))?;
+
+            println!("MCP '{}' connected, {} tools:", name, tools.len());
+            for tool in tools {
+                println!();
+                println!("  \x1b[1m{}\x1b[0m", tool.name());
+                println!("  \x1b[2m{}\x1b[0m", tool.description().trim());
+                let schema = serde_json::to_string_pretty(&tool.parameters_schema())?;
+                for line in schema.lines() {
+                    println!("    {}", line);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 async fn handle_agent_command(action: AgentAction) -> Result<(), Box<dyn std::error::Error>> {
     match action {
         AgentAction::List => {
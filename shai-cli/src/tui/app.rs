@@ -13,7 +13,7 @@ use ratatui::style::Stylize;
 use ratatui::text::{Line, Span, Text};
 use ratatui::Terminal;
 use shai_core::agent::{Agent, AgentRequest, AgentEvent, AgentController, PublicAgentState};
-use shai_core::agent::events::{PermissionRequest, PermissionResponse};
+use shai_core::agent::events::{PermissionRequest, PermissionResponse, UserRequest};
 use shai_core::agent::output::PrettyFormatter;
 use shai_core::config::config::ShaiConfig;
 use shai_core::config::agent::AgentConfig;
@@ -21,7 +21,7 @@ use shai_core::agent::builder::AgentBuilder;
 use shai_core::logging::LoggingConfig;
 use shai_core::runners::coder::coder::coder;
 use shai_core::tools::{ToolCall, ToolResult};
-use shai_llm::{LlmClient, ToolCallMethod};
+use shai_llm::{ModelInfo, ToolCallMethod};
 use ratatui::{
     layout::{Constraint, Direction, Layout},
     style::{Color, Style},
@@ -40,6 +40,7 @@ use super::input::UserAction;
 use crate::tui::perm::PermissionWidget;
 use crate::tui::perm_alt_screen::AlternateScreenPermissionModal;
 use super::perm::PermissionModalAction;
+use super::query::{UserQueryModalAction, UserQueryWidget};
 use super::theme::Theme;
 
 
@@ -47,6 +48,9 @@ pub enum AppModalState<'a> {
     InputShown,
     PermissionModal {
         widget: PermissionWidget<'a>   
+    },
+    QueryModal {
+        widget: UserQueryWidget
     }
 }
 
@@ -70,9 +74,13 @@ pub struct App<'a> {
     pub(crate) commands: HashMap<(String, String),Vec<String>>,
     pub(crate) exit: bool,
     pub(crate) permission_queue: VecDeque<(String, PermissionRequest)>, // (request_id, request)
+    pub(crate) query_queue: VecDeque<(String, UserRequest)>, // (request_id, request)
 
     pub(crate) total_input_tokens: u32,
+    pub(crate) total_cached_tokens: u32,
     pub(crate) total_output_tokens: u32,
+    pub(crate) last_input_tokens: u32, // prompt size of the last call
+    pub(crate) model_info: Option<ModelInfo>, // context window and pricing of the agent model
     
     pub(crate) theme: Theme, // UI theme (dark/light)
 }
@@ -86,14 +94,19 @@ impl App<'_> {
             let config = AgentConfig::load(agent_name)?;
             
             println!("\x1b[2m░ agent {} - {} on {}\x1b[0m", agent_name, config.llm_provider.model, config.llm_provider.provider);
-            
+
             // Create agent from config
             let agent_builder = AgentBuilder::from_config(config).await?;
+            if let (Some(llm), Some(model)) = (&agent_builder.llm, &agent_builder.model) {
+                self.model_info = Some(llm.model_info(model).await);
+            }
             Box::new(agent_builder.build())
         } else {
             // Use default coder agent
             let (llm, model) = ShaiConfig::get_llm().await?;
             println!("\x1b[2m░ {} on {}\x1b[0m", model, llm.provider().name());
+
+            self.model_info = Some(llm.model_info(&model).await);
             
             Box::new(coder(Arc::new(llm), model))
         };
@@ -157,10 +170,17 @@ impl App<'_> {
             self.permission_queue.push_back((request_id.clone(), request.clone()));
         }
 
+        // Handle user input requests (e.g. MCP elicitation) - just add to queue
+        if let AgentEvent::UserInputRequired { request_id, request } = &event {
+            self.query_queue.push_back((request_id.clone(), request.clone()));
+        }
+
         // Handle token usage tracking
-        if let AgentEvent::TokenUsage { input_tokens, output_tokens } = &event {
+        if let AgentEvent::TokenUsage { input_tokens, output_tokens, cached_tokens } = &event {
             self.total_input_tokens += input_tokens;
             self.total_output_tokens += output_tokens;
+            self.total_cached_tokens += cached_tokens;
+            self.last_input_tokens = *input_tokens;
         }
         
         Ok(())
@@ -186,8 +206,12 @@ impl App<'_> {
             exit: false,
             running_tools: HashMap::new(),
             permission_queue: VecDeque::new(),
+            query_queue: VecDeque::new(),
             total_input_tokens: 0,
+            total_cached_tokens: 0,
             total_output_tokens: 0,
+            last_input_tokens: 0,
+            model_info: None,
             theme,
         }
     }
@@ -257,8 +281,9 @@ impl App<'_> {
                 }
             }
             
-            // Check permission queue and update state
+            // Check permission and query queues and update state
             self.check_permission_queue().await?;
+            self.check_query_queue();
         }
         Ok(())
     }
@@ -301,6 +326,10 @@ impl App<'_> {
                 let action = widget.handle_key_event(key_event).await;
                 self.handle_permission_action(action).await?;
             }
+            AppModalState::QueryModal { widget } => {
+                let action = widget.handle_key_event(key_event).await;
+                self.handle_query_action(action).await?;
+            }
         }
         Ok(())
     }
@@ -369,6 +398,44 @@ impl App<'_> {
         Ok(())
     }
 
+    async fn handle_query_action(&mut self, action: UserQueryModalAction) -> io::Result<()> {
+        match action {
+            UserQueryModalAction::Response { request_id, response } => {
+                if let Some(ref agent) = self.agent {
+                    if agent.controller.response_user_query(request_id, response).await.is_err() {
+                        self.input.alert_msg("channel with agent closed. Please restart the app", Duration::from_secs(3));
+                    }
+                }
+
+                self.query_queue.pop_front();
+                self.state = AppModalState::InputShown;
+            }
+            UserQueryModalAction::Nope => {}
+        }
+        Ok(())
+    }
+
+    /// Permissions go first, queries are shown once no other modal is open
+    fn check_query_queue(&mut self) {
+        match &self.state {
+            AppModalState::InputShown if !self.query_queue.is_empty() => {
+                let (request_id, request) = self.query_queue.front().unwrap();
+                self.state = AppModalState::QueryModal {
+                    widget: UserQueryWidget::new(
+                        request_id.clone(),
+                        request.clone(),
+                        self.query_queue.len(),
+                        self.theme.palette()
+                    )
+                };
+            }
+            AppModalState::QueryModal { .. } if self.query_queue.is_empty() => {
+                self.state = AppModalState::InputShown;
+            }
+            _ => {}
+        }
+    }
+
     async fn handle_user_action(&mut self, action: UserAction) -> io::Result<()> {
         match action {
             UserAction::Nope => {}
@@ -400,6 +467,7 @@ impl App<'_> {
         let modal_height = match &self.state {
             AppModalState::InputShown => self.input.height(),
             AppModalState::PermissionModal { widget } => widget.height(),
+            AppModalState::QueryModal { widget } => widget.height(),
         }.max(5);
         let height = modal_height
         + 1 
@@ -434,6 +502,9 @@ impl App<'_> {
                     AppModalState::PermissionModal { widget } => {
                         widget.draw(frame, modal)
                     }
+                    AppModalState::QueryModal { widget } => {
+                        widget.draw(frame, modal)
+                    }
                 }
             })?;
         }
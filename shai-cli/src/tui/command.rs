@@ -1,4 +1,7 @@
 use std::{collections::HashMap, io, time::Duration};
+use shai_core::config::agent::McpToolConfig;
+use shai_core::config::config::ShaiConfig;
+use shai_core::tools::McpConfig;
 use shai_llm::ToolCallMethod;
 
 use crate::tui::App;
@@ -9,9 +12,10 @@ impl App<'_> {
         HashMap::from([
             (("/exit","exit from the tui"), vec![]),
             (("/auth","select a provider"), vec![]),
-            (("/tc","set the tool call method: [fc | fc2 | so]"), vec!["method"]),
-            (("/tokens","display token usage (input/output)"), vec![]),
+            (("/tc","set the tool call method: [auto | fc | fc2 | so | parsing]"), vec!["method"]),
+            (("/tokens","display token usage, context window and cost"), vec![]),
             (("/theme","set theme: [dark | light | toggle]"), vec!["mode"]),
+            (("/mcp","manage mcp servers: [list | connect <name> [url|command..] | disconnect <name> | refresh <name>]"), vec!["action"]),
         ])
         .into_iter()
         .map(|((cmd,desc),args)|((cmd.to_string(),desc.to_string()),args.into_iter().map(|s|s.to_string()).collect()))
@@ -54,17 +58,32 @@ impl App<'_> {
                                 self.input.set_tool_call_method(method);
                             }
                         }
+                        Some("parsing") => {
+                            if let Ok(method) = agent.controller.set_method(Some(ToolCallMethod::Parsing)).await {
+                                self.input.alert_msg("llm will now write tool calls as tags in its answer", Duration::from_secs(3));
+                                self.input.set_tool_call_method(method);
+                            }
+                        }
                         _ => {}
                     }
                 }
             }
             "/tokens" => {
-                let msg = format!(
-                    "Token Usage - Input: {}, Output: {}, Total: {}",
+                let mut msg = format!(
+                    "Token Usage - Input: {} ({} cached), Output: {}, Total: {}",
                     self.total_input_tokens,
+                    self.total_cached_tokens,
                     self.total_output_tokens,
                     self.total_input_tokens + self.total_output_tokens
                 );
+                if let Some(info) = &self.model_info {
+                    if let (Some(usage), Some(window)) = (info.context_usage(self.last_input_tokens), info.context_window) {
+                        msg.push_str(&format!(" | Context: {:.0}% of {}k", usage, window / 1000));
+                    }
+                    if let Some(cost) = info.cost(self.total_input_tokens, self.total_output_tokens, self.total_cached_tokens) {
+                        msg.push_str(&format!(" | Cost: ${:.4}", cost));
+                    }
+                }
                 self.input.alert_msg(&msg, Duration::from_secs(5));
             }
             "/theme" => {
@@ -96,10 +115,63 @@ impl App<'_> {
                     }
                 }
             }
+            "/mcp" => {
+                self.handle_mcp_command(&args).await;
+            }
             _ => {
                 self.input.alert_msg("command unknown", Duration::from_secs(1));
             }
         }
         Ok(())
     }
-}
\ No newline at end of file
+
+    async fn handle_mcp_command(&mut self, args: &[&str]) {
+        let Some(ref agent) = self.agent else {
+            return;
+        };
+
+        let msg = match args {
+            [] | ["list"] => match agent.controller.list_mcp_servers().await {
+                Ok(servers) if servers.is_empty() => "no mcp server connected".to_string(),
+                Ok(servers) => servers.iter()
+                    .map(|(name, tools)| format!("{} ({} tools)", name, tools.len()))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                Err(e) => e.to_string(),
+            },
+            ["connect", name, rest @ ..] => {
+                // a bare name refers to a server added with `shai mcp add`
+                let config = match rest {
+                    [] => ShaiConfig::load().ok().and_then(|c| c.get_mcp_config(name).cloned()),
+                    [target, args @ ..] => Some(McpConfig::from_target(target, args.iter().map(|s| s.to_string()).collect())),
+                };
+                match config {
+                    Some(config) => match agent.controller.add_mcp_server(name, McpToolConfig::new(config)).await {
+                        Ok((tools, skipped)) => format!("mcp '{}' connected: {}{}", name, tools.join(", "), skipped_tools(&skipped)),
+                        Err(e) => e.to_string(),
+                    },
+                    None => format!("mcp '{}' is not configured, run shai mcp add first", name),
+                }
+            }
+            ["disconnect", name] => match agent.controller.remove_mcp_server(name).await {
+                Ok(tools) => format!("mcp '{}' disconnected ({} tools removed)", name, tools.len()),
+                Err(e) => e.to_string(),
+            },
+            ["refresh", name] => match agent.controller.refresh_mcp_server(name).await {
+                Ok((tools, skipped)) => format!("mcp '{}' refreshed ({} tools){}", name, tools.len(), skipped_tools(&skipped)),
+                Err(e) => e.to_string(),
+            },
+            _ => "Usage: /mcp [list | connect <name> [url|command..] | disconnect <name> | refresh <name>]".to_string(),
+        };
+        self.input.alert_msg(&msg, Duration::from_secs(5));
+    }
+}
+
+/// Alert suffix for the MCP tools left out because their name is taken
+fn skipped_tools(skipped: &[String]) -> String {
+    if skipped.is_empty() {
+        String::new()
+    } else {
+        format!(", skipped (name already taken): {}", skipped.join(", "))
+    }
+}
@@ -14,7 +14,7 @@ impl HelpArea {
             "",
             "  Available Commands:",
             "  /exit                exit from the tui",
-            "  /tc <method>         set tool call method: [auto | fc | fc2 | so]",
+            "  /tc <method>         set tool call method: [auto | fc | fc2 | so | parsing]",
             "  /tokens              display token usage"
         ].join("\n").to_string()
     }
@@ -3,6 +3,7 @@ pub mod app;
 pub mod input;
 pub mod perm;
 pub mod perm_alt_screen;
+pub mod query;
 pub mod theme;
 pub mod command;
 pub mod helper;
@@ -0,0 +1,178 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    symbols::border,
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Padding, Paragraph},
+    Frame
+};
+use shai_core::agent::{UserRequest, UserResponse};
+
+use super::theme::ThemePalette;
+
+pub enum UserQueryModalAction {
+    Nope,
+    Response {
+        request_id: String,
+        response: UserResponse
+    }
+}
+
+/// Modal answering a UserInputRequired event (MCP elicitation, sampling approval...)
+#[derive(Clone)]
+pub struct UserQueryWidget {
+    pub request_id: String,
+    pub request: UserRequest,
+    pub remaining_queries: usize,
+
+    selected_index: usize,
+    text: String,
+    palette: ThemePalette,
+}
+
+impl UserQueryWidget {
+    pub fn new(request_id: String, request: UserRequest, total: usize, palette: ThemePalette) -> Self {
+        Self {
+            request_id,
+            request,
+            remaining_queries: total,
+            selected_index: 0,
+            text: String::new(),
+            palette,
+        }
+    }
+
+    fn prompt(&self) -> &str {
+        match &self.request {
+            UserRequest::Text { prompt } => prompt,
+            UserRequest::Choice { prompt, .. } => prompt,
+            UserRequest::Confirmation { prompt } => prompt,
+        }
+    }
+
+    fn options(&self) -> Vec<String> {
+        match &self.request {
+            UserRequest::Text { .. } => vec![],
+            UserRequest::Choice { options, .. } => options.clone(),
+            UserRequest::Confirmation { .. } => vec!["Yes".to_string(), "No".to_string()],
+        }
+    }
+
+    pub fn move_up(&mut self) {
+        let count = self.options().len();
+        if count > 0 {
+            self.selected_index = if self.selected_index == 0 { count - 1 } else { self.selected_index - 1 };
+        }
+    }
+
+    pub fn move_down(&mut self) {
+        let count = self.options().len();
+        if count > 0 {
+            self.selected_index = (self.selected_index + 1) % count;
+        }
+    }
+
+    pub fn get_response(&self) -> UserResponse {
+        match &self.request {
+            UserRequest::Text { .. } => UserResponse::Text(self.text.clone()),
+            UserRequest::Choice { .. } => UserResponse::Choice(self.selected_index),
+            UserRequest::Confirmation { .. } => UserResponse::Confirmation(self.selected_index == 0),
+        }
+    }
+
+    pub async fn handle_key_event(&mut self, key_event: KeyEvent) -> UserQueryModalAction {
+        let is_text = matches!(self.request, UserRequest::Text { .. });
+        match key_event.code {
+            KeyCode::Up if !is_text => {
+                self.move_up();
+                UserQueryModalAction::Nope
+            }
+            KeyCode::Down if !is_text => {
+                self.move_down();
+                UserQueryModalAction::Nope
+            }
+            KeyCode::Char(c) if is_text => {
+                self.text.push(c);
+                UserQueryModalAction::Nope
+            }
+            KeyCode::Backspace if is_text => {
+                self.text.pop();
+                UserQueryModalAction::Nope
+            }
+            KeyCode::Enter => {
+                let request_id = self.request_id.clone();
+                let response = self.get_response();
+                UserQueryModalAction::Response { request_id, response }
+            }
+            KeyCode::Esc => {
+                let request_id = self.request_id.clone();
+                UserQueryModalAction::Response { request_id, response: UserResponse::Cancel }
+            }
+            _ => UserQueryModalAction::Nope
+        }
+    }
+
+    fn body_height(&self) -> u16 {
+        match &self.request {
+            UserRequest::Text { .. } => 1,
+            _ => self.options().len() as u16,
+        }
+    }
+
+    pub fn height(&self) -> u16 {
+        4 // outer block 2 + 1 top/bottom padding
+        + self.prompt().lines().count() as u16
+        + 1 // space
+        + self.body_height()
+    }
+
+    pub fn draw(&self, f: &mut Frame, area: Rect) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED)
+            .padding(Padding{left: 1, right: 1, top: 1, bottom: 1})
+            .border_style(Style::default().fg(self.palette.status))
+            .title(if self.remaining_queries > 1 {
+                format!(" ❓ Input Required ({}/{}) ", 1, self.remaining_queries)
+            } else {
+                format!(" ❓ Input Required ")
+            });
+
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let prompt_height = self.prompt().lines().count() as u16;
+        let [prompt, _, body] = Layout::vertical([
+            Constraint::Length(prompt_height),
+            Constraint::Length(1),
+            Constraint::Length(self.body_height())
+        ]).areas(inner);
+
+        let lines: Vec<Line> = self.prompt().lines()
+            .map(|l| Line::from(Span::styled(l.to_string(), Style::default().fg(self.palette.input_text))))
+            .collect();
+        f.render_widget(Paragraph::new(Text::from(lines)), prompt);
+
+        let lines = match &self.request {
+            UserRequest::Text { .. } => vec![Line::from(vec![
+                Span::styled("❯ ", self.palette.suggestion_selected_fg),
+                Span::styled(self.text.clone(), Style::default().fg(self.palette.input_text).bold())
+            ])],
+            _ => self.options().into_iter().enumerate().map(|(i, s)| {
+                if i == self.selected_index {
+                    Line::from(vec![
+                        Span::styled("❯ ", self.palette.suggestion_selected_fg),
+                        Span::styled(s,    self.palette.suggestion_selected_fg)
+                    ])
+                } else {
+                    Line::from(vec![
+                        Span::styled("  ", self.palette.placeholder),
+                        Span::styled(s,    self.palette.placeholder)
+                    ])
+                }
+            }).collect(),
+        };
+        f.render_widget(Paragraph::new(Text::from(lines)), body);
+    }
+}
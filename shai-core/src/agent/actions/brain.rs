@@ -1,5 +1,8 @@
+use std::sync::Arc;
 use chrono::Utc;
 use openai_dive::v1::resources::chat::ChatMessage;
+use shai_llm::retry::{observe_retries, RetryNotice, RetryObserver};
+use shai_llm::ReasoningState;
 use tracing::info;
 use tokio_util::sync::CancellationToken;
 use crate::agent::{AgentCore, AgentError, AgentEvent, InternalAgentEvent, InternalAgentState, ThinkerContext, ThinkerDecision, ThinkerFlowControl};
@@ -19,13 +22,24 @@ impl AgentCore {
             method
         };
         let brain = self.brain.clone();
+        let event_tx = self.socket.tx_event.clone();
+        let on_retry: RetryObserver = Arc::new(move |notice: RetryNotice| {
+            if let Some(tx) = &event_tx {
+                let _ = tx.send(AgentEvent::LlmRetry {
+                    attempt: notice.attempt,
+                    max_retries: notice.max_retries,
+                    delay: notice.delay,
+                    reason: notice.reason,
+                });
+            }
+        });
         
         //////////////////////// TOKIO SPAWN
         tokio::spawn(async move {
             tokio::select! {
-                result = async {
+                result = observe_retries(on_retry, async {
                     brain.write().await.next_step(context).await
-                } => {
+                }) => {
                     let _ = tx_clone.send(InternalAgentEvent::BrainResult {
                         result
                     });
@@ -47,7 +61,7 @@ impl AgentCore {
 
     /// Process a brain task result
     pub async fn process_next_step(&mut self, result: Result<ThinkerDecision, AgentError>) -> Result<(), AgentError> {
-        let ThinkerDecision{message, flow, token_usage} = self.handle_brain_error(result).await?;
+        let ThinkerDecision{message, flow, token_usage, cached_tokens} = self.handle_brain_error(result).await?;
         let ChatMessage::Assistant { content, reasoning_content, tool_calls, .. } = message.clone() else {
             return self.handle_brain_error::<ThinkerDecision>(
                 Err(AgentError::InvalidResponse(format!("ChatMessage::Assistant expected, but got {:?} instead", message)))).await.map(|_| ()
@@ -59,19 +73,21 @@ impl AgentCore {
         let trace = self.trace.clone();
         trace.write().await.push(message.clone());
         
-        // Emit event to external consumers
-        let _ = self.emit_event(AgentEvent::BrainResult {
-            timestamp: Utc::now(),
-            thought: Ok(message.clone())
-        }).await;
-
-        // Emit token usage event if available
+        // Emit token usage event if available, before the result so that a consumer
+        // stopping at the result has the usage of the step
         if let Some((input_tokens, output_tokens)) = token_usage {
             let _ = self.emit_event(AgentEvent::TokenUsage {
                 input_tokens,
-                output_tokens
+                output_tokens,
+                cached_tokens
             }).await;
         }
+
+        // Emit event to external consumers, the reasoning state is only meant for the provider
+        let _ = self.emit_event(AgentEvent::BrainResult {
+            timestamp: Utc::now(),
+            thought: Ok(message.clone().without_reasoning_state())
+        }).await;
     
         // run tool call if any
         let tool_calls_from_brain = tool_calls.unwrap_or(vec![]);
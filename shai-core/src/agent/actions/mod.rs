@@ -1,2 +1,3 @@
 pub mod brain;
 pub mod tools;
+pub mod toolbox;
@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+use shai_llm::ToolDescription;
+use tracing::{debug, error};
+use crate::agent::{AgentCore, AgentError};
+use crate::tools::ToolSet;
+
+impl AgentCore {
+    /// Add tools to the toolbox, they are available from the next brain step
+    /// Fails without adding anything if a name is already taken
+    pub fn add_tools(&mut self, tools: ToolSet) -> Result<Vec<String>, AgentError> {
+        for tool in &tools.0 {
+            let name = tool.name();
+            if let Some(existing) = self.available_tools.iter().find(|t| t.name() == name) {
+                return Err(AgentError::ToolError(format!(
+                    "Tool name collision: '{}' is already provided by '{}'",
+                    name, existing.group().unwrap_or("unknown")
+                )));
+            }
+        }
+
+        let names = tools.names();
+        self.available_tools.extend(tools.0);
+        Ok(names)
+    }
+
+    /// Remove tools by name, returns the removed names
+    pub fn remove_tools(&mut self, names: &[String]) -> Vec<String> {
+        let mut removed = Vec::new();
+        self.available_tools.retain(|t| {
+            let keep = !names.contains(&t.name());
+            if !keep {
+                removed.push(t.name());
+            }
+            keep
+        });
+        removed
+    }
+
+    /// Remove every tool of a group (an MCP server), returns the removed names
+    pub fn remove_tool_group(&mut self, group: &str) -> Vec<String> {
+        let names: Vec<String> = self.available_tools.iter()
+            .filter(|t| t.group() == Some(group))
+            .map(|t| t.name())
+            .collect();
+        self.remove_tools(&names)
+    }
+
+    /// Replace the tools of a group, tools colliding with another group are skipped
+    /// Returns the names of the tools added and of the tools skipped
+    pub fn replace_tool_group(&mut self, group: &str, tools: ToolSet) -> (Vec<String>, Vec<String>) {
+        self.remove_tool_group(group);
+        let (mut added, mut skipped) = (Vec::new(), Vec::new());
+        for tool in tools.0 {
+            let name = tool.name();
+            if self.available_tools.iter().any(|t| t.name() == name) {
+                error!(target: "agent::toolbox", "tool '{}' from '{}' collides with an existing tool, skipped", name, group);
+                skipped.push(name);
+                continue;
+            }
+            self.available_tools.push(tool);
+            added.push(name);
+        }
+        debug!(target: "agent::toolbox", group = group, "tools refreshed");
+        (added, skipped)
+    }
+
+    /// Tools grouped by MCP server, builtin tools excluded
+    pub fn list_mcp_servers(&self) -> Vec<(String, Vec<String>)> {
+        let mut servers: BTreeMap<String, Vec<String>> = self.mcp_host.servers()
+            .into_iter()
+            .map(|name| (name, vec![]))
+            .collect();
+        for tool in &self.available_tools {
+            if let Some(group) = tool.group() {
+                if let Some(tools) = servers.get_mut(group) {
+                    tools.push(tool.name());
+                }
+            }
+        }
+        servers.into_iter().collect()
+    }
+}
@@ -1,11 +1,12 @@
 use std::sync::Arc;
 use std::boxed::Box;
 use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
-use shai_llm::ToolCallMethod;
+use shai_llm::{ToolCallMethod, ToolDescription};
 use tokio::sync::{mpsc, broadcast, RwLock, oneshot};
 use serde::{Serialize, Deserialize};
 use async_trait::async_trait;
 use crate::tools::AnyTool;
+use crate::tools::mcp::{McpAgentLink, McpHost};
 use crate::agent::ClaimManager;
 
 // Helper functions to make the main loop more readable
@@ -73,6 +74,9 @@ pub struct AgentCore {
     /// internal event
     pub internal_tx: broadcast::Sender<InternalAgentEvent>,   // event may be produced from many part of the agent
     pub internal_rx: broadcast::Receiver<InternalAgentEvent>, // events are mostly consumed by the main event loop, but also in spawn tool to monitor permissions
+
+    /// answers sampling / elicitation requests of the MCP servers
+    pub mcp_host: McpHost,
 }
 
 pub struct AgentSocket {
@@ -107,6 +111,7 @@ impl AgentCore {
             state: InternalAgentState::Starting,
             internal_tx,
             internal_rx,
+            mcp_host: McpHost::default(),
         }
     }
 
@@ -274,8 +279,20 @@ impl AgentCore {
         }
     }
     
-    /// Main execution loop with single command receiver
+    /// Run the agent, MCP servers can reach the user for the duration of the run
     async fn start(&mut self) -> Result<AgentResult, AgentError> {
+        self.mcp_host.attach(McpAgentLink {
+            public_event_tx: self.socket.tx_event.clone(),
+            internal_tx: self.internal_tx.clone(),
+            permissions: self.permissions.clone(),
+        });
+        let result = self.run_loop().await;
+        self.mcp_host.detach();
+        result
+    }
+
+    /// Main execution loop with single command receiver
+    async fn run_loop(&mut self) -> Result<AgentResult, AgentError> {
         self.handle_event(InternalAgentEvent::AgentInitialized).await?;
         
         loop {
@@ -444,6 +461,36 @@ impl AgentCore {
                 }).map_err(|_| AgentError::SessionClosed)?;
                 Ok(AgentResponse::Ack)
             }
+            AgentRequest::AddTools { tools } => {
+                self.add_tools(tools).map(|tools| AgentResponse::Tools { tools })
+            }
+            AgentRequest::RemoveTools { names } => {
+                Ok(AgentResponse::Tools { tools: self.remove_tools(&names) })
+            }
+            AgentRequest::ListTools => {
+                let tools = self.available_tools.iter().map(|t| t.name()).collect();
+                Ok(AgentResponse::Tools { tools })
+            }
+            AgentRequest::ListMcpServers => {
+                Ok(AgentResponse::McpServers { servers: self.list_mcp_servers() })
+            }
+            AgentRequest::GetMcpHost => {
+                Ok(AgentResponse::McpHost { host: self.mcp_host.clone() })
+            }
+            AgentRequest::RemoveMcpServer { name } => {
+                if self.mcp_host.servers().contains(&name) {
+                    let tools = self.remove_tool_group(&name);
+                    let host = self.mcp_host.clone();
+                    tokio::spawn(async move { host.unregister(&name).await; });
+                    Ok(AgentResponse::Tools { tools })
+                } else {
+                    Err(AgentError::ConfigurationError(format!("MCP '{}' is not connected", name)))
+                }
+            }
+            AgentRequest::ReplaceToolGroup { group, tools } => {
+                let (tools, skipped) = self.replace_tool_group(&group, tools);
+                Ok(AgentResponse::ToolGroup { tools, skipped })
+            }
             AgentRequest::WaitTurn => {
                 self.handle_wait_turn(backchannel).await;
                 return Ok(()); // We handle the response in the spawned task
@@ -459,6 +506,13 @@ impl AgentCore {
     /// Handle an event
     async fn handle_event(&mut self, event: InternalAgentEvent) -> Result<(), AgentError> {
         debug!(target: "agent::internal_event", event = ?event);
+
+        // toolbox updates do not depend on the state, they apply to the next step
+        if let InternalAgentEvent::ToolsChanged { group, tools } = event {
+            self.replace_tool_group(&group, tools);
+            return Ok(());
+        }
+
         match self.state {
             InternalAgentState::Starting => {
                 self.state_starting_handle_event(event).await
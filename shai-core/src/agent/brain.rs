@@ -30,6 +30,7 @@ pub struct ThinkerDecision {
     pub message: ChatMessage,
     pub flow:    ThinkerFlowControl,
     pub token_usage: Option<(u32, u32)>, // (input_tokens, output_tokens)
+    pub cached_tokens: u32,               // part of input_tokens served from the provider cache
 }
 
 impl ThinkerDecision {
@@ -38,6 +39,7 @@ impl ThinkerDecision {
             message,
             flow: ThinkerFlowControl::AgentPause,
             token_usage: None,
+            cached_tokens: 0,
         }
     }
 
@@ -46,6 +48,7 @@ impl ThinkerDecision {
             message,
             flow: ThinkerFlowControl::AgentContinue,
             token_usage: None,
+            cached_tokens: 0,
         }
     }
 
@@ -54,6 +57,7 @@ impl ThinkerDecision {
             message,
             flow: ThinkerFlowControl::AgentPause,
             token_usage: None,
+            cached_tokens: 0,
         }
     }
 
@@ -62,6 +66,7 @@ impl ThinkerDecision {
             message,
             flow: ThinkerFlowControl::AgentContinue,
             token_usage: Some((input_tokens, output_tokens)),
+            cached_tokens: 0,
         }
     }
 
@@ -70,9 +75,15 @@ impl ThinkerDecision {
             message,
             flow: ThinkerFlowControl::AgentPause,
             token_usage: Some((input_tokens, output_tokens)),
+            cached_tokens: 0,
         }
     }
 
+    pub fn with_cached_tokens(mut self, cached_tokens: u32) -> Self {
+        self.cached_tokens = cached_tokens;
+        self
+    }
+
     pub fn unwrap(self) -> ChatMessage {
         self.message
     }
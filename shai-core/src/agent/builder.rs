@@ -1,11 +1,15 @@
 use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
-use shai_llm::LlmClient;
+use shai_llm::{LlmClient, ReplayConfig};
 use uuid::Uuid;
+use std::path::Path;
 use std::sync::Arc;
+use tokio::sync::Mutex;
 
 use crate::tools::mcp::mcp_oauth::signin_oauth;
-use crate::tools::{create_mcp_client, get_mcp_tools, AnyTool, BashTool, EditTool, FetchTool, FindTool, FsOperationLog, LsTool, McpConfig, MultiEditTool, ReadTool, TodoReadTool, TodoStorage, TodoWriteTool, WriteTool};
-use crate::config::agent::AgentConfig;
+use crate::tools::mcp::{create_mcp_client_with_host, wrap_mcp_tools, McpHost};
+use crate::tools::mcp::mcp::sanitize_tool_name;
+use crate::tools::{create_mcp_client, AnyTool, BashTool, EditTool, FetchTool, FindTool, FsOperationLog, LsTool, McpConfig, MultiEditTool, ReadTool, TodoReadTool, TodoStorage, TodoWriteTool, WorkspaceRoot, WriteTool};
+use crate::config::agent::{AgentConfig, McpToolConfig};
 use crate::config::config::ShaiConfig;
 use crate::runners::coder::CoderBrain;
 use super::Brain;
@@ -21,6 +25,13 @@ pub struct AgentBuilder {
     pub trace: Vec<ChatMessage>,
     pub available_tools: Vec<Box<dyn AnyTool>>,
     pub permissions: ClaimManager,
+    pub mcp_host: McpHost,
+    /// Shared with the builtin tools, see `workspace`
+    pub root: WorkspaceRoot,
+    /// Model of the agent LLM, None for a brain given by the caller
+    pub model: Option<String>,
+    /// Client of the agent LLM, None for a brain given by the caller
+    pub llm: Option<Arc<LlmClient>>,
 }
 
 impl AgentBuilder {
@@ -45,13 +56,33 @@ impl AgentBuilder {
             .map_err(|e| AgentError::ConfigurationError(format!("Failed to get LLM from config: {}", e)))?;
 
         // Create default brain
-        let brain = Box::new(CoderBrain::new(Arc::new(llm_client), model));
+        let llm_client = Arc::new(llm_client);
+        let brain = Box::new(CoderBrain::new(llm_client.clone(), model.clone()));
 
         // Create default toolbox (using ToolConfig from shai-cli)
         // For now, create basic tools - we can expand this later
-        let tools = Self::create_default_tools();
+        let root = WorkspaceRoot::default();
+        let mut tools = Self::create_default_tools(&root);
+
+        // Attach the global MCP servers selected for the default agent, a failing server is skipped
+        let mcp_host = McpHost::new().with_llm(llm_client.clone(), model.clone());
+        let attached = ShaiConfig::load().map(|c| c.default_agent_mcp).unwrap_or_default();
+        for mcp_name in attached {
+            let result = match Self::connect_global_mcp_server(&mcp_name, &mcp_host).await {
+                Ok(mcp_tools) => Self::push_mcp_tools(&mut tools, &mcp_name, mcp_tools),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                eprintln!("\x1b[2m░ MCP '{}' skipped: {}\x1b[0m", mcp_name, e);
+            }
+        }
 
-        Ok(Self::with_brain(brain).tools(tools))
+        let mut builder = Self::with_brain(brain).tools(tools);
+        builder.mcp_host = mcp_host;
+        builder.root = root;
+        builder.model = Some(model);
+        builder.llm = Some(llm_client);
+        Ok(builder)
     }
 
     /// Create AgentBuilder with a specific brain
@@ -63,25 +94,29 @@ impl AgentBuilder {
             trace: vec![],
             available_tools: vec![],
             permissions: ClaimManager::new(),
+            mcp_host: McpHost::default(),
+            root: WorkspaceRoot::default(),
+            model: None,
+            llm: None,
         }
     }
 
     /// Create default set of tools
-    fn create_default_tools() -> Vec<Box<dyn AnyTool>> {
+    fn create_default_tools(root: &WorkspaceRoot) -> Vec<Box<dyn AnyTool>> {
         let fs_log = Arc::new(FsOperationLog::new());
         let todo_storage = Arc::new(TodoStorage::new());
 
         vec![
-            Box::new(BashTool::new()),
-            Box::new(EditTool::new(fs_log.clone())),
-            Box::new(MultiEditTool::new(fs_log.clone())),
+            Box::new(BashTool::new().with_root(root.clone())),
+            Box::new(EditTool::new(fs_log.clone()).with_root(root.clone())),
+            Box::new(MultiEditTool::new(fs_log.clone()).with_root(root.clone())),
             Box::new(FetchTool::new()),
-            Box::new(FindTool::new()),
-            Box::new(LsTool::new()),
-            Box::new(ReadTool::new(fs_log.clone())),
+            Box::new(FindTool::new().with_root(root.clone())),
+            Box::new(LsTool::new().with_root(root.clone())),
+            Box::new(ReadTool::new(fs_log.clone()).with_root(root.clone())),
             Box::new(TodoReadTool::new(todo_storage.clone())),
             Box::new(TodoWriteTool::new(todo_storage.clone())),
-            Box::new(WriteTool::new(fs_log)),
+            Box::new(WriteTool::new(fs_log).with_root(root.clone())),
         ]
     }
 }
@@ -117,6 +152,13 @@ impl AgentBuilder {
         self
     }
 
+    /// Confine the builtin file system tools, bash and find to `dir`
+    pub fn workspace(self, dir: &Path) -> Result<Self, AgentError> {
+        self.root.set(dir)
+            .map_err(|e| AgentError::ConfigurationError(format!("Invalid workspace {}: {}", dir.display(), e)))?;
+        Ok(self)
+    }
+
     /// Enable sudo mode - bypasses all permission checks
     pub fn sudo(mut self) -> Self {
         self.permissions.sudo();
@@ -130,22 +172,21 @@ impl AgentBuilder {
         }
 
 
-        AgentCore::new(
+        let mut agent = AgentCore::new(
             self.session_id.clone(),
             self.brain,
             self.trace,
             self.available_tools,
             self.permissions
-        )
+        );
+        agent.mcp_host = self.mcp_host;
+        agent
     }
 
     /// Create an AgentBuilder from an AgentConfig
     pub async fn from_config(mut config: AgentConfig) -> Result<Self, AgentError> {
-        // Create LLM client from provider config using the utility method
-        let llm_client = Arc::new(
-            LlmClient::create_provider(&config.llm_provider.provider, &config.llm_provider.env_vars)
-                .map_err(|e| AgentError::LlmError(e.to_string()))?
-        );
+        // Create LLM client from provider config, wrapped in a router if fallbacks or routes are configured
+        let llm_client = Arc::new(Self::llm_from_config(&config)?);
 
         // Create brain with custom system prompt and temperature
         let brain = Box::new(CoderBrain::with_custom_prompt(
@@ -155,8 +196,12 @@ impl AgentBuilder {
             config.temperature,
         ));
 
+        // MCP servers may sample the agent LLM
+        let mcp_host = McpHost::new().with_llm(llm_client.clone(), config.llm_provider.model.clone());
+
         // Create tools
-        let tools = Self::create_tools_from_config(&mut config).await?;
+        let root = WorkspaceRoot::default();
+        let tools = Self::create_tools_from_config(&mut config, &mcp_host, &root).await?;
         
         // Display available tools by category
         let mut tool_groups: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
@@ -178,13 +223,43 @@ impl AgentBuilder {
             }
         }
 
-        Ok(Self::with_brain(brain)
+        let mut builder = Self::with_brain(brain)
             .tools(tools)
-            .id(&format!("agent-{}", config.name)))
+            .id(&format!("agent-{}", config.name));
+        builder.mcp_host = mcp_host;
+        builder.root = root;
+        builder.model = Some(config.llm_provider.model.clone());
+        builder.llm = Some(llm_client);
+        Ok(builder)
+    }
+
+    /// Build the agent LLM client, the primary provider heads the default route.
+    /// Model info comes from the cached registry (bundled table and user overrides).
+    fn llm_from_config(config: &AgentConfig) -> Result<LlmClient, AgentError> {
+        let llm = &config.llm_provider;
+        let primary = LlmClient::create_provider(&llm.provider, &llm.env_vars)
+            .and_then(|client| client.with_retry(llm.retry.clone()).with_reasoning(llm.reasoning.clone()).with_json_hooks(llm.hooks.clone()))
+            .map_err(|e| AgentError::ConfigurationError(format!("Failed to create {} client: {}", llm.provider, e)))?;
+        let Some(routing) = &config.routing else {
+            return Self::with_configured_replay(primary.with_model_registry(ShaiConfig::model_registry()));
+        };
+
+        let router = routing.router(primary, llm.model.clone()).map_err(AgentError::ConfigurationError)?;
+        Self::with_configured_replay(LlmClient::router(router).with_model_registry(ShaiConfig::model_registry()))
+    }
+
+    /// Record to or replay from the cassette of the shai config, else SHAI_REPLAY_CASSETTE
+    fn with_configured_replay(llm: LlmClient) -> Result<LlmClient, AgentError> {
+        let replay = ShaiConfig::load().ok().and_then(|c| c.replay);
+        match replay.or_else(ReplayConfig::from_env) {
+            Some(replay) => llm.with_replay(replay)
+                .map_err(|e| AgentError::ConfigurationError(format!("Failed to set up replay: {}", e))),
+            None => Ok(llm),
+        }
     }
 
     /// Create tools from config
-    async fn create_tools_from_config(config: &mut AgentConfig) -> Result<Vec<Box<dyn AnyTool>>, AgentError> {
+    async fn create_tools_from_config(config: &mut AgentConfig, mcp_host: &McpHost, root: &WorkspaceRoot) -> Result<Vec<Box<dyn AnyTool>>, AgentError> {
         let mut tools: Vec<Box<dyn AnyTool>> = Vec::new();
 
         // Create shared storage for todo tools
@@ -209,16 +284,16 @@ impl AgentBuilder {
             }
             
             match tool_name {
-                "bash" => tools.push(Box::new(BashTool::new())),
-                "edit" => tools.push(Box::new(EditTool::new(fs_log.clone()))),
-                "multiedit" => tools.push(Box::new(MultiEditTool::new(fs_log.clone()))),
+                "bash" => tools.push(Box::new(BashTool::new().with_root(root.clone()))),
+                "edit" => tools.push(Box::new(EditTool::new(fs_log.clone()).with_root(root.clone()))),
+                "multiedit" => tools.push(Box::new(MultiEditTool::new(fs_log.clone()).with_root(root.clone()))),
                 "fetch" => tools.push(Box::new(FetchTool::new())),
-                "find" => tools.push(Box::new(FindTool::new())),
-                "ls" => tools.push(Box::new(LsTool::new())),
-                "read" => tools.push(Box::new(ReadTool::new(fs_log.clone()))),
+                "find" => tools.push(Box::new(FindTool::new().with_root(root.clone()))),
+                "ls" => tools.push(Box::new(LsTool::new().with_root(root.clone()))),
+                "read" => tools.push(Box::new(ReadTool::new(fs_log.clone()).with_root(root.clone()))),
                 "todo_read" => tools.push(Box::new(TodoReadTool::new(todo_storage.clone()))),
                 "todo_write" => tools.push(Box::new(TodoWriteTool::new(todo_storage.clone()))),
-                "write" => tools.push(Box::new(WriteTool::new(fs_log.clone()))),
+                "write" => tools.push(Box::new(WriteTool::new(fs_log.clone()).with_root(root.clone()))),
                 _ => return Err(AgentError::ConfigurationError(format!("Unknown builtin tool: {}", tool_name))),
             }
         }
@@ -231,37 +306,23 @@ impl AgentBuilder {
                 config_changed = true;
             }
 
-            // Get all tools from MCP client
-            let mcp_client = create_mcp_client(mcp_tool_config.config.clone());
-            let all_mcp_tools = get_mcp_tools(mcp_client, mcp_name).await
-                .map_err(|e| AgentError::ConfigurationError(format!("Failed to get tools from MCP '{}': {}", mcp_name, e)))?;
-            
-            // Check if we should add all tools or filter by enabled_tools
-            if mcp_tool_config.enabled_tools.contains(&"*".to_string()) {
-                // Add all tools from this MCP client (except excluded ones)
-                for tool in all_mcp_tools {
-                    let tool_name = tool.name();
-                    if !mcp_tool_config.excluded_tools.contains(&tool_name) {
-                        tools.push(tool);
-                    }
-                }
-            } else {
-                // Filter and add only enabled tools (except excluded ones)
-                for tool in all_mcp_tools {
-                    let tool_name = tool.name();
-                    if mcp_tool_config.enabled_tools.contains(&tool_name) && !mcp_tool_config.excluded_tools.contains(&tool_name) {
-                        tools.push(tool);
-                    }
-                }
-                
-                // Check if all enabled tools were found (only when not using wildcard)
-                for enabled_tool in &mcp_tool_config.enabled_tools {
-                    let found = tools.iter().any(|t| t.name() == *enabled_tool);
-                    if !found {
-                        return Err(AgentError::ConfigurationError(format!("Tool '{}' not found in MCP client '{}'", enabled_tool, mcp_name)));
-                    }
+            let mcp_tools = Self::connect_mcp_server(mcp_name, mcp_tool_config, mcp_host).await?;
+            for (server_tool, alias) in &mcp_tool_config.aliases {
+                if !mcp_tools.iter().any(|t| t.name() == sanitize_tool_name(alias)) {
+                    eprintln!("\x1b[2m░ MCP '{}' has no tool '{}', alias '{}' ignored\x1b[0m", mcp_name, server_tool, alias);
                 }
             }
+
+            Self::push_mcp_tools(&mut tools, mcp_name, mcp_tools)?;
+        }
+
+        // Attach global MCP servers, an MCP of the agent config with the same name takes precedence
+        for mcp_name in &config.tools.global_mcp {
+            if config.tools.mcp.contains_key(mcp_name) {
+                continue;
+            }
+            let mcp_tools = Self::connect_global_mcp_server(mcp_name, mcp_host).await?;
+            Self::push_mcp_tools(&mut tools, mcp_name, mcp_tools)?;
         }
 
         // Save config if OAuth flow added new tokens
@@ -272,6 +333,56 @@ impl AgentBuilder {
         Ok(tools)
     }
 
+    /// Connect to an MCP server and return its enabled tools, named server__tool unless aliased
+    /// The server is registered on `mcp_host` so its tools can be refreshed while the agent runs
+    pub async fn connect_mcp_server(mcp_name: &str, mcp_tool_config: &McpToolConfig, mcp_host: &McpHost) -> Result<Vec<Box<dyn AnyTool>>, AgentError> {
+        let mut mcp_client = create_mcp_client_with_host(mcp_tool_config.config.clone(), mcp_name, mcp_host.clone());
+        mcp_client.connect().await
+            .map_err(|e| AgentError::ConfigurationError(format!("Failed to connect to MCP '{}': {}", mcp_name, e)))?;
+
+        let mcp_client = Arc::new(Mutex::new(mcp_client));
+        let all_mcp_tools = wrap_mcp_tools(mcp_client.clone(), mcp_name, &mcp_tool_config.aliases).await
+            .map_err(|e| AgentError::ConfigurationError(format!("Failed to get tools from MCP '{}': {}", mcp_name, e)))?;
+
+        // Filter with the enabled/excluded glob patterns
+        let mcp_tools = mcp_tool_config.select_tools(mcp_name, all_mcp_tools)
+            .map_err(AgentError::ConfigurationError)?;
+
+        mcp_host.register(mcp_name, mcp_client, mcp_tool_config.clone());
+        Ok(mcp_tools)
+    }
+
+    /// Connect to an MCP server of the global config (`shai mcp add`), refreshed OAuth tokens are saved there
+    pub async fn connect_global_mcp_server(mcp_name: &str, mcp_host: &McpHost) -> Result<Vec<Box<dyn AnyTool>>, AgentError> {
+        let mut shai_config = ShaiConfig::load()
+            .map_err(|e| AgentError::ConfigurationError(format!("Failed to load config: {}", e)))?;
+        let mut mcp_config = shai_config.get_mcp_config(mcp_name).cloned()
+            .ok_or_else(|| AgentError::ConfigurationError(format!("MCP '{}' is not configured, run shai mcp add first", mcp_name)))?;
+
+        if Self::mcp_check_oauth(mcp_name, &mut mcp_config).await? {
+            shai_config.add_mcp_config(mcp_name.to_string(), mcp_config.clone());
+            shai_config.save()
+                .map_err(|e| AgentError::ConfigurationError(format!("Failed to save config: {}", e)))?;
+        }
+
+        Self::connect_mcp_server(mcp_name, &McpToolConfig::new(mcp_config), mcp_host).await
+    }
+
+    /// Add MCP tools to the toolbox, refusing tools shadowing a builtin or another MCP tool
+    fn push_mcp_tools(tools: &mut Vec<Box<dyn AnyTool>>, mcp_name: &str, mcp_tools: Vec<Box<dyn AnyTool>>) -> Result<(), AgentError> {
+        for tool in mcp_tools {
+            let tool_name = tool.name();
+            if let Some(existing) = tools.iter().find(|t| t.name() == tool_name) {
+                return Err(AgentError::ConfigurationError(format!(
+                    "Tool name collision: '{}' from MCP '{}' is already provided by '{}', add an alias in the MCP config",
+                    tool_name, mcp_name, existing.group().unwrap_or("unknown")
+                )));
+            }
+            tools.push(tool);
+        }
+        Ok(())
+    }
+
     /// Handle OAuth flow for MCP connections if needed
     async fn mcp_check_oauth(mcp_name: &str, mcp_config: &mut McpConfig) -> Result<bool, AgentError> {
         use crate::tools::mcp::McpConfig;
@@ -7,7 +7,7 @@ use async_trait::async_trait;
 use super::brain::ThinkerDecision;
 use super::AgentError;
 use crate::agent::PublicAgentState;
-use crate::tools::{ToolResult, ToolCall};
+use crate::tools::{ToolResult, ToolCall, ToolSet};
 use chrono::{DateTime, TimeDelta, Utc};
 
 /// Internal events for agent state machine communication
@@ -48,6 +48,11 @@ pub enum InternalAgentEvent {
     PermissionResponseReceived { 
         request_id: String,
         response: PermissionResponse
+    },
+    /// The tools of a group (MCP server) changed, they replace the current ones
+    ToolsChanged {
+        group: String,
+        tools: ToolSet
     }
 }
 
@@ -99,7 +104,16 @@ pub enum AgentEvent {
     /// Token usage information from LLM response
     TokenUsage {
         input_tokens: u32,
-        output_tokens: u32
+        output_tokens: u32,
+        /// part of input_tokens read from the provider prompt cache
+        cached_tokens: u32
+    },
+    /// A transient LLM error occurred, the call is retried after `delay`
+    LlmRetry {
+        attempt: u32,
+        max_retries: u32,
+        delay: std::time::Duration,
+        reason: String,
     },
 }
 
@@ -268,10 +282,19 @@ impl std::fmt::Debug for AgentEvent {
                     .field("message", message)
                     .finish()
             }
-            AgentEvent::TokenUsage { input_tokens, output_tokens } => {
+            AgentEvent::TokenUsage { input_tokens, output_tokens, cached_tokens } => {
                 f.debug_struct("TokenUsage")
                     .field("input_tokens", input_tokens)
                     .field("output_tokens", output_tokens)
+                    .field("cached_tokens", cached_tokens)
+                    .finish()
+            }
+            AgentEvent::LlmRetry { attempt, max_retries, delay, reason } => {
+                f.debug_struct("LlmRetry")
+                    .field("attempt", attempt)
+                    .field("max_retries", max_retries)
+                    .field("delay", delay)
+                    .field("reason", reason)
                     .finish()
             }
         }
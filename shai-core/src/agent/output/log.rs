@@ -53,8 +53,11 @@ impl FileEventLogger {
             AgentEvent::Completed { success, message } => {
                 format!("Completed: success={} - {}", success, message)
             }
-            AgentEvent::TokenUsage { input_tokens, output_tokens } => {
-                format!("Token Usage: input={} output={} total={}", input_tokens, output_tokens, input_tokens + output_tokens)
+            AgentEvent::TokenUsage { input_tokens, output_tokens, cached_tokens } => {
+                format!("Token Usage: input={} (cached={}) output={} total={}", input_tokens, cached_tokens, output_tokens, input_tokens + output_tokens)
+            }
+            AgentEvent::LlmRetry { attempt, max_retries, delay, reason } => {
+                format!("LlmRetry: {} - attempt {}/{} in {:?}", reason, attempt, max_retries, delay)
             }
         };
 
@@ -114,6 +114,12 @@ impl PrettyFormatter {
                 // Don't display token usage in the main output - it's handled by /tokens command
                 None
             },
+            AgentEvent::LlmRetry { attempt, max_retries, delay, reason } => {
+                let markdown = format!("⏳ {}, retrying in {}s ({}/{})", reason, delay.as_secs_f64().ceil(), attempt, max_retries);
+                let mut retry_skin = self.skin.clone();
+                retry_skin.paragraph.set_fg(rgb(255, 200, 100)); // Amber for transient errors
+                Some(retry_skin.term_text(&markdown).to_string())
+            },
         }.map(|s| format!("\n{}", s))
     }
 
@@ -2,7 +2,9 @@ use openai_dive::v1::resources::chat::ChatMessage;
 use shai_llm::ToolCallMethod;
 use tokio::sync::{mpsc, oneshot};
 use tokio::time::{timeout, Duration};
-use crate::agent::AgentError;
+use crate::agent::{AgentBuilder, AgentError};
+use crate::config::agent::McpToolConfig;
+use crate::tools::{AnyTool, McpHost, ToolSet};
 
 use super::{PermissionResponse, PublicAgentState, UserResponse};
 
@@ -44,6 +46,29 @@ pub enum AgentRequest {
     /// Manage sudo mode: Some(true) = enable, Some(false) = disable, None = get status
     /// Always returns current sudo status after operation
     Sudo(Option<bool>),
+    /// Add tools to the toolbox (names must be free)
+    AddTools {
+        tools: ToolSet
+    },
+    /// Remove tools from the toolbox by name
+    RemoveTools {
+        names: Vec<String>
+    },
+    /// List the name of the available tools
+    ListTools,
+    /// List the connected MCP servers and their tools
+    ListMcpServers,
+    /// Get the host handling the MCP connections of the agent
+    GetMcpHost,
+    /// Disconnect an MCP server and remove its tools
+    RemoveMcpServer {
+        name: String
+    },
+    /// Replace the tools of a group (an MCP server), tools whose name is taken are skipped
+    ReplaceToolGroup {
+        group: String,
+        tools: ToolSet
+    },
     /// Drop controller IO, this closes it for all controller.
     /// Once this is done, it cannot be reopen!
     Droping,
@@ -65,6 +90,20 @@ pub enum AgentResponse {
     SudoStatus {
         enabled: bool
     },
+    Tools {
+        tools: Vec<String>
+    },
+    ToolGroup {
+        tools: Vec<String>,
+        /// tools not added because another tool has their name
+        skipped: Vec<String>
+    },
+    McpServers {
+        servers: Vec<(String, Vec<String>)>
+    },
+    McpHost {
+        host: McpHost
+    },
     Error {
         error: String
     }
@@ -195,4 +234,89 @@ impl AgentController {
             _ => Err(AgentError::InvalidResponse("Expected SudoStatus response".to_string()))
         }
     }
+
+    /// Add tools to the running agent, returns their names
+    pub async fn add_tools(&self, tools: Vec<Box<dyn AnyTool>>) -> Result<Vec<String>, AgentError> {
+        match self.send(AgentRequest::AddTools { tools: ToolSet::from(tools) }).await? {
+            AgentResponse::Tools { tools } => Ok(tools),
+            AgentResponse::Error { error } => Err(AgentError::ToolError(error)),
+            _ => Err(AgentError::InvalidResponse("Expected Tools response".to_string()))
+        }
+    }
+
+    /// Remove tools from the running agent, returns the removed names
+    pub async fn remove_tools(&self, names: Vec<String>) -> Result<Vec<String>, AgentError> {
+        match self.send(AgentRequest::RemoveTools { names }).await? {
+            AgentResponse::Tools { tools } => Ok(tools),
+            _ => Err(AgentError::InvalidResponse("Expected Tools response".to_string()))
+        }
+    }
+
+    pub async fn list_tools(&self) -> Result<Vec<String>, AgentError> {
+        match self.send(AgentRequest::ListTools).await? {
+            AgentResponse::Tools { tools } => Ok(tools),
+            _ => Err(AgentError::InvalidResponse("Expected Tools response".to_string()))
+        }
+    }
+
+    /// Connected MCP servers with the tools they currently provide
+    pub async fn list_mcp_servers(&self) -> Result<Vec<(String, Vec<String>)>, AgentError> {
+        match self.send(AgentRequest::ListMcpServers).await? {
+            AgentResponse::McpServers { servers } => Ok(servers),
+            _ => Err(AgentError::InvalidResponse("Expected McpServers response".to_string()))
+        }
+    }
+
+    /// Replace the tools of a group, returns the names of the tools added and of the tools
+    /// skipped because their name is taken by another group
+    pub async fn replace_tool_group(&self, group: &str, tools: Vec<Box<dyn AnyTool>>) -> Result<(Vec<String>, Vec<String>), AgentError> {
+        match self.send(AgentRequest::ReplaceToolGroup { group: group.to_string(), tools: ToolSet::from(tools) }).await? {
+            AgentResponse::ToolGroup { tools, skipped } => Ok((tools, skipped)),
+            AgentResponse::Error { error } => Err(AgentError::ToolError(error)),
+            _ => Err(AgentError::InvalidResponse("Expected ToolGroup response".to_string()))
+        }
+    }
+
+    async fn mcp_host(&self) -> Result<McpHost, AgentError> {
+        match self.send(AgentRequest::GetMcpHost).await? {
+            AgentResponse::McpHost { host } => Ok(host),
+            _ => Err(AgentError::InvalidResponse("Expected McpHost response".to_string()))
+        }
+    }
+
+    /// Connect an MCP server and add its tools, returns the names of the tools added and of the
+    /// tools skipped because another tool has their name
+    /// The connection happens here so the agent loop is not blocked while the server starts
+    pub async fn add_mcp_server(&self, name: &str, config: McpToolConfig) -> Result<(Vec<String>, Vec<String>), AgentError> {
+        let host = self.mcp_host().await?;
+        if host.servers().iter().any(|s| s == name) {
+            return Err(AgentError::ConfigurationError(format!("MCP '{}' is already connected", name)));
+        }
+
+        let tools = AgentBuilder::connect_mcp_server(name, &config, &host).await?;
+        match self.replace_tool_group(name, tools).await {
+            Ok(tools) => Ok(tools),
+            Err(e) => {
+                host.unregister(name).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Disconnect an MCP server, returns the removed tool names
+    pub async fn remove_mcp_server(&self, name: &str) -> Result<Vec<String>, AgentError> {
+        match self.send(AgentRequest::RemoveMcpServer { name: name.to_string() }).await? {
+            AgentResponse::Tools { tools } => Ok(tools),
+            AgentResponse::Error { error } => Err(AgentError::ConfigurationError(error)),
+            _ => Err(AgentError::InvalidResponse("Expected Tools response".to_string()))
+        }
+    }
+
+    /// List the tools of an MCP server again (e.g. after the server restarted), returns the
+    /// names of the tools now provided and of the tools skipped because their name is taken
+    pub async fn refresh_mcp_server(&self, name: &str) -> Result<(Vec<String>, Vec<String>), AgentError> {
+        let host = self.mcp_host().await?;
+        let tools = host.refresh_tools(name).await.map_err(AgentError::ConfigurationError)?;
+        self.replace_tool_group(name, tools).await
+    }
 }
\ No newline at end of file
@@ -461,3 +461,39 @@ async fn test_agent_with_real_tools() {
         }
     }
 }
+
+#[tokio::test]
+async fn test_runtime_toolbox_update() {
+    use crate::tools::ToolSet;
+    use shai_llm::ToolDescription;
+
+    let mut agent = AgentBuilder::with_brain(Box::new(SleepingThinker::new()))
+        .id("test-toolbox-agent")
+        .tools(vec![Box::new(SleepingTool::new(10))])
+        .build();
+
+    // name collision is refused without adding anything
+    let duplicate: Vec<Box<dyn AnyTool>> = vec![Box::new(LsTool::new()), Box::new(SleepingTool::new(10))];
+    assert!(agent.add_tools(ToolSet::from(duplicate)).is_err());
+    assert_eq!(agent.available_tools.len(), 1);
+
+    let added = agent.add_tools(ToolSet::from(vec![Box::new(LsTool::new()) as Box<dyn AnyTool>])).unwrap();
+    assert_eq!(added, vec!["ls".to_string()]);
+    assert_eq!(agent.available_tools.len(), 2);
+
+    let removed = agent.remove_tools(&["ls".to_string(), "unknown".to_string()]);
+    assert_eq!(removed, vec!["ls".to_string()]);
+
+    // refreshing a group replaces all of its tools (both tools are in the builtin group)
+    let (added, skipped) = agent.replace_tool_group("builtin", ToolSet::from(vec![Box::new(LsTool::new()) as Box<dyn AnyTool>]));
+    assert_eq!(added, vec!["ls".to_string()]);
+    assert!(skipped.is_empty());
+    let names: Vec<String> = agent.available_tools.iter().map(|t| t.name()).collect();
+    assert_eq!(names, vec!["ls".to_string()]);
+
+    // a tool whose name is taken by another group is skipped and reported
+    let (added, skipped) = agent.replace_tool_group("other", ToolSet::from(vec![Box::new(LsTool::new()) as Box<dyn AnyTool>]));
+    assert!(added.is_empty());
+    assert_eq!(skipped, vec!["ls".to_string()]);
+    assert_eq!(agent.available_tools.len(), 1);
+}
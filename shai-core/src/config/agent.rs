@@ -1,8 +1,12 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
-use shai_llm::ToolCallMethod;
+use shai_llm::{JsonHooksConfig, LlmClient, ReasoningConfig, RetryConfig, ToolCallMethod};
+use shai_llm::providers::router::{RouteTarget, RouterProvider};
+use shai_llm::ToolDescription;
+use crate::tools::AnyTool;
 use crate::tools::mcp::McpConfig;
+use crate::tools::mcp::mcp::WrappedMcpTool;
 use super::config::ShaiConfig;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +15,65 @@ pub struct AgentProviderConfig {
     pub env_vars: HashMap<String, String>,
     pub model: String,
     pub tool_method: ToolCallMethod,
+    /// Retry policy for transient LLM errors (rate limits, overload, timeouts)
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// Reasoning effort or thinking budget sent with every request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<ReasoningConfig>,
+    /// Request tweaks (headers, renamed or removed params, merge patch) and think tag extraction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<JsonHooksConfig>,
+}
+
+/// A provider/model step of a routing chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteTargetConfig {
+    pub provider: String,
+    #[serde(default)]
+    pub env_vars: HashMap<String, String>,
+    pub model: String,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<ReasoningConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<JsonHooksConfig>,
+}
+
+impl RouteTargetConfig {
+    fn target(&self) -> Result<RouteTarget, String> {
+        LlmClient::create_provider(&self.provider, &self.env_vars)
+            .and_then(|llm| llm.with_retry(self.retry.clone()).with_reasoning(self.reasoning.clone()).with_json_hooks(self.hooks.clone()))
+            .map(|llm| RouteTarget::new(llm, self.model.clone()))
+            .map_err(|e| format!("Failed to create {} client: {}", self.provider, e))
+    }
+}
+
+/// Failover and per request type routing, `llm_provider` stays the first choice
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentRoutingConfig {
+    /// Tried in order when `llm_provider` fails or times out
+    #[serde(default)]
+    pub fallbacks: Vec<RouteTargetConfig>,
+    /// Chains by request type, `fast` is used for status lines and shell command fixes
+    #[serde(default)]
+    pub routes: HashMap<String, Vec<RouteTargetConfig>>,
+}
+
+impl AgentRoutingConfig {
+    /// Router whose default route is `primary` with `model`, then the fallbacks
+    pub fn router(&self, primary: LlmClient, model: String) -> Result<RouterProvider, String> {
+        let chain = |targets: &[RouteTargetConfig]| targets.iter().map(|t| t.target()).collect::<Result<Vec<_>, _>>();
+
+        let mut default = vec![RouteTarget::new(primary, model)];
+        default.extend(chain(&self.fallbacks)?);
+        let mut router = RouterProvider::new(default);
+        for (route, targets) in &self.routes {
+            router = router.with_route(route.clone(), chain(targets)?);
+        }
+        Ok(router)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +83,88 @@ pub struct McpToolConfig {
     pub enabled_tools: Vec<String>,
     #[serde(default)]
     pub excluded_tools: Vec<String>,
+    /// Rename server tools: server tool name -> name exposed to the LLM
+    /// Tools without an alias are exposed as `server__tool`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub aliases: HashMap<String, String>,
+}
+
+impl McpToolConfig {
+    /// All tools enabled, no alias
+    pub fn new(config: McpConfig) -> Self {
+        Self {
+            config,
+            enabled_tools: default_enabled_tools(),
+            excluded_tools: Vec::new(),
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Check a tool against the enabled/excluded glob patterns
+    /// Patterns are matched against the exposed (namespaced or aliased) name, and the server tool name
+    pub fn is_tool_enabled(&self, exposed_name: &str, server_name: &str) -> bool {
+        let matches = |pattern: &String| glob_match(pattern, exposed_name) || glob_match(pattern, server_name);
+        self.enabled_tools.iter().any(matches) && !self.excluded_tools.iter().any(matches)
+    }
+
+    /// Keep the tools of the server `mcp_name` allowed by the enabled/excluded patterns
+    /// Explicitly enabled tools (no wildcard) must exist, and two kept tools cannot end up with
+    /// the same exposed name once sanitized
+    pub fn select_tools(&self, mcp_name: &str, tools: Vec<WrappedMcpTool>) -> Result<Vec<Box<dyn AnyTool>>, String> {
+        let selected: Vec<WrappedMcpTool> = tools.into_iter()
+            .filter(|tool| self.is_tool_enabled(&tool.name, &tool.desc.name))
+            .collect();
+
+        for (i, tool) in selected.iter().enumerate() {
+            if let Some(other) = selected[..i].iter().find(|other| other.name == tool.name) {
+                return Err(format!(
+                    "Tool name collision: '{}' and '{}' from MCP '{}' are both exposed as '{}', add an alias in the MCP config",
+                    other.desc.name, tool.desc.name, mcp_name, tool.name
+                ));
+            }
+        }
+
+        for enabled_tool in &self.enabled_tools {
+            if enabled_tool.contains('*') || enabled_tool.contains('?') {
+                continue;
+            }
+            let found = selected.iter().any(|t| t.name == *enabled_tool || t.desc.name == *enabled_tool);
+            if !found {
+                return Err(format!("Tool '{}' not found in MCP client '{}'", enabled_tool, mcp_name));
+            }
+        }
+
+        Ok(selected.into_iter().map(|tool| Box::new(tool) as Box<dyn AnyTool>).collect())
+    }
+}
+
+/// Minimal glob matching supporting `*` and `?`
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let (pattern, name): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    let (mut p, mut n) = (0, 0);
+    // last `*` seen and the name position it was tried at, to backtrack on a mismatch
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +175,9 @@ pub struct AgentTools {
     pub builtin_excluded: Vec<String>,
     #[serde(default)]
     pub mcp: HashMap<String, McpToolConfig>,
+    /// MCP servers from the global config (`shai mcp add`) attached by name, all their tools are enabled
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub global_mcp: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +186,8 @@ pub struct AgentConfig {
     pub description: String,
     #[serde(default = "default_llm_provider")]
     pub llm_provider: AgentProviderConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing: Option<AgentRoutingConfig>,
     #[serde(default)]
     pub tools: AgentTools,
     #[serde(default = "default_system_prompt")]
@@ -62,6 +212,9 @@ fn default_llm_provider() -> AgentProviderConfig {
         env_vars: provider_config.env_vars.clone(),
         model: provider_config.model.clone(),
         tool_method: provider_config.tool_method.clone(),
+        retry: provider_config.retry.clone(),
+        reasoning: provider_config.reasoning.clone(),
+        hooks: provider_config.hooks.clone(),
     }
 }
 
@@ -87,6 +240,7 @@ impl Default for AgentTools {
             builtin: vec!["*".to_string()],
             builtin_excluded: Vec::new(),
             mcp: HashMap::new(),
+            global_mcp: Vec::new(),
         }
     }
 }
@@ -188,7 +342,7 @@ impl AgentConfig {
     pub fn is_mcp_tool_enabled(&self, mcp_name: &str, tool_name: &str) -> bool {
         self.tools.mcp
             .get(mcp_name)
-            .map(|mcp_tool| mcp_tool.enabled_tools.contains(&tool_name.to_string()))
+            .map(|mcp_tool| mcp_tool.is_tool_enabled(tool_name, tool_name))
             .unwrap_or(false)
     }
 
@@ -200,4 +354,97 @@ impl AgentConfig {
             .cloned()
             .collect()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::mcp::McpConfig;
+
+    fn mcp_tool_config(enabled: &[&str], excluded: &[&str]) -> McpToolConfig {
+        McpToolConfig {
+            config: McpConfig::Sse { url: "http://localhost:8000/sse".to_string() },
+            enabled_tools: enabled.iter().map(|s| s.to_string()).collect(),
+            excluded_tools: excluded.iter().map(|s| s.to_string()).collect(),
+            aliases: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", "github__search"));
+        assert!(glob_match("github__*", "github__search"));
+        assert!(glob_match("github__sea?ch", "github__search"));
+        assert!(!glob_match("gitlab__*", "github__search"));
+        assert!(!glob_match("search", "github__search"));
+        assert!(glob_match("get.list", "get.list"));
+        assert!(!glob_match("get.list", "get-list"));
+        assert!(glob_match("*search*", "github__search_code"));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b*c", "aXbYbZ"));
+        assert!(glob_match("**", ""));
+        assert!(!glob_match("?", ""));
+    }
+
+    #[test]
+    fn test_mcp_tool_filters_on_namespaced_names() {
+        let config = mcp_tool_config(&["*"], &["github__delete_*"]);
+        assert!(config.is_tool_enabled("github__search", "search"));
+        assert!(!config.is_tool_enabled("github__delete_repo", "delete_repo"));
+
+        let config = mcp_tool_config(&["github__search*"], &[]);
+        assert!(config.is_tool_enabled("github__search_code", "search_code"));
+        assert!(!config.is_tool_enabled("github__create_issue", "create_issue"));
+
+        // server tool names keep working for existing configs
+        let config = mcp_tool_config(&["*"], &["delete_repo"]);
+        assert!(!config.is_tool_enabled("github__delete_repo", "delete_repo"));
+    }
+
+    fn wrapped_tool(mcp_name: &str, server_name: &str) -> WrappedMcpTool {
+        let config = McpConfig::Sse { url: "http://localhost:8000/sse".to_string() };
+        WrappedMcpTool {
+            name: crate::tools::mcp::namespaced_tool_name(mcp_name, server_name),
+            desc: crate::tools::McpToolDescription {
+                name: server_name.to_string(),
+                description: String::new(),
+                parameters_schema: serde_json::json!({}),
+            },
+            client: std::sync::Arc::new(tokio::sync::Mutex::new(crate::tools::create_mcp_client(config))),
+            mcp_name: mcp_name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_select_tools_on_server_names_and_sanitized_collisions() {
+        let tools = || vec![wrapped_tool("docs", "get.list"), wrapped_tool("docs", "get_list")];
+
+        let err = mcp_tool_config(&["*"], &[]).select_tools("docs", tools()).err().unwrap();
+        assert!(err.contains("'get.list' and 'get_list'") && err.contains("'docs__get_list'"), "{}", err);
+
+        // patterns match the server name, not only the sanitized one
+        let selected = mcp_tool_config(&["get.list"], &[]).select_tools("docs", tools()).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].name(), "docs__get_list");
+        let selected = mcp_tool_config(&["*"], &["get_list"]).select_tools("docs", tools()).unwrap();
+        assert_eq!(selected.len(), 1);
+    }
+
+    #[test]
+    fn test_routing_config_builds_the_routes() {
+        let routing: AgentRoutingConfig = serde_json::from_value(serde_json::json!({
+            "fallbacks": [ { "provider": "ollama", "model": "qwen3" } ],
+            "routes": { "fast": [ { "provider": "ollama", "model": "qwen3:0.6b" } ] }
+        })).unwrap();
+        let primary = LlmClient::create_provider("ollama", &HashMap::new()).unwrap();
+        let llm = LlmClient::router(routing.router(primary, "qwen3:32b".to_string()).unwrap());
+        assert_eq!(llm.route_model(shai_llm::providers::router::ROUTE_FAST, "qwen3:32b"), "fast");
+        assert_eq!(llm.route_model("summary", "qwen3:32b"), "qwen3:32b");
+
+        let unknown: AgentRoutingConfig = serde_json::from_value(serde_json::json!({
+            "fallbacks": [ { "provider": "nope", "model": "x" } ]
+        })).unwrap();
+        let primary = LlmClient::create_provider("ollama", &HashMap::new()).unwrap();
+        assert!(unknown.router(primary, "qwen3:32b".to_string()).is_err());
+    }
+}
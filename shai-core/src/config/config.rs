@@ -4,15 +4,25 @@ use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use reqwest::Url;
 use serde::{Serialize, Deserialize};
-use shai_llm::{LlmClient, ToolCallMethod};
+use shai_llm::{CacheConfig, JsonHooksConfig, LlmClient, ModelRegistry, ReasoningConfig, ReplayConfig, RetryConfig, ToolCallMethod};
 use crate::tools::mcp::McpConfig;
+use super::agent::AgentRoutingConfig;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
     pub provider: String,
     pub env_vars: std::collections::HashMap<String, String>,
     pub model: String,
-    pub tool_method: ToolCallMethod
+    pub tool_method: ToolCallMethod,
+    /// Retry policy for transient LLM errors (rate limits, overload, timeouts)
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// Reasoning effort or thinking budget sent with every request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<ReasoningConfig>,
+    /// Request tweaks (headers, renamed or removed params, merge patch) and think tag extraction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<JsonHooksConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +31,19 @@ pub struct ShaiConfig {
     pub selected_provider: usize,
     #[serde(default)]
     pub mcp_configs: HashMap<String, McpConfig>,
+    /// Names of the mcp_configs attached to the default agent
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_agent_mcp: Vec<String>,
+    /// Record the LLM exchanges to a cassette or replay them, overrides SHAI_REPLAY_CASSETTE
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replay: Option<ReplayConfig>,
+    /// On-disk cache for the runners that opt in (shell fix, status lines)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheConfig>,
+    /// Fallbacks and routes of the default agent and of the shell command fixes,
+    /// the selected provider heads the default route
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing: Option<AgentRoutingConfig>,
 }
 
 impl ShaiConfig {
@@ -36,7 +59,10 @@ impl ShaiConfig {
             provider,
             env_vars,
             model,
-            tool_method: ToolCallMethod::FunctionCall
+            tool_method: ToolCallMethod::FunctionCall,
+            retry: RetryConfig::default(),
+            reasoning: None,
+            hooks: None,
         };
         
         self.providers.push(provider_config);
@@ -69,6 +95,31 @@ impl ShaiConfig {
     }
 
     pub fn config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(Self::config_dir()?.join("auth.config"))
+    }
+
+    /// User model table (context window, capabilities, pricing) overriding the bundled one
+    pub fn models_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(Self::config_dir()?.join("models.json"))
+    }
+
+    /// API keys and CORS origins of `shai serve`
+    pub fn serve_config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(Self::config_dir()?.join("serve.config"))
+    }
+
+    /// Bundled model table with the entries of models.json on top
+    pub fn model_registry() -> ModelRegistry {
+        let Ok(path) = Self::models_path() else {
+            return ModelRegistry::bundled();
+        };
+        ModelRegistry::bundled().with_overrides_file(&path).unwrap_or_else(|e| {
+            eprintln!("\x1b[2m░ ignoring {}: {}\x1b[0m", path.display(), e);
+            ModelRegistry::bundled()
+        })
+    }
+
+    fn config_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
         let config_dir = std::env::var("XDG_CONFIG_HOME")
             .map(PathBuf::from)
             .or_else(|_| {
@@ -80,7 +131,7 @@ impl ShaiConfig {
         let shai_config_dir = config_dir.join("shai");
         std::fs::create_dir_all(&shai_config_dir)?;
         
-        Ok(shai_config_dir.join("auth.config"))
+        Ok(shai_config_dir)
     }
 
     pub fn load() -> Result<ShaiConfig, Box<dyn std::error::Error>> {
@@ -186,9 +237,21 @@ impl ShaiConfig {
     }
 
     pub fn remove_mcp_config(&mut self, name: &str) -> Option<McpConfig> {
+        self.default_agent_mcp.retain(|n| n != name);
         self.mcp_configs.remove(name)
     }
 
+    /// Attach a configured MCP server to the default agent
+    pub fn attach_mcp_to_default_agent(&mut self, name: &str) -> Result<(), String> {
+        if !self.has_mcp_config(name) {
+            return Err(format!("MCP '{}' is not configured", name));
+        }
+        if !self.default_agent_mcp.iter().any(|n| n == name) {
+            self.default_agent_mcp.push(name.to_string());
+        }
+        Ok(())
+    }
+
     pub fn get_mcp_config(&self, name: &str) -> Option<&McpConfig> {
         self.mcp_configs.get(name)
     }
@@ -228,10 +291,17 @@ impl Default for ShaiConfig {
                     (String::from("OVH_BASE_URL"), String::from("https://qwen-3-32b.endpoints.kepler.ai.cloud.ovh.net/api/openai_compat/v1"))
                 ]),
                 model: "Qwen3-32B".to_string(),
-                tool_method: ToolCallMethod::FunctionCall
+                tool_method: ToolCallMethod::FunctionCall,
+                retry: RetryConfig::default(),
+                reasoning: None,
+                hooks: None,
             }],
             selected_provider: 0,
             mcp_configs: HashMap::new(),
+            default_agent_mcp: Vec::new(),
+            replay: None,
+            cache: None,
+            routing: None,
         }
     }
 }
@@ -248,11 +318,30 @@ impl ShaiConfig {
                 &provider_config.provider, 
                 &provider_config.env_vars)
                 .map_err(|e| format!("Failed to create {} client: {}", provider_config.provider, e))?
+                .with_retry(provider_config.retry.clone())
+                .with_reasoning(provider_config.reasoning.clone())
+                .with_json_hooks(provider_config.hooks.clone())
+                .map_err(|e| format!("Failed to configure {} hooks: {}", provider_config.provider, e))?
         } else {
             return Err("No provider configured".into());
         };
-    
+
+        // The model of the primary is resolved first, the router only knows route names
         let model = llm.default_model().await.map_err(|_| "no Model available")?;
+        let llm = match &config.routing {
+            Some(routing) => LlmClient::router(routing.router(llm, model.clone())?),
+            None => llm,
+        };
+        let llm = llm
+            .with_model_registry(Self::model_registry())
+            .with_cache(config.cache.clone());
+
+        let llm = match config.replay.clone().or_else(ReplayConfig::from_env) {
+            Some(replay) => llm.with_replay(replay)
+                .map_err(|e| format!("Failed to set up replay: {}", e))?,
+            None => llm,
+        };
+
         Ok((llm, model))
     }
 }
\ No newline at end of file
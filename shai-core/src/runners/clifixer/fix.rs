@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use openai_dive::v1::resources::chat::{ChatCompletionParametersBuilder, ChatCompletionResponseFormat, JsonSchemaBuilder, ChatMessage, ChatMessageContent};
-use shai_llm::{client::LlmClient, provider::LlmError};
+use shai_llm::{client::LlmClient, provider::LlmError, providers::router::ROUTE_FAST, CacheMode};
 use serde::{Deserialize, Serialize};
 
 use super::prompt::clifix_prompt;
@@ -21,10 +21,12 @@ pub async fn clifix(llm: Arc<LlmClient>, model: String, messages: Vec<ChatMessag
 
     
 
+    // a small request, use the fast route when the router has one
+    let model = llm.route_model(ROUTE_FAST, &model);
     let request = ChatCompletionParametersBuilder::default()
-        .model(model.clone())
+        .model(model)
         .messages(messages)
-        .temperature(0.1)
+        .temperature(0.0)
         .response_format(ChatCompletionResponseFormat::JsonSchema {
             json_schema: JsonSchemaBuilder::default()
                 .name("cli_fix_response")
@@ -53,7 +55,8 @@ pub async fn clifix(llm: Arc<LlmClient>, model: String, messages: Vec<ChatMessag
         }
         */
         
-        let response = llm.chat(request)
+        // the same failing command gets the same fix, at temperature 0 the answer can be cached
+        let response = llm.chat_cached(request, CacheMode::Deterministic)
         .await?;
 
     if let ChatMessage::Assistant { content: Some(ChatMessageContent::Text(content)), .. } = response.choices[0].message.clone() {
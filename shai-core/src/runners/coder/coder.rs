@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use openai_dive::v1::resources::chat::{ChatCompletionParametersBuilder, ChatMessage, ChatMessageContent};
-use shai_llm::client::LlmClient;
+use shai_llm::client::{CachedTokens, LlmClient};
 use async_trait::async_trait;
 use tracing::debug;
 
@@ -84,19 +84,20 @@ impl Brain for CoderBrain {
             let output = usage.completion_tokens.unwrap_or(0);
             (input, output)
         });
+        let cached_tokens = brain_decision.usage.as_ref().map_or(0, |usage| usage.cached_tokens());
 
         // stop here if there's no other tool calls
         let message = brain_decision.choices.into_iter().next().unwrap().message;
         if let ChatMessage::Assistant { reasoning_content, content, tool_calls, .. } = &message {
             if tool_calls.as_ref().map_or(true, |calls| calls.is_empty()) {
                 return Ok(match token_usage {
-                    Some((input_tokens, output_tokens)) => ThinkerDecision::agent_pause_with_tokens(message, input_tokens, output_tokens),
+                    Some((input_tokens, output_tokens)) => ThinkerDecision::agent_pause_with_tokens(message, input_tokens, output_tokens).with_cached_tokens(cached_tokens),
                     None => ThinkerDecision::agent_pause(message),
                 });
             }
         }
         Ok(match token_usage {
-            Some((input_tokens, output_tokens)) => ThinkerDecision::agent_continue_with_tokens(message, input_tokens, output_tokens),
+            Some((input_tokens, output_tokens)) => ThinkerDecision::agent_continue_with_tokens(message, input_tokens, output_tokens).with_cached_tokens(cached_tokens),
             None => ThinkerDecision::agent_continue(message),
         })
     }
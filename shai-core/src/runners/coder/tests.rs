@@ -249,3 +249,54 @@ if __name__ == "__main__":
     // Cleanup is automatic when TempDir is dropped
 }
 
+
+// Recorded cassette of a write tool call followed by the final answer, runs with no network
+#[tokio::test]
+async fn test_coder_integration_replayed() {
+    use shai_llm::providers::replay::{Cassette, Exchange, ReplayProvider};
+
+    init_test_logging();
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let hello_py_path = temp_dir.path().join("hello.py");
+
+    let response = |message: serde_json::Value, finish_reason: &str| serde_json::from_value(serde_json::json!({
+        "id": "replayed", "object": "chat.completion", "created": 0, "model": "recorded-model",
+        "choices": [{ "index": 0, "message": message, "finish_reason": finish_reason }],
+        "usage": { "prompt_tokens": 100, "completion_tokens": 20, "total_tokens": 120 }
+    })).unwrap();
+    let arguments = serde_json::json!({ "path": hello_py_path, "content": "print('Hello, World!')\n" }).to_string();
+    let cassette = Cassette {
+        exchanges: vec![
+            Exchange::Chat {
+                request: serde_json::Value::Null,
+                response: response(serde_json::json!({
+                    "role": "assistant",
+                    "content": "",
+                    "tool_calls": [{ "id": "call_write", "type": "function", "function": { "name": "write", "arguments": arguments } }]
+                }), "tool_calls"),
+            },
+            Exchange::Chat {
+                request: serde_json::Value::Null,
+                response: response(serde_json::json!({ "role": "assistant", "content": "hello.py is ready." }), "stop"),
+            },
+        ],
+        ..Cassette::default()
+    };
+
+    let llm_client = Arc::new(LlmClient::from_provider(Box::new(ReplayProvider::from_cassette(cassette, false))));
+    let fs_log = Arc::new(crate::tools::FsOperationLog::new());
+    let toolbox: Vec<Box<dyn AnyTool>> = vec![Box::new(crate::tools::WriteTool::new(fs_log))];
+    let mut agent = crate::agent::AgentBuilder::with_brain(Box::new(CoderBrain::new(llm_client, "recorded-model".to_string())))
+        .goal("Create hello.py printing Hello, World!")
+        .tools(toolbox)
+        .sudo()
+        .build();
+
+    let result = agent.run().await.expect("replayed agent should complete");
+    assert!(result.success);
+    assert_eq!(std::fs::read_to_string(&hello_py_path).unwrap(), "print('Hello, World!')\n");
+    assert!(matches!(
+        result.trace.last(),
+        Some(ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. }) if text == "hello.py is ready."
+    ));
+}
@@ -1,11 +1,13 @@
 use openai_dive::v1::resources::chat::{ChatCompletionParametersBuilder, ChatMessage, ChatMessageContent};
-use shai_llm::{client::LlmClient, provider::LlmError};
+use shai_llm::{client::LlmClient, provider::LlmError, providers::router::ROUTE_FAST, CacheMode};
 
 use super::prompt::gerund_prompt;
 
 
 
+/// Status line for a user message, uses the `fast` route when the client routes on it
 pub async fn gerund(llm: LlmClient, model: String, message: String) -> Result<ChatMessage, LlmError> {
+    let model = llm.route_model(ROUTE_FAST, &model);
     let message = if message.is_empty() { "the user has sent an empty message".to_string()} else {message}; 
     let mut messages = vec![ChatMessage::User { content: ChatMessageContent::Text(message.clone()), name: None }];
     messages.push(ChatMessage::System { 
@@ -16,12 +18,12 @@ pub async fn gerund(llm: LlmClient, model: String, message: String) -> Result<Ch
     let request = ChatCompletionParametersBuilder::default()
         .model(model.clone())
         .messages(messages)
-        .temperature(0.1)
+        .temperature(0.0)
         .build()
         .map_err(|e| e)?;
         
-        // submit it to our big brain coder
-        let response = llm.chat(request)
+        // the same message gets the same status line, at temperature 0 the answer can be cached
+        let response = llm.chat_cached(request, CacheMode::Deterministic)
         .await?;
 
         Ok(response.choices[0].message.clone())
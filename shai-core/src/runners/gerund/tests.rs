@@ -36,6 +36,7 @@ async fn get_test_model_for_provider(client: &LlmClient) -> String {
         match client.provider_name() {
             "openai" => "gpt-3.5-turbo".to_string(),
             "anthropic" => "claude-3-haiku-20240307".to_string(),
+            "gemini" => "gemini-2.5-flash".to_string(),
             "openrouter" => "openai/gpt-3.5-turbo".to_string(),
             "ovhcloud" => "gpt-3.5-turbo".to_string(),
             "mistral" => "mistral-tiny".to_string(),
@@ -200,7 +201,7 @@ fn test_llm_client_selection() {
     
     // Should be one of our supported providers
     assert!(
-        ["openai", "anthropic", "openrouter", "openai_compatible", "ovhcloud", "mistral", "ollama"]
+        ["openai", "anthropic", "gemini", "openrouter", "openai_compatible", "ovhcloud", "mistral", "ollama"]
             .contains(&provider_name),
         "Should select a valid provider, got: {}",
         provider_name
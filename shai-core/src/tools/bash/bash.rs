@@ -1,5 +1,5 @@
 use super::structs::BashToolParams;
-use crate::tools::{tool, ToolResult};
+use crate::tools::{tool, ToolResult, WorkspaceRoot};
 use serde_json::json;
 use tokio_util::sync::CancellationToken;
 use std::collections::HashMap;
@@ -8,11 +8,20 @@ use std::time::{Duration, Instant};
 use tokio::process::Command;
 use tokio::io::{AsyncReadExt, BufReader};
 
-pub struct BashTool;
+pub struct BashTool {
+    root: WorkspaceRoot,
+}
 
 impl BashTool {
     pub fn new() -> Self {
-        Self
+        Self { root: WorkspaceRoot::default() }
+    }
+
+    /// Run the commands from `root`, a `working_dir` outside of it is refused. The command
+    /// itself is not sandboxed and may still reach files outside of the root
+    pub fn with_root(mut self, root: WorkspaceRoot) -> Self {
+        self.root = root;
+        self
     }
 
     async fn kill_process_group(child: &mut tokio::process::Child) {
@@ -48,9 +57,15 @@ impl BashTool {
         let mut cmd = Command::new("bash");
         cmd.args(["-c", &params.command]);
 
-        // Set working directory if specified
-        if let Some(working_dir) = &params.working_dir {
-            cmd.current_dir(working_dir);
+        // Set working directory if specified, commands start from the workspace root if any
+        match (&params.working_dir, self.root.dir()) {
+            (Some(working_dir), _) => {
+                cmd.current_dir(self.root.resolve(working_dir)?);
+            }
+            (None, Some(root)) => {
+                cmd.current_dir(root);
+            }
+            (None, None) => {}
         }
 
         // Set environment variables
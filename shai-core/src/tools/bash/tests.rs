@@ -1,6 +1,6 @@
 use super::structs::BashToolParams;
 use super::bash::BashTool;
-use crate::tools::{Tool, ToolCapability};
+use crate::tools::{Tool, ToolCapability, WorkspaceRoot};
 use shai_llm::ToolDescription;
 use std::collections::HashMap;
 use serde_json::json;
@@ -42,4 +42,36 @@ async fn test_bash_tool_execution() {
     } else {
         panic!("Expected success result");
     }
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_bash_tool_workspace_root() {
+    let workspace = tempfile::tempdir().unwrap();
+    let root = WorkspaceRoot::default();
+    root.set(workspace.path()).unwrap();
+    let tool = BashTool::new().with_root(root.clone());
+
+    // commands start from the root
+    let params = BashToolParams {
+        command: "pwd".to_string(),
+        timeout: None,
+        working_dir: None,
+        env: HashMap::new(),
+    };
+    let result = Tool::execute(&tool, params, None).await;
+    if let crate::tools::types::ToolResult::Success { output, .. } = result {
+        assert_eq!(output.trim(), root.dir().unwrap().to_string_lossy());
+    } else {
+        panic!("Expected success result");
+    }
+
+    // a working_dir outside of it is refused
+    let params = BashToolParams {
+        command: "pwd".to_string(),
+        timeout: None,
+        working_dir: Some("..".to_string()),
+        env: HashMap::new(),
+    };
+    let result = Tool::execute(&tool, params, None).await;
+    assert!(result.is_error());
+}
@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use shai_llm::ToolDescription;
+use tokio_util::sync::CancellationToken;
+
+use crate::tools::{AnyTool, ToolCapability, ToolResult};
+
+/// Group of the tools defined by the client of the agent
+pub const EXTERNAL_TOOL_GROUP: &str = "external";
+
+/// A tool the client of the agent runs itself (e.g. a function sent by an IDE over the
+/// chat completions API). The agent only knows its schema: calling it parks the tool
+/// call until the agent is cancelled, the client watches the tool calls of the brain
+/// and answers them with a new trace holding the results
+#[derive(Debug, Clone)]
+pub struct ExternalTool {
+    pub name: String,
+    pub description: String,
+    pub parameters_schema: serde_json::Value,
+}
+
+impl ExternalTool {
+    pub fn new(name: String, description: String, parameters_schema: serde_json::Value) -> Self {
+        Self { name, description, parameters_schema }
+    }
+}
+
+impl ToolDescription for ExternalTool {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        self.parameters_schema.clone()
+    }
+
+    fn group(&self) -> Option<&str> {
+        Some(EXTERNAL_TOOL_GROUP)
+    }
+}
+
+#[async_trait]
+impl AnyTool for ExternalTool {
+    // the client asks its own user, never the agent permission system
+    fn capabilities(&self) -> &[ToolCapability] {
+        &[]
+    }
+
+    async fn execute_json(&self, _params: serde_json::Value, cancel_token: Option<CancellationToken>) -> ToolResult {
+        match cancel_token {
+            Some(token) => {
+                token.cancelled().await;
+                ToolResult::error(format!("{} is run by the client, the agent stopped before its result", self.name))
+            }
+            None => ToolResult::error(format!("{} can only be run by the client", self.name)),
+        }
+    }
+
+    async fn execute_preview_json(&self, _params: serde_json::Value) -> Option<ToolResult> {
+        None
+    }
+}
@@ -1,6 +1,6 @@
 use super::structs::EditToolParams;
 use super::super::{FsOperationLog, FsOperationType};
-use crate::tools::{tool, ToolResult};
+use crate::tools::{tool, ToolResult, WorkspaceRoot};
 use similar::{ChangeTag, TextDiff};
 use serde_json::json;
 use std::collections::HashMap;
@@ -12,6 +12,7 @@ use std::sync::Arc;
 pub struct EditTool {
     operation_log: Arc<FsOperationLog>,
     context_lines: usize,
+    root: WorkspaceRoot,
 }
 
 impl EditTool {
@@ -23,9 +24,16 @@ impl EditTool {
         Self {
             operation_log,
             context_lines,
+            root: WorkspaceRoot::default(),
         }
     }
 
+    /// Resolve the paths inside `root`
+    pub fn with_root(mut self, root: WorkspaceRoot) -> Self {
+        self.root = root;
+        self
+    }
+
     pub fn myers_diff(&self, before_content: &str, after_content: &str) -> String {
         let diff = TextDiff::from_lines(before_content, after_content);
 
@@ -228,6 +236,12 @@ impl EditTool {
     }
 
     async fn execute_internal(&self, params: EditToolParams, preview: bool) -> ToolResult {
+        let mut params = params;
+        params.path = match self.root.resolve_str(&params.path) {
+            Ok(path) => path,
+            Err(e) => return ToolResult::error(e),
+        };
+
         // Validate that old_string and new_string are different
         if params.old_string == params.new_string {
             return ToolResult::error("old_string and new_string cannot be the same".to_string());
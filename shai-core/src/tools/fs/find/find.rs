@@ -1,5 +1,5 @@
 use super::structs::{FindToolParams, SearchResult, FindType};
-use crate::tools::{tool, ToolResult};
+use crate::tools::{tool, ToolResult, WorkspaceRoot};
 use serde_json::json;
 use std::collections::HashMap;
 use std::path::Path;
@@ -8,11 +8,19 @@ use walkdir::WalkDir;
 use std::fs;
 use std::io::{BufRead, BufReader};
 
-pub struct FindTool;
+pub struct FindTool {
+    root: WorkspaceRoot,
+}
 
 impl FindTool {
     pub fn new() -> Self {
-        Self
+        Self { root: WorkspaceRoot::default() }
+    }
+
+    /// Resolve the paths inside `root`
+    pub fn with_root(mut self, root: WorkspaceRoot) -> Self {
+        self.root = root;
+        self
     }
 
     fn should_include_file(&self, path: &Path, include_extensions: &Option<String>, exclude_patterns: &Option<String>) -> bool {
@@ -128,8 +136,10 @@ impl FindTool {
     async fn execute(&self, params: FindToolParams) -> ToolResult {
         let mut meta = HashMap::new();
         meta.insert("pattern".to_string(), json!(params.pattern));
-        let default_path = ".".to_string();
-        let search_path = params.path.as_ref().unwrap_or(&default_path);
+        let search_path = match self.root.resolve_str(params.path.as_deref().unwrap_or(".")) {
+            Ok(path) => path,
+            Err(e) => return ToolResult::error(e),
+        };
         meta.insert("path".to_string(), json!(search_path));
         meta.insert("case_sensitive".to_string(), json!(params.case_sensitive));
         meta.insert("max_results".to_string(), json!(params.max_results));
@@ -161,7 +171,7 @@ impl FindTool {
         let mut all_results = Vec::new();
 
         // Walk through directory
-        for entry in WalkDir::new(search_path)
+        for entry in WalkDir::new(&search_path)
             .follow_links(false)
             .into_iter()
             .filter_map(|e| e.ok())
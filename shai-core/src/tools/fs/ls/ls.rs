@@ -1,15 +1,23 @@
 use super::structs::{LsToolParams, FileInfo};
-use crate::tools::{tool, ToolResult};
+use crate::tools::{tool, ToolResult, WorkspaceRoot};
 use serde_json::json;
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
-pub struct LsTool;
+pub struct LsTool {
+    root: WorkspaceRoot,
+}
 
 impl LsTool {
     pub fn new() -> Self {
-        Self
+        Self { root: WorkspaceRoot::default() }
+    }
+
+    /// Resolve the paths inside `root`
+    pub fn with_root(mut self, root: WorkspaceRoot) -> Self {
+        self.root = root;
+        self
     }
 
     fn get_file_info(&self, path: &Path) -> Result<FileInfo, Box<dyn std::error::Error>> {
@@ -214,6 +222,12 @@ impl LsTool {
 - Use `recursive: true` carefully, especially in directories like `node_modules/` which contain thousands of files."#, capabilities = [ToolCapability::Read])]
 impl LsTool {
     async fn execute(&self, params: LsToolParams) -> ToolResult {
+        let mut params = params;
+        params.directory = match self.root.resolve_str(&params.directory) {
+            Ok(path) => path,
+            Err(e) => return ToolResult::error(e),
+        };
+
         let mut files_collected = 0;
         match self.list_directory(&params, 0, &mut files_collected) {
             Ok(files) => {
@@ -1,6 +1,6 @@
 use super::structs::MultiEditToolParams;
 use super::super::{FsOperationLog, FsOperationType, EditTool};
-use crate::tools::{tool, ToolResult};
+use crate::tools::{tool, ToolResult, WorkspaceRoot};
 use serde_json::json;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -11,6 +11,7 @@ use std::path::Path;
 pub struct MultiEditTool {
     operation_log: Arc<FsOperationLog>,
     edit_tool: EditTool,
+    root: WorkspaceRoot,
 }
 
 impl MultiEditTool {
@@ -20,7 +21,13 @@ impl MultiEditTool {
 
     pub fn with_context_lines(operation_log: Arc<FsOperationLog>, context_lines: usize) -> Self {
         let edit_tool = EditTool::with_context_lines(operation_log.clone(), context_lines);
-        Self { operation_log, edit_tool }
+        Self { operation_log, edit_tool, root: WorkspaceRoot::default() }
+    }
+
+    /// Resolve the paths inside `root`
+    pub fn with_root(mut self, root: WorkspaceRoot) -> Self {
+        self.root = root;
+        self
     }
     
     async fn perform_multi_edit(&self, params: &MultiEditToolParams, preview: bool) -> Result<(String, Vec<usize>), String> {
@@ -80,6 +87,12 @@ impl MultiEditTool {
     }
 
     async fn execute_internal(&self, params: MultiEditToolParams, preview: bool) -> ToolResult {
+        let mut params = params;
+        params.file_path = match self.root.resolve_str(&params.file_path) {
+            Ok(path) => path,
+            Err(e) => return ToolResult::error(e),
+        };
+
         // Validate that we have at least one edit operation
         if params.edits.is_empty() {
             return ToolResult::error("At least one edit operation is required".to_string());
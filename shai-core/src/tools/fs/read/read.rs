@@ -1,4 +1,4 @@
-use crate::tools::{ToolResult, tool};
+use crate::tools::{ToolResult, WorkspaceRoot, tool};
 use super::structs::ReadToolParams;
 use super::super::{FsOperationLog, FsOperationType};
 use serde_json::json;
@@ -11,11 +11,18 @@ use std::sync::Arc;
 #[derive(Clone)]
 pub struct ReadTool {
     operation_log: Arc<FsOperationLog>,
+    root: WorkspaceRoot,
 }
 
 impl ReadTool {
     pub fn new(operation_log: Arc<FsOperationLog>) -> Self {
-        Self { operation_log }
+        Self { operation_log, root: WorkspaceRoot::default() }
+    }
+
+    /// Resolve the paths inside `root`
+    pub fn with_root(mut self, root: WorkspaceRoot) -> Self {
+        self.root = root;
+        self
     }
 
     fn read_file_content(&self, params: &ReadToolParams) -> io::Result<String> {
@@ -134,6 +141,12 @@ impl ReadTool {
 - When investigating a task, it is often effective to read multiple potentially relevant files in a single turn to build a complete understanding of the context."#, capabilities = [Read])]
 impl ReadTool {
     async fn execute(&self, params: ReadToolParams) -> ToolResult {
+        let mut params = params;
+        params.path = match self.root.resolve_str(&params.path) {
+            Ok(path) => path,
+            Err(e) => return ToolResult::error(e),
+        };
+
         let path = Path::new(&params.path);
         
         // Check if file exists
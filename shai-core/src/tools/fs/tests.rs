@@ -4,7 +4,7 @@ mod integration_tests {
     use tempfile::tempdir;
     use crate::tools::{
         Tool, FsOperationLog, 
-        LsTool, FindTool, WriteTool, ReadTool, EditTool, MultiEditTool
+        LsTool, FindTool, WriteTool, ReadTool, EditTool, MultiEditTool, WorkspaceRoot
     };
     use crate::tools::fs::{
         ls::structs::LsToolParams,
@@ -332,4 +332,62 @@ mod integration_tests {
         assert!(read_files.contains(&config_path.to_string_lossy().to_string()));
         assert!(read_files.contains(&script_path.to_string_lossy().to_string()));
     }
-}
\ No newline at end of file
+
+    /// Test: tools sharing a workspace root resolve relative paths inside it and refuse
+    /// paths escaping it
+    #[tokio::test]
+    async fn test_workspace_root_confinement() {
+        let workspace = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+
+        let root = WorkspaceRoot::default();
+        root.set(workspace.path()).unwrap();
+        let fs_log = Arc::new(FsOperationLog::new());
+        let write_tool = WriteTool::new(fs_log.clone()).with_root(root.clone());
+        let read_tool = ReadTool::new(fs_log.clone()).with_root(root.clone());
+        let ls_tool = LsTool::new().with_root(root.clone());
+
+        // relative paths land in the workspace
+        let write_result = write_tool.execute(WriteToolParams {
+            path: "src/main.py".to_string(),
+            content: "print('hello')".to_string(),
+        }, None).await;
+        assert!(write_result.is_success());
+        assert!(workspace.path().join("src/main.py").exists());
+
+        let ls_result = ls_tool.execute(LsToolParams {
+            directory: "src".to_string(),
+            recursive: false,
+            show_hidden: false,
+            long_format: false,
+            max_depth: None,
+            max_files: None,
+        }, None).await;
+        assert!(ls_result.is_success());
+
+        // escaping through .. or an absolute path is refused
+        let secret = outside.path().join("secret.txt").to_string_lossy().to_string();
+        for path in ["../secret.txt".to_string(), "src/../../secret.txt".to_string(), secret] {
+            let read_result = read_tool.execute(ReadToolParams {
+                path,
+                line_start: None,
+                line_end: None,
+                show_line_numbers: false,
+            }, None).await;
+            assert!(read_result.is_error());
+        }
+
+        // so is writing through a symlink pointing outside
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(outside.path(), workspace.path().join("link")).unwrap();
+            let write_result = write_tool.execute(WriteToolParams {
+                path: "link/new.txt".to_string(),
+                content: "leak".to_string(),
+            }, None).await;
+            assert!(write_result.is_error());
+            assert!(!outside.path().join("new.txt").exists());
+        }
+    }
+}
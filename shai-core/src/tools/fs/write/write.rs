@@ -1,6 +1,6 @@
 use super::structs::WriteToolParams;
 use super::super::{FsOperationLog, FsOperationType};
-use crate::tools::{ToolResult, tool};
+use crate::tools::{ToolResult, WorkspaceRoot, tool};
 //use crate::tools::highlight::highlight_content;
 use serde_json::json;
 use std::collections::HashMap;
@@ -11,11 +11,18 @@ use std::sync::Arc;
 #[derive(Clone)]
 pub struct WriteTool {
     operation_log: Arc<FsOperationLog>,
+    root: WorkspaceRoot,
 }
 
 impl WriteTool {
     pub fn new(operation_log: Arc<FsOperationLog>) -> Self {
-        Self { operation_log }
+        Self { operation_log, root: WorkspaceRoot::default() }
+    }
+
+    /// Resolve the paths inside `root`
+    pub fn with_root(mut self, root: WorkspaceRoot) -> Self {
+        self.root = root;
+        self
     }
 
     fn perform_write(&self, params: &WriteToolParams) -> Result<String, String> {
@@ -50,6 +57,12 @@ impl WriteTool {
 impl WriteTool {
 
     async fn execute_preview(&self, params: WriteToolParams) -> Option<ToolResult> {
+        let mut params = params;
+        params.path = match self.root.resolve_str(&params.path) {
+            Ok(path) => path,
+            Err(e) => return Some(ToolResult::error(e)),
+        };
+
         //let highlighted_content = highlight_content(&params.content, &params.path);
 
         let mut metadata = HashMap::new();
@@ -65,6 +78,12 @@ impl WriteTool {
     }
 
     async fn execute(&self, params: WriteToolParams) -> ToolResult {
+        let mut params = params;
+        params.path = match self.root.resolve_str(&params.path) {
+            Ok(path) => path,
+            Err(e) => return ToolResult::error(e),
+        };
+
         match self.perform_write(&params) {
             Ok(message) => {
                 // Log the write operation
@@ -1,5 +1,6 @@
 use async_trait::async_trait;
 use shai_llm::ToolDescription;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -20,15 +21,47 @@ pub trait McpClient: Send + Sync {
     async fn execute_tool(&self, tool_call: ToolCall) -> Result<ToolResult, Box<dyn std::error::Error + Send + Sync>>;
 }
 
+/// Separator between the MCP server name and the tool name (`server__tool`)
+pub const MCP_NAMESPACE_SEPARATOR: &str = "__";
+
+/// Longest tool name accepted by the providers
+pub const MAX_TOOL_NAME_LEN: usize = 64;
+
+/// Name under which an MCP tool is exposed to the LLM, see `sanitize_tool_name`
+pub fn namespaced_tool_name(mcp_name: &str, tool_name: &str) -> String {
+    sanitize_tool_name(&format!("{}{}{}", mcp_name, MCP_NAMESPACE_SEPARATOR, tool_name))
+}
+
+/// Make a name match `^[a-zA-Z0-9_-]{1,64}$` as the providers require: other characters become
+/// `_` and a longer name is cut, ending with a hash of the full name so that it stays unique
+pub fn sanitize_tool_name(name: &str) -> String {
+    let mut sanitized: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect();
+    if sanitized.is_empty() {
+        sanitized.push('_');
+    }
+    if sanitized.len() > MAX_TOOL_NAME_LEN {
+        // stable across builds, the name is kept in the saved traces
+        let hash = name.bytes().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+        let suffix = format!("_{:08x}", hash as u32);
+        sanitized.truncate(MAX_TOOL_NAME_LEN - suffix.len());
+        sanitized.push_str(&suffix);
+    }
+    sanitized
+}
+
 pub struct WrappedMcpTool {
     pub desc: McpToolDescription,
     pub client: Arc<Mutex<Box<dyn McpClient>>>,
     pub mcp_name: String,
+    /// exposed name, `server__tool` unless aliased. The server is always called with `desc.name`
+    pub name: String,
 }
 
 impl ToolDescription for WrappedMcpTool {
     fn name(&self) -> String {
-        self.desc.name.clone()
+        self.name.clone()
     }
 
     fn description(&self) -> String {
@@ -72,22 +105,37 @@ impl AnyTool for WrappedMcpTool {
     }
 }
 
+/// Create AnyTool instances from an MCP client, tools are named `server__tool`
+pub async fn get_mcp_tools(client: Box<dyn McpClient>, mcp_name: &str) -> Result<Vec<Box<dyn AnyTool>>, Box<dyn std::error::Error + Send + Sync>> {
+    get_mcp_tools_with_aliases(client, mcp_name, &HashMap::new()).await
+}
+
 /// Create AnyTool instances from an MCP client
-pub async fn get_mcp_tools(mut client: Box<dyn McpClient>, mcp_name: &str) -> Result<Vec<Box<dyn AnyTool>>, Box<dyn std::error::Error + Send + Sync>> {
+/// aliases maps a server tool name to the name exposed to the LLM, other tools are named `server__tool`
+pub async fn get_mcp_tools_with_aliases(mut client: Box<dyn McpClient>, mcp_name: &str, aliases: &HashMap<String, String>) -> Result<Vec<Box<dyn AnyTool>>, Box<dyn std::error::Error + Send + Sync>> {
     // Auto-connect if not already connected
     client.connect().await?;
+    let tools = wrap_mcp_tools(Arc::new(Mutex::new(client)), mcp_name, aliases).await?;
+    Ok(tools.into_iter().map(|tool| Box::new(tool) as Box<dyn AnyTool>).collect())
+}
+
+/// List the tools of an already connected client and wrap them, the client is shared by all its tools
+pub async fn wrap_mcp_tools(client: Arc<Mutex<Box<dyn McpClient>>>, mcp_name: &str, aliases: &HashMap<String, String>) -> Result<Vec<WrappedMcpTool>, Box<dyn std::error::Error + Send + Sync>> {
+    let tool_descriptions = client.lock().await.list_tools().await?;
+    let client_ref = client;
     
-    let tool_descriptions = client.list_tools().await?;
-    let client_ref = Arc::new(Mutex::new(client));
-    
-    let wrapped_tools: Vec<Box<dyn AnyTool>> = tool_descriptions
+    let wrapped_tools: Vec<WrappedMcpTool> = tool_descriptions
         .into_iter()
         .map(|desc| {
-            Box::new(WrappedMcpTool {
+            let name = aliases.get(&desc.name)
+                .map(|alias| sanitize_tool_name(alias))
+                .unwrap_or_else(|| namespaced_tool_name(mcp_name, &desc.name));
+            WrappedMcpTool {
+                name,
                 desc,
                 client: client_ref.clone(),
                 mcp_name: mcp_name.to_string(),
-            }) as Box<dyn AnyTool>
+            }
         })
         .collect();
     
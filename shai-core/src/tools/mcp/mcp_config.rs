@@ -2,6 +2,7 @@ use crate::tools::McpClient;
 use serde::{Serialize, Deserialize};
 
 use super::{StdioClient, HttpClient, SseClient};
+use super::mcp_host::McpHost;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OAuthToken {
@@ -25,6 +26,22 @@ pub enum McpConfig {
     Sse { url: String },
 }
 
+impl McpConfig {
+    /// Build a config from a url or a command line: http(s) urls are streamable http servers
+    /// (sse when the path ends with /sse), anything else is a stdio command
+    pub fn from_target(target: &str, args: Vec<String>) -> Self {
+        if target.starts_with("http://") || target.starts_with("https://") {
+            if target.trim_end_matches('/').ends_with("/sse") {
+                McpConfig::Sse { url: target.to_string() }
+            } else {
+                McpConfig::Http { url: target.to_string(), auth: None }
+            }
+        } else {
+            McpConfig::Stdio { command: target.to_string(), args }
+        }
+    }
+}
+
 impl OAuthToken {
     /// Check if the token is expired or will expire within the next 60 seconds
     pub fn is_expired(&self) -> bool {
@@ -45,16 +62,21 @@ impl OAuthToken {
 
 /// Factory function to create an MCP client from configuration
 pub fn create_mcp_client(config: McpConfig) -> Box<dyn McpClient> {
+    create_mcp_client_with_host(config, "", McpHost::default())
+}
+
+/// Same as create_mcp_client, sampling and elicitation requests of the server are handled by `host`
+pub fn create_mcp_client_with_host(config: McpConfig, mcp_name: &str, host: McpHost) -> Box<dyn McpClient> {
     match config {
         McpConfig::Stdio { command, args } => {
-            Box::new(StdioClient::new(command, args))
+            Box::new(StdioClient::new(command, args).with_host(mcp_name, host))
         }
         McpConfig::Http { url, auth } => {
             let bearer_token = auth.map(|t| t.access_token);
-            Box::new(HttpClient::new_with_auth(url, bearer_token))
+            Box::new(HttpClient::new_with_auth(url, bearer_token).with_host(mcp_name, host))
         }
         McpConfig::Sse { url } => {
-            Box::new(SseClient::new(url))
+            Box::new(SseClient::new(url).with_host(mcp_name, host))
         }
     }
 }
\ No newline at end of file
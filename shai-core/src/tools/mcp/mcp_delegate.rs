@@ -0,0 +1,71 @@
+use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::agent::{Agent, AgentBuilder, AgentResult, ClaimManager};
+use crate::tools::{tool, ToolCapability, ToolResult};
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DelegateTaskToolParams {
+    /// The task to hand over to the agent, with all the context it needs to complete it
+    pub prompt: String,
+}
+
+/// Runs a complete agent on a task and returns its final answer.
+/// Used by the MCP server to expose a whole agent as a single tool.
+/// The agent runs with the permissions of the server: unless it is in sudo mode, the tool calls
+/// of the agent that are not permitted are denied since nobody can answer a permission request
+#[derive(Clone)]
+pub struct DelegateTaskTool {
+    agent_name: Option<String>,
+    permissions: Arc<RwLock<ClaimManager>>,
+}
+
+#[tool(name = "delegate_task", description = r#"Delegates a complete task to an autonomous coding agent running on this machine.
+
+The agent has its own tools (shell, file system, fetch...) and works until the task is done, then returns its final answer.
+
+Usage notes:
+- Describe the task precisely and include every piece of context the agent needs, it does not see your conversation.
+- Use this for multi-step work (investigating a codebase, applying a change, running tests), not for single file reads.
+"#, capabilities = [ToolCapability::Read, ToolCapability::Write, ToolCapability::Network])]
+impl DelegateTaskTool {
+    pub fn new(agent_name: Option<String>, permissions: Arc<RwLock<ClaimManager>>) -> Self {
+        Self { agent_name, permissions }
+    }
+
+    /// Permissions the delegated agent gets, those of the caller at the time of the call
+    pub async fn delegated_permissions(&self) -> ClaimManager {
+        self.permissions.read().await.clone()
+    }
+
+    async fn execute(&self, params: DelegateTaskToolParams) -> ToolResult {
+        let builder = match AgentBuilder::create(self.agent_name.clone()).await {
+            Ok(builder) => builder,
+            Err(e) => return ToolResult::error(format!("Failed to create agent: {}", e)),
+        };
+
+        // the delegated agent is not watched, so its tool calls needing permission are denied
+        let mut agent = builder
+            .with_traces(vec![ChatMessage::User {
+                content: ChatMessageContent::Text(params.prompt),
+                name: None,
+            }])
+            .permissions(self.delegated_permissions().await)
+            .build();
+
+        match agent.run().await {
+            Ok(AgentResult { success: true, message, trace }) => {
+                let answer = match trace.last() {
+                    Some(ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. }) => text.clone(),
+                    _ => message,
+                };
+                ToolResult::success(answer)
+            }
+            Ok(AgentResult { message, .. }) => ToolResult::error(format!("Agent failed: {}", message)),
+            Err(e) => ToolResult::error(format!("Agent failed: {}", e)),
+        }
+    }
+}
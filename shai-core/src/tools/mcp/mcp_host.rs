@@ -0,0 +1,461 @@
+use openai_dive::v1::resources::chat::{ChatCompletionParametersBuilder, ChatMessage, ChatMessageContent};
+use rmcp::{
+    model::{
+        ClientCapabilities, ClientInfo, Content, CreateElicitationRequestParam, CreateElicitationResult,
+        CreateMessageRequestParam, CreateMessageResult, ElicitationAction, Implementation, RawContent,
+        Role, SamplingMessage,
+    },
+    service::{NotificationContext, RequestContext},
+    ClientHandler, ErrorData as McpError, RoleClient,
+};
+use serde_json::{Map, Value};
+use shai_llm::LlmClient;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, PoisonError};
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::agent::{AgentEvent, ClaimManager, InternalAgentEvent, UserRequest, UserResponse};
+use crate::config::agent::McpToolConfig;
+use crate::tools::{AnyTool, ToolSet};
+use super::mcp::{wrap_mcp_tools, McpClient};
+
+/// How long a sampling approval or an elicitation waits for the user
+pub const USER_RESPONSE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How many times a field is asked before the elicitation is declined
+const MAX_ELICITATION_ATTEMPTS: u32 = 3;
+
+/// Channels of the running agent, used to reach the user from an MCP callback
+#[derive(Clone)]
+pub struct McpAgentLink {
+    pub public_event_tx: Option<broadcast::Sender<AgentEvent>>,
+    pub internal_tx: broadcast::Sender<InternalAgentEvent>,
+    pub permissions: Arc<RwLock<ClaimManager>>,
+}
+
+/// A connected MCP server, kept to refresh its tools
+#[derive(Clone)]
+pub struct McpServerEntry {
+    pub client: Arc<Mutex<Box<dyn McpClient>>>,
+    pub config: McpToolConfig,
+}
+
+/// Host side of the MCP connections of an agent.
+/// Sampling requests are answered with the agent LlmClient once the user approved them,
+/// elicitation requests are turned into UserInputRequired events and
+/// tool list changes are pushed to the agent as ToolsChanged events.
+/// The agent attaches itself when it starts, before that every request is declined.
+#[derive(Clone, Default)]
+pub struct McpHost {
+    llm: Option<(Arc<LlmClient>, String)>,
+    agent: Arc<std::sync::RwLock<Option<McpAgentLink>>>,
+    servers: Arc<std::sync::RwLock<HashMap<String, McpServerEntry>>>,
+}
+
+impl fmt::Debug for McpHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("McpHost").field("servers", &self.servers()).finish()
+    }
+}
+
+impl McpHost {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// LLM and model used to answer sampling requests
+    pub fn with_llm(mut self, llm: Arc<LlmClient>, model: String) -> Self {
+        self.llm = Some((llm, model));
+        self
+    }
+
+    pub fn attach(&self, link: McpAgentLink) {
+        *self.agent.write().unwrap_or_else(PoisonError::into_inner) = Some(link);
+    }
+
+    pub fn detach(&self) {
+        *self.agent.write().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    fn link(&self) -> Option<McpAgentLink> {
+        self.agent.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    pub fn register(&self, mcp_name: &str, client: Arc<Mutex<Box<dyn McpClient>>>, config: McpToolConfig) {
+        self.servers.write().unwrap_or_else(PoisonError::into_inner).insert(mcp_name.to_string(), McpServerEntry { client, config });
+    }
+
+    /// Forget a server and close its connection
+    pub async fn unregister(&self, mcp_name: &str) -> bool {
+        let entry = self.servers.write().unwrap_or_else(PoisonError::into_inner).remove(mcp_name);
+        match entry {
+            Some(entry) => {
+                let _ = entry.client.lock().await.disconnect().await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Names of the connected servers
+    pub fn servers(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.servers.read().unwrap_or_else(PoisonError::into_inner).keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// List the tools of a connected server again, reconnecting once if the server went away
+    pub async fn refresh_tools(&self, mcp_name: &str) -> Result<Vec<Box<dyn AnyTool>>, String> {
+        let entry = self.servers.read().unwrap_or_else(PoisonError::into_inner).get(mcp_name).cloned()
+            .ok_or_else(|| format!("MCP '{}' is not connected", mcp_name))?;
+
+        let tools = match wrap_mcp_tools(entry.client.clone(), mcp_name, &entry.config.aliases).await {
+            Ok(tools) => tools,
+            Err(e) => {
+                debug!(target: "mcp::host", "listing tools of '{}' failed ({}), reconnecting", mcp_name, e);
+                {
+                    let mut client = entry.client.lock().await;
+                    let _ = client.disconnect().await;
+                    client.connect().await.map_err(|e| format!("Failed to reconnect to MCP '{}': {}", mcp_name, e))?;
+                }
+                wrap_mcp_tools(entry.client.clone(), mcp_name, &entry.config.aliases).await
+                    .map_err(|e| format!("Failed to get tools from MCP '{}': {}", mcp_name, e))?
+            }
+        };
+
+        entry.config.select_tools(mcp_name, tools)
+    }
+
+    /// Refresh the tools of `mcp_name` in the background and hand them to the agent
+    pub fn tools_changed(&self, mcp_name: &str) {
+        let host = self.clone();
+        let mcp_name = mcp_name.to_string();
+        tokio::spawn(async move {
+            let Some(link) = host.link() else {
+                return;
+            };
+            match host.refresh_tools(&mcp_name).await {
+                Ok(tools) => {
+                    let _ = link.internal_tx.send(InternalAgentEvent::ToolsChanged {
+                        group: mcp_name,
+                        tools: ToolSet::from(tools),
+                    });
+                }
+                Err(e) => error!(target: "mcp::host", "could not refresh tools: {}", e),
+            }
+        });
+    }
+
+    /// Ask something to the user through the agent event stream
+    pub async fn ask_user(&self, request: UserRequest) -> UserResponse {
+        let Some(link) = self.link() else {
+            return UserResponse::NoUser;
+        };
+        let Some(tx) = link.public_event_tx else {
+            return UserResponse::NoUser;
+        };
+
+        // subscribe before emitting so the answer cannot be missed
+        let mut internal_rx = link.internal_tx.subscribe();
+        let req_id = Uuid::new_v4().to_string();
+        if tx.send(AgentEvent::UserInputRequired { request_id: req_id.clone(), request }).is_err() {
+            return UserResponse::NoUser;
+        }
+
+        let wait = async {
+            loop {
+                match internal_rx.recv().await {
+                    Ok(InternalAgentEvent::UserResponseReceived { request_id, response }) if request_id == req_id => {
+                        return response;
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return UserResponse::NoUser,
+                }
+            }
+        };
+
+        tokio::time::timeout(USER_RESPONSE_TIMEOUT, wait)
+            .await
+            .unwrap_or(UserResponse::NoUser)
+    }
+
+    async fn is_sudo(&self) -> bool {
+        match self.link() {
+            Some(link) => link.permissions.read().await.is_sudo(),
+            None => false,
+        }
+    }
+
+    /// Answer a sampling request from the MCP server `mcp_name`
+    pub async fn sample(&self, mcp_name: &str, params: CreateMessageRequestParam) -> Result<CreateMessageResult, McpError> {
+        let Some((llm, model)) = self.llm.clone() else {
+            return Err(McpError::invalid_request("sampling is not available for this agent", None));
+        };
+
+        if !self.is_sudo().await {
+            let prompt = format!(
+                "MCP '{}' wants to query the LLM ({} message(s), up to {} tokens):\n{}",
+                mcp_name,
+                params.messages.len(),
+                params.max_tokens,
+                sampling_preview(&params),
+            );
+            match self.ask_user(UserRequest::Confirmation { prompt }).await {
+                UserResponse::Confirmation(true) => {}
+                _ => return Err(McpError::invalid_request("sampling request was declined by the user", None)),
+            }
+        }
+
+        let mut messages = Vec::new();
+        if let Some(system_prompt) = &params.system_prompt {
+            messages.push(ChatMessage::System {
+                content: ChatMessageContent::Text(system_prompt.clone()),
+                name: None,
+            });
+        }
+        messages.extend(params.messages.iter().map(to_chat_message));
+
+        let mut builder = ChatCompletionParametersBuilder::default();
+        builder
+            .model(model.clone())
+            .messages(messages)
+            .max_completion_tokens(params.max_tokens);
+        if let Some(temperature) = params.temperature {
+            builder.temperature(temperature);
+        }
+        let request = builder
+            .build()
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        let response = llm.chat(request).await
+            .map_err(|e| McpError::internal_error(format!("sampling failed: {}", e), None))?;
+
+        let text = match response.choices.first().map(|c| &c.message) {
+            Some(ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. }) => text.clone(),
+            _ => String::new(),
+        };
+
+        Ok(CreateMessageResult {
+            model,
+            stop_reason: Some(CreateMessageResult::STOP_REASON_END_TURN.to_string()),
+            message: SamplingMessage {
+                role: Role::Assistant,
+                content: Content::text(text),
+            },
+        })
+    }
+
+    /// Answer an elicitation request from the MCP server `mcp_name`, one question per schema property
+    pub async fn elicit(&self, mcp_name: &str, params: CreateElicitationRequestParam) -> Result<CreateElicitationResult, McpError> {
+        let questions = elicitation_questions(mcp_name, &params.message, &params.requested_schema);
+
+        // nothing to fill, it is a plain confirmation
+        if questions.is_empty() {
+            let prompt = format!("[{}] {}", mcp_name, params.message);
+            return Ok(match self.ask_user(UserRequest::Confirmation { prompt }).await {
+                UserResponse::Confirmation(true) => elicitation_result(ElicitationAction::Accept, Some(Value::Object(Map::new()))),
+                UserResponse::Cancel => elicitation_result(ElicitationAction::Cancel, None),
+                _ => elicitation_result(ElicitationAction::Decline, None),
+            });
+        }
+
+        let mut content = Map::new();
+        for question in questions {
+            let mut request = question.request.clone();
+            let mut attempts = 0;
+            loop {
+                match self.ask_user(request).await {
+                    UserResponse::Cancel => return Ok(elicitation_result(ElicitationAction::Cancel, None)),
+                    UserResponse::NoUser => return Ok(elicitation_result(ElicitationAction::Decline, None)),
+                    UserResponse::Text(text) if text.trim().is_empty() && !question.required => break,
+                    response => {
+                        if let Some(value) = question.to_value(response) {
+                            content.insert(question.property.clone(), value);
+                            break;
+                        }
+                    }
+                }
+
+                // never answer Accept with a missing or malformed field
+                attempts += 1;
+                if attempts >= MAX_ELICITATION_ATTEMPTS {
+                    return Ok(elicitation_result(ElicitationAction::Decline, None));
+                }
+                request = question.retry_request();
+            }
+        }
+
+        Ok(elicitation_result(ElicitationAction::Accept, Some(Value::Object(content))))
+    }
+}
+
+fn elicitation_result(action: ElicitationAction, content: Option<Value>) -> CreateElicitationResult {
+    CreateElicitationResult { action, content }
+}
+
+fn to_chat_message(message: &SamplingMessage) -> ChatMessage {
+    let text = match &message.content.raw {
+        RawContent::Text(text) => text.text.clone(),
+        RawContent::Image(image) => format!("[Image: {}, {} bytes]", image.mime_type, image.data.len()),
+        RawContent::Audio(audio) => format!("[Audio: {}, {} bytes]", audio.mime_type, audio.data.len()),
+        RawContent::Resource(_) => "[Resource]".to_string(),
+    };
+    match message.role {
+        Role::User => ChatMessage::User { content: ChatMessageContent::Text(text), name: None },
+        Role::Assistant => ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text(text)),
+            reasoning_content: None,
+            refusal: None,
+            name: None,
+            audio: None,
+            tool_calls: None,
+        },
+    }
+}
+
+/// Last user message of a sampling request, shown in the approval prompt
+fn sampling_preview(params: &CreateMessageRequestParam) -> String {
+    params.messages.iter()
+        .rev()
+        .find_map(|m| match (&m.role, &m.content.raw) {
+            (Role::User, RawContent::Text(text)) => Some(text.text.chars().take(500).collect()),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// A single question derived from a property of an elicitation schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElicitationQuestion {
+    pub property: String,
+    pub kind: String,
+    pub required: bool,
+    pub options: Vec<String>,
+    pub request: UserRequest,
+}
+
+impl ElicitationQuestion {
+    /// Convert the user answer into the JSON value expected by the schema
+    pub fn to_value(&self, response: UserResponse) -> Option<Value> {
+        match response {
+            UserResponse::Confirmation(b) => Some(Value::Bool(b)),
+            UserResponse::Choice(i) => self.options.get(i).cloned().map(Value::String),
+            UserResponse::Text(text) if text.trim().is_empty() => None,
+            UserResponse::Text(text) => match self.kind.as_str() {
+                "integer" => text.trim().parse::<i64>().ok().map(Value::from),
+                "number" => text.trim().parse::<f64>().ok().map(Value::from),
+                _ => Some(Value::String(text)),
+            },
+            UserResponse::Cancel | UserResponse::NoUser => None,
+        }
+    }
+
+    /// Same question again, telling the user what was wrong with the previous answer
+    pub fn retry_request(&self) -> UserRequest {
+        let hint = match self.kind.as_str() {
+            "integer" => "an integer is required",
+            "number" => "a number is required",
+            _ => "an answer is required",
+        };
+        match &self.request {
+            UserRequest::Text { prompt } => UserRequest::Text { prompt: format!("{} ({})", prompt, hint) },
+            UserRequest::Choice { prompt, options } => UserRequest::Choice {
+                prompt: format!("{} ({})", prompt, hint),
+                options: options.clone(),
+            },
+            UserRequest::Confirmation { prompt } => UserRequest::Confirmation { prompt: prompt.clone() },
+        }
+    }
+}
+
+/// Map an elicitation schema to UserRequests: booleans become confirmations,
+/// string enums become choices and everything else is asked as free text
+pub fn elicitation_questions(mcp_name: &str, message: &str, schema: &Map<String, Value>) -> Vec<ElicitationQuestion> {
+    let Some(Value::Object(properties)) = schema.get("properties") else {
+        return vec![];
+    };
+    let required: Vec<&str> = schema.get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    properties.iter().map(|(property, spec)| {
+        let kind = spec.get("type").and_then(Value::as_str).unwrap_or("string").to_string();
+        let label = spec.get("title")
+            .or_else(|| spec.get("description"))
+            .and_then(Value::as_str)
+            .unwrap_or(property);
+        let prompt = format!("[{}] {}\n{}", mcp_name, message, label);
+        let options: Vec<String> = spec.get("enum")
+            .and_then(Value::as_array)
+            .map(|values| values.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+
+        let request = match kind.as_str() {
+            "boolean" => UserRequest::Confirmation { prompt },
+            _ if !options.is_empty() => UserRequest::Choice { prompt, options: options.clone() },
+            _ => UserRequest::Text { prompt },
+        };
+
+        let required = required.contains(&property.as_str());
+        ElicitationQuestion { property: property.clone(), kind, required, options, request }
+    }).collect()
+}
+
+/// rmcp client handler of a single MCP connection, forwards server requests to the McpHost
+#[derive(Clone)]
+pub struct McpClientHandler {
+    mcp_name: String,
+    client_name: String,
+    host: McpHost,
+}
+
+impl McpClientHandler {
+    pub fn new(mcp_name: &str, client_name: &str, host: McpHost) -> Self {
+        Self {
+            mcp_name: mcp_name.to_string(),
+            client_name: client_name.to_string(),
+            host,
+        }
+    }
+}
+
+impl ClientHandler for McpClientHandler {
+    async fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<CreateMessageResult, McpError> {
+        self.host.sample(&self.mcp_name, params).await
+    }
+
+    async fn create_elicitation(
+        &self,
+        request: CreateElicitationRequestParam,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<CreateElicitationResult, McpError> {
+        self.host.elicit(&self.mcp_name, request).await
+    }
+
+    async fn on_tool_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.host.tools_changed(&self.mcp_name);
+    }
+
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo {
+            protocol_version: Default::default(),
+            capabilities: ClientCapabilities::builder()
+                .enable_sampling()
+                .enable_elicitation()
+                .build(),
+            client_info: Implementation {
+                name: self.client_name.clone(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+        }
+    }
+}
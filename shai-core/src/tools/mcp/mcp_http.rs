@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 use rmcp::{
-    model::{CallToolRequestParam, ClientCapabilities, ClientInfo, Implementation, InitializeRequestParam},
+    model::CallToolRequestParam,
     service::{ServiceExt, RunningService},
     transport::StreamableHttpClientTransport,
     RoleClient,
@@ -9,11 +9,13 @@ use std::borrow::Cow;
 
 use crate::tools::{ToolResult, ToolCall};
 use super::mcp::{McpClient, McpToolDescription};
+use super::mcp_host::{McpClientHandler, McpHost};
 
 pub struct HttpClient {
     url: String,
     bearer_token: Option<String>,
-    service: Option<RunningService<RoleClient, InitializeRequestParam>>,
+    handler: McpClientHandler,
+    service: Option<RunningService<RoleClient, McpClientHandler>>,
 }
 
 impl HttpClient {
@@ -25,9 +27,16 @@ impl HttpClient {
         Self {
             url,
             bearer_token,
+            handler: McpClientHandler::new("", "shai-mcp-http-client", McpHost::default()),
             service: None,
         }
     }
+
+    /// Route sampling and elicitation requests of the server to `host`
+    pub fn with_host(mut self, mcp_name: &str, host: McpHost) -> Self {
+        self.handler = McpClientHandler::new(mcp_name, "shai-mcp-http-client", host);
+        self
+    }
 }
 
 #[async_trait]
@@ -60,15 +69,7 @@ impl McpClient for HttpClient {
             StreamableHttpClientTransport::from_uri(self.url.as_str())
         };
 
-        let client_info = ClientInfo {
-            protocol_version: Default::default(),
-            capabilities: ClientCapabilities::default(),
-            client_info: Implementation {
-                name: "shai-mcp-http-client".to_string(),
-                version: "0.1.0".to_string(),
-            },
-        };
-        let service = client_info.serve(transport).await?;
+        let service = self.handler.clone().serve(transport).await?;
         
         // Give the server a moment to process the initialization
         tokio::time::sleep(std::time::Duration::from_millis(100)).await;
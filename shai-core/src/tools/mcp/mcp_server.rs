@@ -0,0 +1,135 @@
+use rmcp::{
+    model::{
+        CallToolRequestParam, CallToolResult, Content, Implementation, ListToolsResult,
+        PaginatedRequestParam, ServerCapabilities, ServerInfo, Tool,
+    },
+    service::{RequestContext, ServiceExt},
+    transport::streamable_http_server::{
+        session::local::LocalSessionManager, StreamableHttpServerConfig, StreamableHttpService,
+    },
+    ErrorData as McpError, RoleServer, ServerHandler,
+};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::agent::ClaimManager;
+use crate::tools::{AnyTool, AnyToolBox, ToolCapability, ToolResult};
+use super::mcp_delegate::DelegateTaskTool;
+
+/// Exposes a toolbox as an MCP server
+/// Every call goes through the ClaimManager: read-only tools always run,
+/// others must be permitted (or the server must run in sudo mode) since there
+/// is nobody on the other side to answer a permission request
+#[derive(Clone)]
+pub struct McpToolServer {
+    tools: AnyToolBox,
+    permissions: Arc<RwLock<ClaimManager>>,
+}
+
+impl McpToolServer {
+    pub fn new(tools: Vec<Box<dyn AnyTool>>, permissions: ClaimManager) -> Self {
+        Self {
+            tools: tools.into_iter().map(Arc::from).collect(),
+            permissions: Arc::new(RwLock::new(permissions)),
+        }
+    }
+
+    /// Also expose a `delegate_task` tool that runs a whole agent (default agent if None),
+    /// with the permissions of this server
+    pub fn with_delegate(mut self, agent_name: Option<String>) -> Self {
+        self.tools.push(Arc::new(DelegateTaskTool::new(agent_name, self.permissions.clone())));
+        self
+    }
+
+    pub fn tools(&self) -> &AnyToolBox {
+        &self.tools
+    }
+
+    /// Serve over stdin/stdout until the client disconnects
+    pub async fn serve_stdio(self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let service = self.serve(rmcp::transport::stdio()).await?;
+        service.waiting().await?;
+        Ok(())
+    }
+
+    /// Serve over streamable HTTP on `address`, the endpoint is mounted at `/mcp`
+    pub async fn serve_http(self, address: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let service = StreamableHttpService::new(
+            move || Ok(self.clone()),
+            LocalSessionManager::default().into(),
+            StreamableHttpServerConfig::default(),
+        );
+
+        let router = axum::Router::new().nest_service("/mcp", service);
+        let listener = tokio::net::TcpListener::bind(address).await?;
+        axum::serve(listener, router).await?;
+        Ok(())
+    }
+
+    async fn is_permitted(&self, tool: &Arc<dyn AnyTool>, parameters: &serde_json::Value) -> bool {
+        tool.capabilities().is_empty()
+            || tool.capabilities() == &[ToolCapability::Read]
+            || self.permissions.read().await.is_permitted(&tool.name(), parameters)
+    }
+}
+
+/// Convert a tool into its MCP description, the input schema is the tool parameters schema
+pub fn to_mcp_tool(tool: &Arc<dyn AnyTool>) -> Tool {
+    let input_schema = match tool.parameters_schema() {
+        serde_json::Value::Object(schema) => schema,
+        _ => serde_json::Map::new(),
+    };
+    Tool::new(tool.name(), tool.description(), Arc::new(input_schema))
+}
+
+/// Convert a tool result into an MCP call result
+pub fn to_call_tool_result(result: ToolResult) -> CallToolResult {
+    match result {
+        ToolResult::Success { output, .. } => CallToolResult::success(vec![Content::text(output)]),
+        ToolResult::Error { error, .. } => CallToolResult::error(vec![Content::text(error)]),
+        ToolResult::Denied => CallToolResult::error(vec![Content::text("tool call was denied: permission required")]),
+    }
+}
+
+impl ServerHandler for McpToolServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            protocol_version: Default::default(),
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            server_info: Implementation {
+                name: "shai".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            instructions: None,
+        }
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult {
+            tools: self.tools.iter().map(to_mcp_tool).collect(),
+            next_cursor: None,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let tool = self.tools.iter()
+            .find(|t| t.name() == request.name)
+            .cloned()
+            .ok_or_else(|| McpError::invalid_params(format!("tool not found: {}", request.name), None))?;
+
+        let parameters = serde_json::Value::Object(request.arguments.unwrap_or_default());
+        if !self.is_permitted(&tool, &parameters).await {
+            return Ok(to_call_tool_result(ToolResult::denied()));
+        }
+
+        Ok(to_call_tool_result(tool.execute_json(parameters, None).await))
+    }
+}
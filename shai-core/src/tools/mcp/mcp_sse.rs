@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 use rmcp::{
-    model::{CallToolRequestParam, ClientCapabilities, ClientInfo, Implementation, InitializeRequestParam},
+    model::CallToolRequestParam,
     service::{ServiceExt, RunningService},
     transport::SseClientTransport,
     RoleClient,
@@ -9,19 +9,28 @@ use std::borrow::Cow;
 
 use crate::tools::{ToolResult, ToolCall};
 use super::mcp::{McpClient, McpToolDescription};
+use super::mcp_host::{McpClientHandler, McpHost};
 
 pub struct SseClient {
     url: String,
-    service: Option<RunningService<RoleClient, InitializeRequestParam>>,
+    handler: McpClientHandler,
+    service: Option<RunningService<RoleClient, McpClientHandler>>,
 }
 
 impl SseClient {
     pub fn new(url: String) -> Self {
         Self {
             url,
+            handler: McpClientHandler::new("", "shai-mcp-sse-client", McpHost::default()),
             service: None,
         }
     }
+
+    /// Route sampling and elicitation requests of the server to `host`
+    pub fn with_host(mut self, mcp_name: &str, host: McpHost) -> Self {
+        self.handler = McpClientHandler::new(mcp_name, "shai-mcp-sse-client", host);
+        self
+    }
 }
 
 #[async_trait]
@@ -33,15 +42,7 @@ impl McpClient for SseClient {
         }
         
         let transport = SseClientTransport::start(self.url.as_str()).await?;
-        let client_info = ClientInfo {
-            protocol_version: Default::default(),
-            capabilities: ClientCapabilities::default(),
-            client_info: Implementation {
-                name: "shai-mcp-sse-client".to_string(),
-                version: "0.1.0".to_string(),
-            },
-        };
-        let service = client_info.serve(transport).await?;
+        let service = self.handler.clone().serve(transport).await?;
         self.service = Some(service);
         Ok(())
     }
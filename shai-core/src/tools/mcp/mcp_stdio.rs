@@ -10,11 +10,13 @@ use tokio::process::Command;
 
 use crate::tools::{ToolResult, ToolCall};
 use super::mcp::{McpClient, McpToolDescription};
+use super::mcp_host::{McpClientHandler, McpHost};
 
 pub struct StdioClient {
     command: String,
     args: Vec<String>,
-    service: Option<RunningService<RoleClient, ()>>,
+    handler: McpClientHandler,
+    service: Option<RunningService<RoleClient, McpClientHandler>>,
 }
 
 impl StdioClient {
@@ -22,9 +24,16 @@ impl StdioClient {
         Self {
             command,
             args,
+            handler: McpClientHandler::new("", "shai-mcp-stdio-client", McpHost::default()),
             service: None,
         }
     }
+
+    /// Route sampling and elicitation requests of the server to `host`
+    pub fn with_host(mut self, mcp_name: &str, host: McpHost) -> Self {
+        self.handler = McpClientHandler::new(mcp_name, "shai-mcp-stdio-client", host);
+        self
+    }
 }
 
 #[async_trait]
@@ -40,7 +49,7 @@ impl McpClient for StdioClient {
             cmd.arg(arg);
         }
         let transport = TokioChildProcess::new(cmd)?;
-        let service = ().serve(transport).await?;
+        let service = self.handler.clone().serve(transport).await?;
         self.service = Some(service);
         Ok(())
     }
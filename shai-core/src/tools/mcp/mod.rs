@@ -4,12 +4,18 @@ pub mod mcp_http;
 pub mod mcp_sse;
 pub mod mcp_config;
 pub mod mcp_oauth;
+pub mod mcp_server;
+pub mod mcp_delegate;
+pub mod mcp_host;
 
 #[cfg(test)]
 mod tests;
 
-pub use mcp::{McpClient, McpToolDescription, get_mcp_tools};
-pub use mcp_config::{McpConfig, OAuthToken, create_mcp_client};
+pub use mcp::{McpClient, McpToolDescription, get_mcp_tools, get_mcp_tools_with_aliases, wrap_mcp_tools, namespaced_tool_name};
+pub use mcp_config::{McpConfig, OAuthToken, create_mcp_client, create_mcp_client_with_host};
 pub use mcp_stdio::StdioClient;
 pub use mcp_http::HttpClient;
-pub use mcp_sse::SseClient;
\ No newline at end of file
+pub use mcp_sse::SseClient;
+pub use mcp_server::McpToolServer;
+pub use mcp_delegate::DelegateTaskTool;
+pub use mcp_host::{McpHost, McpAgentLink, McpClientHandler, USER_RESPONSE_TIMEOUT};
\ No newline at end of file
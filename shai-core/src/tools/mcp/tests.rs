@@ -310,4 +310,160 @@ mod tests {
             Err(e) => println!("❌ Failed to disconnect: {}", e),
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_mcp_server_tool_conversion() {
+        use crate::tools::mcp::mcp_server::{to_call_tool_result, to_mcp_tool};
+        use crate::tools::{AnyTool, LsTool, ToolResult};
+        use std::sync::Arc;
+
+        let tool: Arc<dyn AnyTool> = Arc::new(LsTool::new());
+        let mcp_tool = to_mcp_tool(&tool);
+        assert_eq!(mcp_tool.name, "ls");
+        assert_eq!(serde_json::Value::Object((*mcp_tool.input_schema).clone()), tool.parameters_schema());
+
+        let result = to_call_tool_result(ToolResult::success("ok".to_string()));
+        assert_eq!(result.is_error, Some(false));
+        let result = to_call_tool_result(ToolResult::denied());
+        assert_eq!(result.is_error, Some(true));
+    }
+
+    #[test]
+    fn test_elicitation_schema_to_user_requests() {
+        use crate::agent::{UserRequest, UserResponse};
+        use crate::tools::mcp::mcp_host::elicitation_questions;
+
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "confirm": { "type": "boolean", "title": "Proceed?" },
+                "flavor": { "type": "string", "enum": ["small", "large"] },
+                "count": { "type": "integer", "description": "How many" }
+            }
+        });
+        let questions = elicitation_questions("ovh", "Create instance", schema.as_object().unwrap());
+        assert_eq!(questions.len(), 3);
+
+        let confirm = questions.iter().find(|q| q.property == "confirm").unwrap();
+        assert!(matches!(confirm.request, UserRequest::Confirmation { .. }));
+        assert_eq!(confirm.to_value(UserResponse::Confirmation(true)), Some(json!(true)));
+
+        let flavor = questions.iter().find(|q| q.property == "flavor").unwrap();
+        assert!(matches!(&flavor.request, UserRequest::Choice { options, .. } if options.len() == 2));
+        assert_eq!(flavor.to_value(UserResponse::Choice(1)), Some(json!("large")));
+
+        let count = questions.iter().find(|q| q.property == "count").unwrap();
+        assert!(matches!(count.request, UserRequest::Text { .. }));
+        assert_eq!(count.to_value(UserResponse::Text(" 3 ".to_string())), Some(json!(3)));
+        assert_eq!(count.to_value(UserResponse::Text("three".to_string())), None);
+        assert_eq!(count.to_value(UserResponse::Text(" ".to_string())), None);
+        assert!(!count.required);
+
+        assert!(elicitation_questions("ovh", "ok?", json!({"type": "object"}).as_object().unwrap()).is_empty());
+    }
+
+    /// Answers every question of the host with the next text of `answers`
+    async fn elicit_with_answers(schema: serde_json::Value, answers: Vec<&str>) -> rmcp::model::CreateElicitationResult {
+        use crate::agent::{AgentEvent, ClaimManager, InternalAgentEvent, UserResponse};
+        use crate::tools::mcp::mcp_host::{McpAgentLink, McpHost};
+        use rmcp::model::CreateElicitationRequestParam;
+        use std::sync::Arc;
+        use tokio::sync::{broadcast, RwLock};
+
+        let (public_tx, mut public_rx) = broadcast::channel(16);
+        let (internal_tx, _) = broadcast::channel(16);
+        let host = McpHost::new();
+        host.attach(McpAgentLink {
+            public_event_tx: Some(public_tx),
+            internal_tx: internal_tx.clone(),
+            permissions: Arc::new(RwLock::new(ClaimManager::new())),
+        });
+
+        let answers: Vec<String> = answers.into_iter().map(str::to_string).collect();
+        tokio::spawn(async move {
+            for answer in answers {
+                if let Ok(AgentEvent::UserInputRequired { request_id, .. }) = public_rx.recv().await {
+                    let _ = internal_tx.send(InternalAgentEvent::UserResponseReceived {
+                        request_id,
+                        response: UserResponse::Text(answer),
+                    });
+                }
+            }
+        });
+
+        host.elicit("ovh", CreateElicitationRequestParam {
+            message: "Create instance".to_string(),
+            requested_schema: schema.as_object().unwrap().clone(),
+        }).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_elicitation_asks_again_then_declines() {
+        use rmcp::model::ElicitationAction;
+
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "count": { "type": "integer" },
+                "note": { "type": "string" }
+            },
+            "required": ["count"]
+        });
+
+        let result = elicit_with_answers(schema.clone(), vec!["three", "3", ""]).await;
+        assert!(matches!(result.action, ElicitationAction::Accept));
+        assert_eq!(result.content, Some(json!({ "count": 3 })));
+
+        let result = elicit_with_answers(schema, vec!["three", "", "lots"]).await;
+        assert!(matches!(result.action, ElicitationAction::Decline));
+        assert_eq!(result.content, None);
+    }
+
+    #[tokio::test]
+    async fn test_delegate_task_keeps_caller_permissions() {
+        use crate::agent::claims::{MatchStrategy, Permission};
+        use crate::agent::ClaimManager;
+        use crate::tools::mcp::DelegateTaskTool;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let permissions = Arc::new(RwLock::new(ClaimManager::new()));
+        let tool = DelegateTaskTool::new(None, permissions.clone());
+
+        // a supervised server gives a supervised agent
+        let delegated = tool.delegated_permissions().await;
+        assert!(!delegated.is_sudo());
+        assert!(!delegated.is_permitted("bash", &json!({"command": "ls"})));
+
+        // the permissions granted to the server reach the agent
+        permissions.write().await.add_permission(Permission::new(
+            "bash".to_string(),
+            MatchStrategy::Exact,
+            json!({"command": "ls"}),
+            true,
+        ));
+        let delegated = tool.delegated_permissions().await;
+        assert!(delegated.is_permitted("bash", &json!({"command": "ls"})));
+        assert!(!delegated.is_permitted("bash", &json!({"command": "rm -rf /"})));
+
+        permissions.write().await.sudo();
+        assert!(tool.delegated_permissions().await.is_sudo());
+    }
+
+    #[test]
+    fn test_namespaced_tool_name_is_sanitized() {
+        use crate::tools::mcp::mcp::{namespaced_tool_name, MAX_TOOL_NAME_LEN};
+
+        assert_eq!(namespaced_tool_name("github", "search"), "github__search");
+        assert_eq!(namespaced_tool_name("my server", "get.list"), "my_server__get_list");
+        assert_eq!(namespaced_tool_name("docs", "lire/écrire"), "docs__lire__crire");
+
+        let long = namespaced_tool_name("server", &"a".repeat(100));
+        assert_eq!(long.len(), MAX_TOOL_NAME_LEN);
+        assert!(long.starts_with("server__aaaa"));
+        assert!(long.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'));
+        // cut names stay apart and stable
+        assert_ne!(long, namespaced_tool_name("server", &"a".repeat(101)));
+        assert_eq!(long, namespaced_tool_name("server", &"a".repeat(100)));
+    }
+}
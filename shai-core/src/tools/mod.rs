@@ -5,16 +5,20 @@ pub mod fs;
 pub mod fetch;
 pub mod bash;
 pub mod mcp;
+pub mod external;
+pub mod workspace;
 
 #[cfg(test)]
 mod tests_llm;
 
 pub use shai_macros::tool;
-pub use types::{Tool, ToolCall, ToolResult, ToolError, ToolCapability, AnyTool, AnyToolBox, ToolEmptyParams};
+pub use types::{Tool, ToolCall, ToolResult, ToolError, ToolCapability, AnyTool, AnyToolBox, ToolSet, ToolEmptyParams};
 
 // Re-export all tools
 pub use bash::BashTool;
 pub use fetch::FetchTool;
 pub use fs::{EditTool, FindTool, LsTool, MultiEditTool, ReadTool, WriteTool, FsOperationLog, FsOperationType, FsOperation, FsOperationSummary};
 pub use todo::{TodoReadTool, TodoWriteTool, TodoStorage, TodoItem, TodoStatus, TodoWriteParams, TodoItemInput};
-pub use mcp::{McpClient, McpToolDescription, McpConfig, create_mcp_client, get_mcp_tools, StdioClient, HttpClient, SseClient};
+pub use external::{ExternalTool, EXTERNAL_TOOL_GROUP};
+pub use workspace::WorkspaceRoot;
+pub use mcp::{McpClient, McpToolDescription, McpConfig, create_mcp_client, create_mcp_client_with_host, get_mcp_tools, get_mcp_tools_with_aliases, StdioClient, HttpClient, SseClient, McpToolServer, McpHost};
@@ -30,7 +30,7 @@ mod llm_integration_tests {
         init_test_logging();
         
         let llm_client = LlmClient::first_from_env()
-            .ok_or("No LLM provider available")?;
+            .map_err(|e| e.to_string())?;
         let model = llm_client.default_model().await.expect("default model");
         
         println!("Testing tool '{}' with model '{}' from provider '{}'", 
@@ -226,3 +226,26 @@ impl ContainsAnyTool for AnyToolBox {
         .cloned()
     }
 }
+
+/// Tools handed over to a running agent (controller requests, MCP refresh)
+/// Debug only prints the tool names
+#[derive(Clone, Default)]
+pub struct ToolSet(pub AnyToolBox);
+
+impl ToolSet {
+    pub fn names(&self) -> Vec<String> {
+        self.0.iter().map(|t| t.name()).collect()
+    }
+}
+
+impl From<Vec<Box<dyn AnyTool>>> for ToolSet {
+    fn from(tools: Vec<Box<dyn AnyTool>>) -> Self {
+        Self(tools.into_iter().map(Arc::from).collect())
+    }
+}
+
+impl fmt::Debug for ToolSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ToolSet").field(&self.names()).finish()
+    }
+}
@@ -0,0 +1,77 @@
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Directory the builtin tools are confined to. The tools of an agent share the same handle,
+/// so the root can be set once they are built (e.g. when the HTTP server gives a session its
+/// own workspace). Without a root, paths are used as given, relative to the process cwd
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceRoot {
+    dir: Arc<RwLock<Option<PathBuf>>>,
+}
+
+impl WorkspaceRoot {
+    /// Confine the tools to `dir`, which must exist
+    pub fn set(&self, dir: &Path) -> io::Result<()> {
+        let dir = dir.canonicalize()?;
+        *self.dir.write().unwrap() = Some(dir);
+        Ok(())
+    }
+
+    pub fn dir(&self) -> Option<PathBuf> {
+        self.dir.read().unwrap().clone()
+    }
+
+    /// Path a tool should use for `path`: relative paths are taken from the root, and paths
+    /// leaving it (through `..`, an absolute path or a symlink) are refused
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, String> {
+        let Some(root) = self.dir() else {
+            return Ok(PathBuf::from(path));
+        };
+
+        let resolved = normalize(&root.join(path));
+        if real_path(&resolved).starts_with(&root) {
+            Ok(resolved)
+        } else {
+            Err(format!("Path {} is outside of the workspace {}", path, root.display()))
+        }
+    }
+
+    /// `resolve` for the string paths of the tool parameters
+    pub fn resolve_str(&self, path: &str) -> Result<String, String> {
+        self.resolve(path).map(|path| path.to_string_lossy().to_string())
+    }
+}
+
+/// Drop the `.` and `..` components without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Follow the symlinks of the longest existing ancestor of `path`, the rest may not exist yet
+fn real_path(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut missing = vec![];
+    loop {
+        if let Ok(real) = existing.canonicalize() {
+            return missing.iter().rev().fold(real, |real, name| real.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use shai_core::config::config::ShaiConfig;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::ServerState;
+
+/// How long the provider has to list its models before the server is reported unready
+const READINESS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a readiness result is reused, the probes come every few seconds
+const READINESS_TTL: Duration = Duration::from_secs(30);
+
+/// Last result of the readiness check, shared by the probes
+#[derive(Clone, Default)]
+pub struct Readiness {
+    last: Arc<Mutex<Option<(Instant, Result<(), String>)>>>,
+}
+
+impl Readiness {
+    /// The last result while recent, a new check otherwise. Concurrent probes wait for the
+    /// running check instead of starting their own
+    pub async fn check(&self) -> Result<(), String> {
+        let mut last = self.last.lock().await;
+        if let Some((checked_at, result)) = last.as_ref() {
+            if checked_at.elapsed() < READINESS_TTL {
+                return result.clone();
+            }
+        }
+
+        let result = tokio::time::timeout(READINESS_TIMEOUT, check_provider())
+            .await
+            .unwrap_or_else(|_| Err(format!("the provider did not list its models within {}s", READINESS_TIMEOUT.as_secs())));
+        *last = Some((Instant::now(), result.clone()));
+        result
+    }
+}
+
+/// GET /healthz - The server is up
+pub async fn handle_healthz() -> Response {
+    Json(serde_json::json!({ "status": "ok" })).into_response()
+}
+
+/// GET /readyz - The configured LLM provider answers, checked by listing its models
+/// The probe needs no API key, so the reason of a failure only goes to the logs
+pub async fn handle_readyz(State(state): State<ServerState>) -> Response {
+    match state.readiness.check().await {
+        Ok(()) => Json(serde_json::json!({ "status": "ready" })).into_response(),
+        Err(error) => {
+            warn!("Readiness check failed: {}", error);
+            (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({ "status": "unavailable" }))).into_response()
+        }
+    }
+}
+
+async fn check_provider() -> Result<(), String> {
+    let (llm, _) = ShaiConfig::get_llm().await.map_err(|e| e.to_string())?;
+    llm.models().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// GET /metrics - Prometheus metrics of the server
+pub async fn handle_metrics(State(state): State<ServerState>) -> Response {
+    let active_sessions = state.session_manager.session_count().await;
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(active_sessions),
+    ).into_response()
+}
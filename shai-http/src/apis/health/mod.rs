@@ -0,0 +1,3 @@
+pub mod handler;
+
+pub use handler::{handle_healthz, handle_metrics, handle_readyz, Readiness};
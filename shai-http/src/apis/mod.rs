@@ -1,2 +1,4 @@
 pub mod simple;
-pub mod openai;
\ No newline at end of file
+pub mod openai;
+pub mod sessions;
+pub mod health;
@@ -1,20 +1,31 @@
+use std::collections::HashSet;
+
 use async_trait::async_trait;
 use openai_dive::v1::resources::chat::{
     ChatCompletionChunkResponse, ChatCompletionChunkChoice, DeltaChatMessage,
-    ChatMessageContent, ChatMessage,
+    ChatMessageContent, ChatMessage, ToolCall,
 };
 use openai_dive::v1::resources::shared::FinishReason;
+use serde_json::json;
 use shai_core::agent::AgentEvent;
 use uuid::Uuid;
 
+use super::tools::{openai_usage, ClientToolCalls};
+use crate::session::TokenTotals;
 use crate::streaming::EventFormatter;
 
 /// Formatter for OpenAI Chat Completion API (streaming)
-/// Tool calls are converted to "thinking" reasoning_content deltas
+/// Tool calls are converted to "thinking" reasoning_content deltas, except the calls to
+/// the client tools which end the response with finish_reason tool_calls once the agent
+/// tools of the same step completed. The last chunk carries the usage of the request.
 pub struct ChatCompletionFormatter {
     pub model: String,
     pub created: u32,
     accumulated_text: String,
+    external_tools: HashSet<String>,
+    client_tool_calls: ClientToolCalls,
+    usage: TokenTotals,
+    done: bool,
 }
 
 impl ChatCompletionFormatter {
@@ -28,7 +39,44 @@ impl ChatCompletionFormatter {
             model,
             created,
             accumulated_text: String::new(),
+            external_tools: HashSet::new(),
+            client_tool_calls: ClientToolCalls::default(),
+            usage: TokenTotals::default(),
+            done: false,
+        }
+    }
+
+    /// Names of the tools sent by the client
+    pub fn with_external_tools(mut self, names: HashSet<String>) -> Self {
+        self.external_tools = names;
+        self
+    }
+
+    /// End the response with the client tool calls once they are ready
+    fn client_tool_calls_chunk(&mut self, reasoning: Option<String>) -> Option<ChatCompletionChunkResponse> {
+        let calls = self.client_tool_calls.ready()?.clone();
+        self.done = true;
+        self.tool_calls_chunk(&calls, reasoning)
+    }
+
+    fn tool_calls_chunk(&self, calls: &[ToolCall], reasoning: Option<String>) -> Option<ChatCompletionChunkResponse> {
+        let mut delta = json!({
+            "role": "assistant",
+            "tool_calls": calls.iter().enumerate().map(|(index, call)| json!({
+                "index": index,
+                "id": call.id,
+                "type": "function",
+                "function": { "name": call.function.name, "arguments": call.function.arguments }
+            })).collect::<Vec<_>>()
+        });
+        if !self.accumulated_text.is_empty() {
+            delta["content"] = json!(self.accumulated_text);
         }
+        if let Some(reasoning) = reasoning {
+            delta["reasoning_content"] = json!(reasoning);
+        }
+        let delta: DeltaChatMessage = serde_json::from_value(delta).ok()?;
+        Some(self.create_chunk(delta, Some(FinishReason::ToolCalls)))
     }
 
     fn create_chunk(&self, delta: DeltaChatMessage, finish_reason: Option<FinishReason>) -> ChatCompletionChunkResponse {
@@ -40,10 +88,10 @@ impl ChatCompletionFormatter {
             choices: vec![ChatCompletionChunkChoice {
                 index: Some(0),
                 delta,
-                finish_reason,
+                finish_reason: finish_reason.clone(),
                 logprobs: None,
             }],
-            usage: None,
+            usage: finish_reason.is_some().then(|| openai_usage(&self.usage)),
             system_fingerprint: None,
         }
     }
@@ -58,20 +106,26 @@ impl EventFormatter for ChatCompletionFormatter {
         event: AgentEvent,
         _session_id: &str,
     ) -> Option<Self::Output> {
+        self.usage.add(&event);
         match event {
             // Capture assistant messages from brain results
             AgentEvent::BrainResult { thought, .. } => {
-                if let Ok(msg) = thought {
-                    if let ChatMessage::Assistant {
-                        content: Some(ChatMessageContent::Text(text)),
-                        ..
-                    } = msg
-                    {
-                        // Accumulate the text for final response
-                        self.accumulated_text = text;
-                    }
+                let Ok(msg) = thought else {
+                    return None;
+                };
+                if let ChatMessage::Assistant {
+                    content: Some(ChatMessageContent::Text(text)),
+                    ..
+                } = &msg
+                {
+                    // Accumulate the text for final response
+                    self.accumulated_text = text.clone();
                 }
-                None
+
+                // the client runs these, the response ends once the agent tools of the
+                // step ran and the agent is stopped
+                self.client_tool_calls.on_brain_result(&msg, &self.external_tools);
+                self.client_tool_calls_chunk(None)
             }
 
             // Tool call started - stream as thinking delta
@@ -88,7 +142,8 @@ impl EventFormatter for ChatCompletionFormatter {
                 Some(self.create_chunk(delta, None))
             }
 
-            // Tool call completed - stream result as thinking delta
+            // Tool call completed - stream result as thinking delta, or end with the client
+            // tool calls waiting for it
             AgentEvent::ToolCallCompleted { call, result, .. } => {
                 use shai_core::tools::ToolResult;
 
@@ -105,6 +160,11 @@ impl EventFormatter for ChatCompletionFormatter {
                     }
                 };
 
+                self.client_tool_calls.on_tool_completed(&call.tool_call_id);
+                if self.client_tool_calls.ready().is_some() {
+                    return self.client_tool_calls_chunk(Some(thinking_text));
+                }
+
                 let delta = DeltaChatMessage::Assistant {
                     content: None,
                     reasoning_content: Some(thinking_text),
@@ -154,4 +214,8 @@ impl EventFormatter for ChatCompletionFormatter {
             _ => None,
         }
     }
+
+    fn is_done(&self) -> bool {
+        self.done
+    }
 }
@@ -5,40 +5,45 @@ use axum::{
 use futures::StreamExt;
 use openai_dive::v1::resources::chat::{
     ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChoice,
-    ChatMessage, ChatMessageContent,
+    ChatMessage, ChatMessageContent, ChatMessageContentPart,
 };
-use openai_dive::v1::resources::shared::{Usage, FinishReason};
+use openai_dive::v1::resources::shared::FinishReason;
 use shai_core::agent::AgentEvent;
 use tokio_stream::wrappers::BroadcastStream;
 use tracing::info;
 use uuid::Uuid;
 
 use super::formatter::ChatCompletionFormatter;
-use crate::{ApiJson, ServerState, ErrorResponse, session_to_sse_stream};
+use super::tools::{external_tools, openai_usage, ClientToolCalls};
+use crate::session::TokenTotals;
+use crate::{ApiJson, Caller, ServerState, ErrorResponse, session_to_sse_stream};
 
 /// Handle OpenAI chat completion - supports both streaming and non-streaming
 pub async fn handle_chat_completion(
     State(state): State<ServerState>,
+    caller: Caller,
     ApiJson(payload): ApiJson<ChatCompletionParameters>,
 ) -> Result<Response, ErrorResponse> {
     let request_id = Uuid::new_v4();
     let session_id = Uuid::new_v4().to_string();
 
     let is_streaming = payload.stream.unwrap_or(false);
-    info!("[{}] POST /v1/chat/completions model={} stream={} (ephemeral)",
-        request_id, payload.model, is_streaming);
+    info!("[{}] POST /v1/chat/completions model={} stream={} key={} (ephemeral)",
+        request_id, payload.model, is_streaming, caller.name);
+    caller.check_agent(&payload.model)?;
 
     // Check if streaming is requested
     if is_streaming {
-        handle_chat_completion_stream(state, payload, request_id, session_id).await
+        handle_chat_completion_stream(state, caller, payload, request_id, session_id).await
     } else {
-        handle_chat_completion_non_stream(state, payload, request_id, session_id).await
+        handle_chat_completion_non_stream(state, caller, payload, request_id, session_id).await
     }
 }
 
 /// Handle streaming chat completion
 async fn handle_chat_completion_stream(
     state: ServerState,
+    caller: Caller,
     payload: ChatCompletionParameters,
     request_id: Uuid,
     session_id: String,
@@ -48,9 +53,16 @@ async fn handle_chat_completion_stream(
 
     // Create ephemeral session
     let agent_session = state.session_manager
-        .create_new_session(&request_id.to_string(), &session_id, Some(model.clone()), true)
+        .create_new_session(&request_id.to_string(), &session_id, Some(model.clone()), true, caller.namespace(), caller.sudo(&model))
         .await
-        .map_err(|e| ErrorResponse::internal_error(format!("Failed to create session: {}", e)))?;
+        .map_err(|e| ErrorResponse::from_session_error(e, |e| ErrorResponse::internal_error(format!("Failed to create session: {}", e))))?;
+
+    // Client tools, their calls are handed back to the client
+    let tools = external_tools(&payload);
+    let tool_names = tools.iter().map(|tool| tool.name.clone()).collect();
+    agent_session.add_external_tools(tools)
+        .await
+        .map_err(|e| ErrorResponse::invalid_request(format!("Invalid tools: {}", e)))?;
 
     // Create request session
     let request_session = agent_session
@@ -59,7 +71,7 @@ async fn handle_chat_completion_stream(
         .map_err(|e| ErrorResponse::internal_error(format!("Failed to handle request: {}", e)))?;
 
     // Create the formatter for OpenAI Chat Completion API
-    let formatter = ChatCompletionFormatter::new(model);
+    let formatter = ChatCompletionFormatter::new(model).with_external_tools(tool_names);
 
     // Create SSE stream
     let stream = session_to_sse_stream(request_session, formatter, session_id, true);
@@ -71,6 +83,7 @@ async fn handle_chat_completion_stream(
 /// Directly processes events and returns a single complete response
 async fn handle_chat_completion_non_stream(
     state: ServerState,
+    caller: Caller,
     payload: ChatCompletionParameters,
     request_id: Uuid,
     session_id: String,
@@ -79,9 +92,16 @@ async fn handle_chat_completion_non_stream(
 
     // Create ephemeral session
     let agent_session = state.session_manager
-        .create_new_session(&request_id.to_string(), &session_id, Some(payload.model.clone()), true)
+        .create_new_session(&request_id.to_string(), &session_id, Some(payload.model.clone()), true, caller.namespace(), caller.sudo(&payload.model))
+        .await
+        .map_err(|e| ErrorResponse::from_session_error(e, |e| ErrorResponse::internal_error(format!("Failed to create session: {}", e))))?;
+
+    // Client tools, their calls are handed back to the client
+    let tools = external_tools(&payload);
+    let tool_names = tools.iter().map(|tool| tool.name.clone()).collect();
+    agent_session.add_external_tools(tools)
         .await
-        .map_err(|e| ErrorResponse::internal_error(format!("Failed to create session: {}", e)))?;
+        .map_err(|e| ErrorResponse::invalid_request(format!("Invalid tools: {}", e)))?;
 
     // Send messages and get event stream
     let request_session = agent_session
@@ -93,6 +113,8 @@ async fn handle_chat_completion_non_stream(
     let mut event_stream = BroadcastStream::new(request_session.event_rx);
     let mut final_message = String::new();
     let mut reasoning_steps = Vec::new();
+    let mut client_tool_calls = ClientToolCalls::default();
+    let mut usage = TokenTotals::default();
 
     while let Some(result) = event_stream.next().await {
         match result {
@@ -107,6 +129,7 @@ async fn handle_chat_completion_non_stream(
                         }
                 );
 
+                usage.add(&event);
                 match event {
                     AgentEvent::Completed { message, .. } => {
                         final_message = message;
@@ -116,10 +139,13 @@ async fn handle_chat_completion_non_stream(
                             if let ChatMessage::Assistant {
                                 content: Some(ChatMessageContent::Text(text)),
                                 ..
-                            } = msg
+                            } = &msg
                             {
-                                final_message = text;
+                                final_message = text.clone();
                             }
+                            // the client runs these, answer once the agent tools of the step ran
+                            // and let the session stop the agent
+                            client_tool_calls.on_brain_result(&msg, &tool_names);
                         }
                     }
                     AgentEvent::ToolCallStarted { call, .. } => {
@@ -127,6 +153,7 @@ async fn handle_chat_completion_non_stream(
                     }
                     AgentEvent::ToolCallCompleted { call, result: tool_result, .. } => {
                         use shai_core::tools::ToolResult;
+                        client_tool_calls.on_tool_completed(&call.tool_call_id);
                         let step = match &tool_result {
                             ToolResult::Success { .. } => format!("[tool succeeded: {}]", call.tool_name),
                             ToolResult::Error { error, .. } => {
@@ -140,7 +167,7 @@ async fn handle_chat_completion_non_stream(
                     _ => {}
                 }
 
-                if is_terminal {
+                if is_terminal || client_tool_calls.ready().is_some() {
                     break;
                 }
             }
@@ -151,6 +178,7 @@ async fn handle_chat_completion_non_stream(
     }
 
     // Build OpenAI-compatible response
+    let client_tool_calls = client_tool_calls.ready().cloned();
     let response = ChatCompletionResponse {
         id: Some(format!("chatcmpl-{}", Uuid::new_v4())),
         object: "chat.completion".to_string(),
@@ -162,9 +190,10 @@ async fn handle_chat_completion_non_stream(
         choices: vec![ChatCompletionChoice {
             index: 0,
             message: ChatMessage::Assistant {
-                content: Some(ChatMessageContent::Text(final_message)),
+                content: (client_tool_calls.is_none() || !final_message.is_empty())
+                    .then_some(ChatMessageContent::Text(final_message)),
                 name: None,
-                tool_calls: None,
+                tool_calls: client_tool_calls.clone(),
                 audio: None,
                 reasoning_content: if reasoning_steps.is_empty() {
                     None
@@ -173,20 +202,14 @@ async fn handle_chat_completion_non_stream(
                 },
                 refusal: None,
             },
-            finish_reason: Some(FinishReason::StopSequenceReached),
+            finish_reason: Some(if client_tool_calls.is_some() {
+                FinishReason::ToolCalls
+            } else {
+                FinishReason::StopSequenceReached
+            }),
             logprobs: None,
         }],
-        usage: Some(Usage {
-            input_tokens: None,
-            input_tokens_details: None,
-            output_tokens: None,
-            output_tokens_details: None,
-            prompt_tokens: Some(0),
-            completion_tokens: Some(0),
-            total_tokens: 0,
-            completion_tokens_details: None,
-            prompt_tokens_details: None,
-        }),
+        usage: Some(openai_usage(&usage)),
         system_fingerprint: None,
         service_tier: None,
     };
@@ -209,32 +232,23 @@ fn build_message_trace(params: &ChatCompletionParameters) -> Vec<ChatMessage> {
                 }
             }
             ChatMessage::User { content, name, .. } => {
-                let text = match content {
-                    ChatMessageContent::Text(t) => t.clone(),
-                    ChatMessageContent::ContentPart(parts) => {
-                        parts
-                            .iter()
-                            .filter_map(|p| match p {
-                                openai_dive::v1::resources::chat::ChatMessageContentPart::Text(t) => Some(t.text.as_str()),
-                                _ => None,
-                            })
-                            .collect::<Vec<_>>()
-                            .join("\n")
-                    }
-                    ChatMessageContent::None => String::new(),
-                };
-                if !text.is_empty() {
+                if let Some(content) = user_content(content) {
                     trace.push(ChatMessage::User {
-                        content: ChatMessageContent::Text(text),
+                        content,
                         name: name.clone(),
                     });
                 }
             }
-            ChatMessage::Assistant { content, name, .. } => {
-                if let Some(ChatMessageContent::Text(text)) = content {
+            ChatMessage::Assistant { content, name, tool_calls, .. } => {
+                let content = match content {
+                    Some(ChatMessageContent::Text(text)) => Some(ChatMessageContent::Text(text.clone())),
+                    _ => None,
+                };
+                // tool calls are kept so the results of the client tools that follow match them
+                if content.is_some() || tool_calls.is_some() {
                     trace.push(ChatMessage::Assistant {
-                        content: Some(ChatMessageContent::Text(text.clone())),
-                        tool_calls: None,
+                        content,
+                        tool_calls: tool_calls.clone(),
                         name: name.clone(),
                         audio: None,
                         reasoning_content: None,
@@ -242,9 +256,39 @@ fn build_message_trace(params: &ChatCompletionParameters) -> Vec<ChatMessage> {
                     });
                 }
             }
+            ChatMessage::Tool { .. } => {
+                trace.push(msg.clone());
+            }
             _ => {}
         }
     }
 
     trace
 }
+
+/// Text of a user message, or its text and image parts: images are kept for vision models,
+/// LlmClient drops them for the others
+fn user_content(content: &ChatMessageContent) -> Option<ChatMessageContent> {
+    match content {
+        ChatMessageContent::ContentPart(parts) if parts.iter().any(|p| matches!(p, ChatMessageContentPart::Image(_))) => {
+            Some(ChatMessageContent::ContentPart(parts
+                .iter()
+                .filter(|p| matches!(p, ChatMessageContentPart::Text(_) | ChatMessageContentPart::Image(_)))
+                .cloned()
+                .collect()))
+        }
+        ChatMessageContent::ContentPart(parts) => {
+            let text = parts
+                .iter()
+                .filter_map(|p| match p {
+                    ChatMessageContentPart::Text(t) => Some(t.text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            (!text.is_empty()).then_some(ChatMessageContent::Text(text))
+        }
+        ChatMessageContent::Text(text) if !text.is_empty() => Some(ChatMessageContent::Text(text.clone())),
+        _ => None,
+    }
+}
@@ -1,4 +1,5 @@
 pub mod handler;
 pub mod formatter;
+pub mod tools;
 
-pub use handler::*;
+pub use handler::*;
\ No newline at end of file
@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+
+use openai_dive::v1::resources::chat::{ChatCompletionParameters, ChatMessage, ToolCall};
+use openai_dive::v1::resources::shared::Usage;
+use serde_json::json;
+use shai_core::tools::ExternalTool;
+
+use crate::session::TokenTotals;
+
+/// Functions sent in `tools` by the client, run by the client itself
+pub fn external_tools(params: &ChatCompletionParameters) -> Vec<ExternalTool> {
+    params.tools.iter()
+        .flatten()
+        .map(|tool| ExternalTool::new(
+            tool.function.name.clone(),
+            tool.function.description.clone().unwrap_or_default(),
+            tool.function.parameters.clone(),
+        ))
+        .collect()
+}
+
+/// Tool calls of a brain step the client has to run, None when the agent runs them all
+pub fn external_tool_calls(message: &ChatMessage, external: &HashSet<String>) -> Option<Vec<ToolCall>> {
+    let ChatMessage::Assistant { tool_calls: Some(tool_calls), .. } = message else {
+        return None;
+    };
+    let calls: Vec<ToolCall> = tool_calls.iter()
+        .filter(|call| external.contains(&call.function.name))
+        .cloned()
+        .collect();
+    (!calls.is_empty()).then_some(calls)
+}
+
+/// Client tool calls of a brain step. The agent tools called in the same step run first,
+/// the response ends once they completed
+#[derive(Debug, Default)]
+pub struct ClientToolCalls {
+    calls: Option<Vec<ToolCall>>,
+    running: HashSet<String>,
+}
+
+impl ClientToolCalls {
+    pub fn on_brain_result(&mut self, message: &ChatMessage, external: &HashSet<String>) {
+        let Some(calls) = external_tool_calls(message, external) else {
+            return;
+        };
+        if let ChatMessage::Assistant { tool_calls: Some(tool_calls), .. } = message {
+            self.running = tool_calls.iter()
+                .filter(|call| !external.contains(&call.function.name))
+                .map(|call| call.id.clone())
+                .collect();
+        }
+        self.calls = Some(calls);
+    }
+
+    pub fn on_tool_completed(&mut self, tool_call_id: &str) {
+        self.running.remove(tool_call_id);
+    }
+
+    /// Calls to hand to the client, once the agent tools of their step are done
+    pub fn ready(&self) -> Option<&Vec<ToolCall>> {
+        self.calls.as_ref().filter(|_| self.running.is_empty())
+    }
+}
+
+/// Usage of a request in the OpenAI format
+pub fn openai_usage(totals: &TokenTotals) -> Usage {
+    let prompt_tokens = totals.input_tokens as u32;
+    let completion_tokens = totals.output_tokens as u32;
+    Usage {
+        input_tokens: None,
+        input_tokens_details: None,
+        output_tokens: None,
+        output_tokens_details: None,
+        prompt_tokens: Some(prompt_tokens),
+        completion_tokens: Some(completion_tokens),
+        total_tokens: prompt_tokens + completion_tokens,
+        completion_tokens_details: None,
+        prompt_tokens_details: serde_json::from_value(json!({ "cached_tokens": totals.cached_tokens })).ok().flatten(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openai_dive::v1::resources::chat::Function;
+
+    fn call(id: &str, name: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            r#type: "function".to_string(),
+            function: Function { name: name.to_string(), arguments: "{}".to_string() },
+        }
+    }
+
+    #[test]
+    fn test_client_tool_calls_wait_for_agent_tools() {
+        let external: HashSet<String> = HashSet::from(["ide_open".to_string()]);
+        let message = ChatMessage::Assistant {
+            content: None,
+            reasoning_content: None,
+            refusal: None,
+            name: None,
+            audio: None,
+            tool_calls: Some(vec![call("1", "read"), call("2", "ide_open"), call("3", "ls")]),
+        };
+
+        let mut calls = ClientToolCalls::default();
+        calls.on_brain_result(&message, &external);
+        assert!(calls.ready().is_none());
+        calls.on_tool_completed("1");
+        assert!(calls.ready().is_none());
+        calls.on_tool_completed("3");
+        let ready = calls.ready().unwrap();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].id, "2");
+    }
+
+    #[test]
+    fn test_openai_usage() {
+        let usage = openai_usage(&TokenTotals { input_tokens: 120, output_tokens: 30, cached_tokens: 100 });
+        assert_eq!(usage.prompt_tokens, Some(120));
+        assert_eq!(usage.completion_tokens, Some(30));
+        assert_eq!(usage.total_tokens, 150);
+    }
+}
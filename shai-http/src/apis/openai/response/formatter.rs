@@ -12,7 +12,8 @@ use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
 use shai_core::agent::AgentEvent;
 use uuid::Uuid;
 
-use super::types::ResponseStreamEvent;
+use super::store::ResponseStore;
+use super::types::{reasoning_output, ResponseStreamEvent};
 use crate::streaming::EventFormatter;
 
 /// Formatter for OpenAI Response API
@@ -26,6 +27,10 @@ pub struct ResponseFormatter {
     output: Vec<ResponseOutput>,
     accumulated_text: String,
     initial_event_sent: bool,
+    input_tokens: u32,
+    output_tokens: u32,
+    store: Option<(ResponseStore, Option<String>)>,
+    response: Option<ResponseObject>,
 }
 
 impl ResponseFormatter {
@@ -43,9 +48,48 @@ impl ResponseFormatter {
             output: Vec::new(),
             accumulated_text: String::new(),
             initial_event_sent: false,
+            input_tokens: 0,
+            output_tokens: 0,
+            store: None,
+            response: None,
         }
     }
 
+    /// Keep the response objects for GET /v1/responses/{id} of the callers of `namespace`,
+    /// unless the request has store=false
+    pub fn with_store(mut self, store: ResponseStore, namespace: Option<String>) -> Self {
+        if self.payload.store.unwrap_or(true) {
+            self.store = Some((store, namespace));
+        }
+        self
+    }
+
+    /// Final response object, once the agent completed or paused
+    pub fn response(&self) -> Option<&ResponseObject> {
+        self.response.as_ref()
+    }
+
+    /// Build the completed (or incomplete) response, remember it and hand it to the store
+    async fn finish(&mut self, session_id: &str, status: ReasoningStatus) -> ResponseObject {
+        let msg_output = ResponseOutput::Message(OutputMessage {
+            id: Uuid::new_v4().to_string(),
+            role: Role::Assistant,
+            status: MessageStatus::Completed,
+            content: vec![OutputContent::Text {
+                text: self.accumulated_text.clone(),
+                annotations: vec![],
+            }],
+        });
+        self.output.push(msg_output);
+
+        let response = self.build_response_object(session_id, status, self.output.clone());
+        if let Some((store, namespace)) = &self.store {
+            store.insert(namespace.clone(), response.clone()).await;
+        }
+        self.response = Some(response.clone());
+        response
+    }
+
     fn build_response_object(
         &self,
         session_id: &str,
@@ -64,7 +108,7 @@ impl ResponseFormatter {
             temperature: self.payload.temperature,
             max_output_tokens: self.payload.max_output_tokens,
             parallel_tool_calls: self.payload.parallel_tool_calls,
-            previous_response_id: None,
+            previous_response_id: self.payload.previous_response_id.clone(),
             reasoning: self.payload.reasoning.clone(),
             text: self.payload.text.clone(),
             tool_choice: self.payload.tool_choice.clone(),
@@ -73,13 +117,13 @@ impl ResponseFormatter {
             truncation: self.payload.truncation.clone(),
             user: self.payload.user.clone(),
             usage: Usage {
-                input_tokens: None,
+                input_tokens: Some(self.input_tokens),
                 input_tokens_details: None,
-                output_tokens: None,
+                output_tokens: Some(self.output_tokens),
                 output_tokens_details: None,
-                completion_tokens: Some(0),
-                prompt_tokens: Some(0),
-                total_tokens: 0,
+                completion_tokens: Some(self.output_tokens),
+                prompt_tokens: Some(self.input_tokens),
+                total_tokens: self.input_tokens + self.output_tokens,
                 completion_tokens_details: None,
                 prompt_tokens_details: None,
             },
@@ -106,6 +150,9 @@ impl EventFormatter for ResponseFormatter {
                 ReasoningStatus::InProgress,
                 vec![],
             );
+            if let Some((store, namespace)) = &self.store {
+                store.insert(namespace.clone(), initial_response.clone()).await;
+            }
             let evt = ResponseStreamEvent::created(self.sequence, initial_response);
             self.sequence += 1;
             return Some(evt);
@@ -114,15 +161,27 @@ impl EventFormatter for ResponseFormatter {
         match event {
             // Capture assistant messages from brain results
             AgentEvent::BrainResult { thought, .. } => {
-                if let Ok(msg) = thought {
-                    if let ChatMessage::Assistant {
-                        content: Some(ChatMessageContent::Text(text)),
-                        ..
-                    } = msg
-                    {
-                        self.accumulated_text = text;
-                    }
+                let Ok(ChatMessage::Assistant { content, reasoning_content, .. }) = thought else {
+                    return None;
+                };
+                if let Some(ChatMessageContent::Text(text)) = content {
+                    self.accumulated_text = text;
                 }
+
+                // reasoning of this step, ahead of the tool calls it leads to
+                let reasoning = reasoning_content.filter(|r| !r.trim().is_empty())
+                    .and_then(|r| reasoning_output(&format!("rs_{}", Uuid::new_v4()), &r))?;
+                let output_index = self.output.len();
+                self.output.push(reasoning.clone());
+
+                let event = ResponseStreamEvent::output_item_done(self.sequence, output_index, reasoning);
+                self.sequence += 1;
+                Some(event)
+            }
+
+            AgentEvent::TokenUsage { input_tokens, output_tokens, .. } => {
+                self.input_tokens += input_tokens;
+                self.output_tokens += output_tokens;
                 None
             }
 
@@ -186,28 +245,13 @@ impl EventFormatter for ResponseFormatter {
                     self.accumulated_text = message;
                 }
 
-                let msg_output = ResponseOutput::Message(OutputMessage {
-                    id: Uuid::new_v4().to_string(),
-                    role: Role::Assistant,
-                    status: MessageStatus::Completed,
-                    content: vec![OutputContent::Text {
-                        text: self.accumulated_text.clone(),
-                        annotations: vec![],
-                    }],
-                });
-                self.output.push(msg_output);
-
                 let final_status = if success {
                     ReasoningStatus::Completed
                 } else {
                     ReasoningStatus::Failed
                 };
 
-                let final_response = self.build_response_object(
-                    session_id,
-                    final_status,
-                    self.output.clone(),
-                );
+                let final_response = self.finish(session_id, final_status).await;
 
                 let event = ResponseStreamEvent::completed(self.sequence, final_response);
 
@@ -216,23 +260,9 @@ impl EventFormatter for ResponseFormatter {
 
             AgentEvent::StatusChanged { new_status, .. } => {
                 use shai_core::agent::PublicAgentState;
+                // the agent pauses once it answered and waits for the next input
                 if matches!(new_status, PublicAgentState::Paused { .. }) {
-                    let msg_output = ResponseOutput::Message(OutputMessage {
-                        id: Uuid::new_v4().to_string(),
-                        role: Role::Assistant,
-                        status: MessageStatus::Completed,
-                        content: vec![OutputContent::Text {
-                            text: self.accumulated_text.clone(),
-                            annotations: vec![],
-                        }],
-                    });
-                    self.output.push(msg_output);
-
-                    let final_response = self.build_response_object(
-                        session_id,
-                        ReasoningStatus::Incomplete,
-                        self.output.clone(),
-                    );
+                    let final_response = self.finish(session_id, ReasoningStatus::Completed).await;
 
                     let event = ResponseStreamEvent::completed(self.sequence, final_response);
 
@@ -1,13 +1,21 @@
+use std::sync::Arc;
+
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::{IntoResponse, Response, Sse},
     Json,
 };
+use futures::StreamExt;
 use openai_dive::v1::resources::response::request::ResponseParameters;
-use tracing::info;
+use serde::Deserialize;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tracing::{info, warn};
 use uuid::Uuid;
 
-use crate::{event_to_sse_stream, session_to_sse_stream, ApiJson, ErrorResponse, ServerState};
+use crate::auth::Caller;
+use crate::session::{AgentSession, RequestSession};
+use crate::streaming::is_terminal_event;
+use crate::{event_to_sse_stream, session_to_sse_stream, ApiJson, ErrorResponse, EventFormatter, ServerState};
 use super::types::build_message_trace;
 use super::formatter::ResponseFormatter;
 
@@ -15,6 +23,7 @@ use super::formatter::ResponseFormatter;
 /// Supports both stateful (store=true, previous_response_id) and stateless (store=false) modes
 pub async fn handle_response(
     State(state): State<ServerState>,
+    caller: Caller,
     ApiJson(payload): ApiJson<ResponseParameters>,
 ) -> Result<Response, ErrorResponse> {
     let request_id = Uuid::new_v4();
@@ -22,51 +31,66 @@ pub async fn handle_response(
     let session_id = payload.previous_response_id.clone()
         .unwrap_or_else(|| format!("resp_{}", Uuid::new_v4()));
 
-    info!("[{}] POST /v1/responses session={} store={} stream={}",
-        request_id, session_id, store, payload.stream.unwrap_or(false));
+    info!("[{}] POST /v1/responses session={} store={} stream={} key={}",
+        request_id, session_id, store, payload.stream.unwrap_or(false), caller.name);
 
     // Check if streaming is requested
     if payload.stream.unwrap_or(false) {
-        handle_response_stream(state, payload, request_id, session_id, !store).await
+        handle_response_stream(state, caller, payload, request_id, session_id, !store).await
     } else {
-        handle_response_non_stream(state, payload, request_id, session_id, !store).await
+        handle_response_non_stream(state, caller, payload, request_id, session_id, !store).await
     }
 }
 
-/// Handle streaming response
-async fn handle_response_stream(
-    state: ServerState,
-    payload: ResponseParameters,
+/// Get or create the session agent and hand it the request input
+async fn start_request(
+    state: &ServerState,
+    caller: &Caller,
+    payload: &ResponseParameters,
     request_id: Uuid,
-    session_id: String,
+    session_id: &str,
     is_ephemeral: bool,
-) -> Result<Response, ErrorResponse> {
-    let trace = build_message_trace(&payload);
+) -> Result<RequestSession, ErrorResponse> {
+    let trace = build_message_trace(payload);
     let model = payload.model.clone();
+    caller.check_agent(&model)?;
 
     // Get or create session agent based on whether previous_response_id was provided
-    let agent_session = if payload.previous_response_id.is_some() {
+    let agent_session: Arc<AgentSession> = if payload.previous_response_id.is_some() {
         // previous_response_id provided -> must exist (in memory or disk), error if not
         state.session_manager
-            .get_session(&request_id.to_string(), &session_id, model.clone())
+            .get_session(&request_id.to_string(), session_id, Some(model.as_str()), caller.namespace(), |agent| caller.sudo(agent))
             .await
-            .map_err(|e| ErrorResponse::invalid_request(format!("Previous response not found: {}", e)))?
+            .map_err(|e| ErrorResponse::from_session_error(e, |e| ErrorResponse::invalid_request(format!("Previous response not found: {}", e))))?
     } else {
         // No previous_response_id -> create new session
         state.session_manager
-            .create_new_session(&request_id.to_string(), &session_id, Some(model.clone()), is_ephemeral)
+            .create_new_session(&request_id.to_string(), session_id, Some(model.clone()), is_ephemeral, caller.namespace(), caller.sudo(&model))
             .await
-            .map_err(|e| ErrorResponse::internal_error(format!("Failed to create session: {}", e)))?
+            .map_err(|e| ErrorResponse::from_session_error(e, |e| ErrorResponse::internal_error(format!("Failed to create session: {}", e))))?
     };
 
     // Create request session
-    let request_session = agent_session
+    agent_session
         .handle_request(&request_id.to_string(), trace)
         .await
-        .map_err(|e| ErrorResponse::internal_error(format!("Failed to handle request: {}", e)))?;
+        .map_err(|e| ErrorResponse::internal_error(format!("Failed to handle request: {}", e)))
+}
+
+/// Handle streaming response
+async fn handle_response_stream(
+    state: ServerState,
+    caller: Caller,
+    payload: ResponseParameters,
+    request_id: Uuid,
+    session_id: String,
+    is_ephemeral: bool,
+) -> Result<Response, ErrorResponse> {
+    let request_session = start_request(&state, &caller, &payload, request_id, &session_id, is_ephemeral).await?;
 
     // Create the formatter for OpenAI Response API
-    let formatter = ResponseFormatter::new(model, payload);
+    let formatter = ResponseFormatter::new(payload.model.clone(), payload)
+        .with_store(state.responses.clone(), caller.namespace.clone());
 
     // Create SSE stream
     let stream = session_to_sse_stream(request_session, formatter, session_id, true);
@@ -75,33 +99,78 @@ async fn handle_response_stream(
 }
 
 /// Handle non-streaming response
+/// Runs the turn to completion through the same formatter as the stream and returns its final object
 async fn handle_response_non_stream(
-    _state: ServerState,
-    _payload: ResponseParameters,
-    _request_id: Uuid,
-    _session_id: String,
-    _is_ephemeral: bool,
+    state: ServerState,
+    caller: Caller,
+    payload: ResponseParameters,
+    request_id: Uuid,
+    session_id: String,
+    is_ephemeral: bool,
 ) -> Result<Response, ErrorResponse> {
-    return Err(ErrorResponse::internal_error("Response API (non-stream) not yet implemented".to_string()));
+    let RequestSession { event_rx, lifecycle, .. } =
+        start_request(&state, &caller, &payload, request_id, &session_id, is_ephemeral).await?;
+
+    let mut formatter = ResponseFormatter::new(payload.model.clone(), payload)
+        .with_store(state.responses.clone(), caller.namespace.clone());
+
+    let mut event_stream = BroadcastStream::new(event_rx);
+    while let Some(result) = event_stream.next().await {
+        // a slow reader misses some events, the turn goes on and its final object is still built
+        let event = match result {
+            Ok(event) => event,
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                warn!("[{}] {} events skipped while waiting for the response", request_id, skipped);
+                continue;
+            }
+        };
+        let is_terminal = is_terminal_event(&event, true);
+        formatter.format_event(event, &session_id).await;
+        if is_terminal {
+            break;
+        }
+    }
+
+    // release the session (saved, or destroyed when ephemeral)
+    drop(lifecycle);
+
+    let response = formatter.response()
+        .cloned()
+        .ok_or_else(|| ErrorResponse::internal_error("Agent stopped before completing the response".to_string()))?;
+
+    Ok(Json(response).into_response())
 }
 
 
+#[derive(Debug, Default, Deserialize)]
+pub struct GetResponseQuery {
+    #[serde(default)]
+    pub stream: bool,
+}
+
 /// GET /v1/responses/{response_id} - Retrieve a model response
-/// Read-only access to an ongoing or completed session
+/// Returns the stored response object, or with ?stream=true (or when nothing was stored)
+/// streams the events of the ongoing session
 pub async fn handle_get_response(
     State(state): State<ServerState>,
+    caller: Caller,
     Path(response_id): Path<String>,
+    Query(query): Query<GetResponseQuery>,
 ) -> Result<Response, ErrorResponse> {
     let request_id = Uuid::new_v4();
-    info!("[{}] GET /v1/responses/{}", request_id, response_id);
+    info!("[{}] GET /v1/responses/{} stream={}", request_id, response_id, query.stream);
+
+    if !query.stream {
+        if let Some(response) = state.responses.get(&response_id, caller.namespace()).await {
+            return Ok(Json(response).into_response());
+        }
+    }
 
-    // Get the existing session (note: without agent_name, will only check memory, not disk)
-    // For GET we don't have the model from request, so we use the session's agent_name
-    // This means GET can only access in-memory sessions
+    // For GET we don't have the model from request, the session keeps the agent it runs
     let agent_session = state.session_manager
-        .get_session(&request_id.to_string(), &response_id, "default".to_string())
+        .get_session(&request_id.to_string(), &response_id, None, caller.namespace(), |agent| caller.sudo(agent))
         .await
-        .map_err(|e| ErrorResponse::invalid_request(format!("Response not found: {}", e)))?;
+        .map_err(|e| ErrorResponse::from_session_error(e, |e| ErrorResponse::invalid_request(format!("Response not found: {}", e))))?;
 
     // Subscribe to events (non-blocking, read-only)
     let event_rx = agent_session.watch();
@@ -117,8 +186,8 @@ pub async fn handle_get_response(
     let formatter = ResponseFormatter::new(agent_session.agent_name.clone(), placeholder_payload);
 
     // Create SSE stream using the simple sse_stream (no lifecycle needed for read-only)
-    // stop_on_pause = false means stream stops on Completed OR Paused
-    let stream = event_to_sse_stream(event_rx, formatter, response_id, false);
+    // stop_on_pause = true means stream stops on Completed OR Paused, at the end of the turn
+    let stream = event_to_sse_stream(event_rx, formatter, response_id, true);
 
     Ok(Sse::new(stream).into_response())
 }
@@ -127,6 +196,7 @@ pub async fn handle_get_response(
 /// POST /v1/responses/{response_id}/cancel - Cancel a model response
 pub async fn handle_cancel_response(
     State(state): State<ServerState>,
+    caller: Caller,
     Path(response_id): Path<String>,
 ) -> Result<Response, ErrorResponse> {
     let request_id = Uuid::new_v4();
@@ -134,7 +204,7 @@ pub async fn handle_cancel_response(
 
     // Cancel the session
     state.session_manager
-        .cancel_session(&request_id.to_string(), &response_id)
+        .cancel_session(&request_id.to_string(), &response_id, caller.namespace())
         .await
         .map_err(|e| ErrorResponse::internal_error(format!("Failed to cancel session: {}", e)))?;
 
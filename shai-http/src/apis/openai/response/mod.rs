@@ -1,5 +1,7 @@
 pub mod handler;
 pub mod types;
 pub mod formatter;
+pub mod store;
 
-pub use handler::{handle_response, handle_get_response, handle_cancel_response};
\ No newline at end of file
+pub use handler::{handle_response, handle_get_response, handle_cancel_response};
+pub use store::ResponseStore;
\ No newline at end of file
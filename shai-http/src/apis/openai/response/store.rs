@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use openai_dive::v1::resources::response::response::ResponseObject;
+use tokio::sync::Mutex;
+
+use crate::auth::can_access;
+
+/// Responses kept at most, the least recently updated are dropped first
+const MAX_RESPONSES: usize = 1000;
+
+/// Last response object of each session, served by GET /v1/responses/{id}
+/// A session keeps its id across turns (previous_response_id), so a new turn replaces it.
+/// The session manager removes it with its session
+#[derive(Clone, Default)]
+pub struct ResponseStore {
+    inner: Arc<Mutex<Responses>>,
+}
+
+#[derive(Default)]
+struct Responses {
+    /// response id -> (owner namespace, response, insertion number)
+    entries: HashMap<String, (Option<String>, ResponseObject, u64)>,
+    inserted: u64,
+}
+
+impl ResponseStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn insert(&self, namespace: Option<String>, response: ResponseObject) {
+        let mut responses = self.inner.lock().await;
+        responses.inserted += 1;
+        let inserted = responses.inserted;
+        responses.entries.insert(response.id.clone(), (namespace, response, inserted));
+
+        if responses.entries.len() > MAX_RESPONSES {
+            let oldest = responses.entries.iter()
+                .min_by_key(|(_, (_, _, inserted))| *inserted)
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                responses.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// The response if `namespace` may see it
+    pub async fn get(&self, response_id: &str, namespace: Option<&str>) -> Option<ResponseObject> {
+        self.inner.lock().await.entries.get(response_id)
+            .filter(|(owner, _, _)| can_access(namespace, owner.as_deref()))
+            .map(|(_, response, _)| response.clone())
+    }
+
+    pub async fn remove(&self, response_id: &str) -> Option<ResponseObject> {
+        self.inner.lock().await.entries.remove(response_id).map(|(_, response, _)| response)
+    }
+}
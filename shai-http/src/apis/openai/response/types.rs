@@ -153,6 +153,17 @@ impl ResponseStreamEvent {
     }
 }
 
+/// Reasoning output item with the reasoning as a single summary part
+/// (built from json, openai_dive has no constructor for it)
+pub fn reasoning_output(id: &str, reasoning: &str) -> Option<ResponseOutput> {
+    serde_json::from_value(serde_json::json!({
+        "type": "reasoning",
+        "id": id,
+        "summary": [{ "type": "summary_text", "text": reasoning }],
+        "status": "completed"
+    })).ok()
+}
+
 /// Convert OpenAI Response API input to ChatMessage trace
 pub fn build_message_trace(params: &ResponseParameters) -> Vec<ChatMessage> {
     let mut trace = Vec::new();
@@ -0,0 +1,258 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{ApiJson, Caller, ErrorResponse, ServerState};
+use super::types::{
+    EventsQuery, ForkQuery, PermissionAnswer, PermissionList, QueryAnswer, QueryList, SessionEvents, SessionList,
+    SessionListQuery, SessionObject, SessionTrace,
+};
+
+/// GET /v1/sessions - Sessions of the caller, loaded or saved in the store
+pub async fn handle_list_sessions(
+    State(state): State<ServerState>,
+    caller: Caller,
+    Query(query): Query<SessionListQuery>,
+) -> Result<Response, ErrorResponse> {
+    let request_id = Uuid::new_v4();
+    info!("[{}] GET /v1/sessions key={}", request_id, caller.name);
+
+    let loaded = state.session_manager.loaded_sessions(caller.namespace()).await;
+    // the store applies the agent filter and the limit itself
+    let saved = match query.store_filter(caller.namespace()) {
+        Some(filter) => state.session_manager.saved_sessions(&filter).await,
+        None => vec![],
+    };
+
+    let mut data: Vec<SessionObject> = loaded.iter()
+        .map(|session| SessionObject::from(session.as_ref()))
+        .chain(saved.iter().map(SessionObject::from))
+        .filter(|session| query.matches(session))
+        .collect();
+    data.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    if let Some(limit) = query.limit {
+        data.truncate(limit);
+    }
+
+    Ok(Json(SessionList { object: "list", data }).into_response())
+}
+
+/// GET /v1/sessions/{session_id} - A single session
+pub async fn handle_get_session(
+    State(state): State<ServerState>,
+    caller: Caller,
+    Path(session_id): Path<String>,
+) -> Result<Response, ErrorResponse> {
+    let request_id = Uuid::new_v4();
+    info!("[{}] GET /v1/sessions/{}", request_id, session_id);
+
+    if let Some(session) = state.session_manager.find_session(&session_id, caller.namespace()).await {
+        return Ok(Json(SessionObject::from(session.as_ref())).into_response());
+    }
+
+    let saved = state.session_manager
+        .snapshot_session(&session_id, caller.namespace())
+        .await
+        .map_err(|_| ErrorResponse::not_found(format!("Session not found: {}", session_id)))?;
+
+    Ok(Json(SessionObject::from(&saved.info)).into_response())
+}
+
+/// GET /v1/sessions/{session_id}/trace - Messages of the session
+pub async fn handle_get_trace(
+    State(state): State<ServerState>,
+    caller: Caller,
+    Path(session_id): Path<String>,
+) -> Result<Response, ErrorResponse> {
+    let request_id = Uuid::new_v4();
+    info!("[{}] GET /v1/sessions/{}/trace", request_id, session_id);
+
+    let snapshot = state.session_manager
+        .snapshot_session(&session_id, caller.namespace())
+        .await
+        .map_err(|_| ErrorResponse::not_found(format!("Session not found: {}", session_id)))?;
+
+    Ok(Json(SessionTrace {
+        object: "list",
+        session_id,
+        data: snapshot.trace,
+    }).into_response())
+}
+
+/// GET /v1/sessions/{session_id}/events - Event log of the session (empty with the file store)
+pub async fn handle_get_events(
+    State(state): State<ServerState>,
+    caller: Caller,
+    Path(session_id): Path<String>,
+    Query(query): Query<EventsQuery>,
+) -> Result<Response, ErrorResponse> {
+    let request_id = Uuid::new_v4();
+    info!("[{}] GET /v1/sessions/{}/events", request_id, session_id);
+
+    let events = state.session_manager
+        .session_events(&session_id, caller.namespace(), query.limit)
+        .await
+        .map_err(|_| ErrorResponse::not_found(format!("Session not found: {}", session_id)))?;
+
+    Ok(Json(SessionEvents {
+        object: "list",
+        session_id,
+        data: events,
+    }).into_response())
+}
+
+/// DELETE /v1/sessions/{session_id} - Stop the session agent and remove it from the store
+pub async fn handle_delete_session(
+    State(state): State<ServerState>,
+    caller: Caller,
+    Path(session_id): Path<String>,
+) -> Result<Response, ErrorResponse> {
+    let request_id = Uuid::new_v4();
+    info!("[{}] DELETE /v1/sessions/{} key={}", request_id, session_id, caller.name);
+
+    state.session_manager
+        .delete_session(&request_id.to_string(), &session_id, caller.namespace())
+        .await
+        .map_err(|_| ErrorResponse::not_found(format!("Session not found: {}", session_id)))?;
+
+    Ok(Json(serde_json::json!({
+        "id": session_id,
+        "object": "session",
+        "deleted": true
+    })).into_response())
+}
+
+/// POST /v1/sessions/{session_id}/fork - New session starting from a copy of the trace and of the workspace
+pub async fn handle_fork_session(
+    State(state): State<ServerState>,
+    caller: Caller,
+    Path(session_id): Path<String>,
+    Query(query): Query<ForkQuery>,
+) -> Result<Response, ErrorResponse> {
+    let request_id = Uuid::new_v4();
+    let new_session_id = query.session_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    info!("[{}] POST /v1/sessions/{}/fork new={} key={}", request_id, session_id, new_session_id, caller.name);
+
+    let source = state.session_manager
+        .snapshot_session(&session_id, caller.namespace())
+        .await
+        .map_err(|_| ErrorResponse::not_found(format!("Session not found: {}", session_id)))?;
+
+    let agent = source.info.agent_name.clone().unwrap_or_else(|| "default".to_string());
+    caller.check_agent(&agent)?;
+
+    let session = state.session_manager
+        .fork_session(&request_id.to_string(), source, &new_session_id, caller.sudo(&agent))
+        .await
+        .map_err(|e| ErrorResponse::from_session_error(e, |e| ErrorResponse::invalid_request(format!("Failed to fork session: {}", e))))?;
+
+    Ok(Json(SessionObject::from(session.as_ref())).into_response())
+}
+
+/// GET /v1/sessions/{session_id}/permissions - Permission requests waiting for an answer
+pub async fn handle_list_permissions(
+    State(state): State<ServerState>,
+    caller: Caller,
+    Path(session_id): Path<String>,
+) -> Result<Response, ErrorResponse> {
+    let request_id = Uuid::new_v4();
+    info!("[{}] GET /v1/sessions/{}/permissions", request_id, session_id);
+
+    let session = state.session_manager
+        .find_session(&session_id, caller.namespace())
+        .await
+        .ok_or_else(|| ErrorResponse::not_found(format!("Session not found: {}", session_id)))?;
+
+    Ok(Json(PermissionList {
+        object: "list",
+        data: session.pending_permissions(),
+    }).into_response())
+}
+
+/// POST /v1/sessions/{session_id}/permissions/{request_id} - Approve or deny a pending tool call
+pub async fn handle_answer_permission(
+    State(state): State<ServerState>,
+    caller: Caller,
+    Path((session_id, permission_id)): Path<(String, String)>,
+    ApiJson(payload): ApiJson<PermissionAnswer>,
+) -> Result<Response, ErrorResponse> {
+    let request_id = Uuid::new_v4();
+    info!("[{}] POST /v1/sessions/{}/permissions/{} decision={:?} key={}",
+        request_id, session_id, permission_id, payload.decision, caller.name);
+
+    let session = state.session_manager
+        .find_session(&session_id, caller.namespace())
+        .await
+        .ok_or_else(|| ErrorResponse::not_found(format!("Session not found: {}", session_id)))?;
+
+    if !session.pending_permissions().iter().any(|p| p.request_id == permission_id) {
+        return Err(ErrorResponse::not_found(format!("Permission request not found: {}", permission_id)));
+    }
+
+    session
+        .respond_permission(&request_id.to_string(), &permission_id, payload.decision.into())
+        .await
+        .map_err(|e| ErrorResponse::internal_error(format!("Failed to answer permission request: {}", e)))?;
+
+    Ok(Json(serde_json::json!({
+        "request_id": permission_id,
+        "session_id": session_id,
+        "decision": payload.decision
+    })).into_response())
+}
+
+/// GET /v1/sessions/{session_id}/queries - Questions of the agent waiting for an answer
+pub async fn handle_list_queries(
+    State(state): State<ServerState>,
+    caller: Caller,
+    Path(session_id): Path<String>,
+) -> Result<Response, ErrorResponse> {
+    let request_id = Uuid::new_v4();
+    info!("[{}] GET /v1/sessions/{}/queries", request_id, session_id);
+
+    let session = state.session_manager
+        .find_session(&session_id, caller.namespace())
+        .await
+        .ok_or_else(|| ErrorResponse::not_found(format!("Session not found: {}", session_id)))?;
+
+    Ok(Json(QueryList {
+        object: "list",
+        data: session.pending_queries(),
+    }).into_response())
+}
+
+/// POST /v1/sessions/{session_id}/queries/{request_id} - Answer a question of the agent
+/// (MCP sampling approval or elicitation)
+pub async fn handle_answer_query(
+    State(state): State<ServerState>,
+    caller: Caller,
+    Path((session_id, query_id)): Path<(String, String)>,
+    ApiJson(payload): ApiJson<QueryAnswer>,
+) -> Result<Response, ErrorResponse> {
+    let request_id = Uuid::new_v4();
+    info!("[{}] POST /v1/sessions/{}/queries/{} key={}", request_id, session_id, query_id, caller.name);
+
+    let session = state.session_manager
+        .find_session(&session_id, caller.namespace())
+        .await
+        .ok_or_else(|| ErrorResponse::not_found(format!("Session not found: {}", session_id)))?;
+
+    if !session.pending_queries().iter().any(|q| q.request_id == query_id) {
+        return Err(ErrorResponse::not_found(format!("Query not found: {}", query_id)));
+    }
+
+    session
+        .respond_query(&request_id.to_string(), &query_id, payload.clone().into())
+        .await
+        .map_err(|e| ErrorResponse::internal_error(format!("Failed to answer query: {}", e)))?;
+
+    Ok(Json(serde_json::json!({
+        "request_id": query_id,
+        "session_id": session_id,
+        "answer": payload
+    })).into_response())
+}
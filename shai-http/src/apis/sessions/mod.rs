@@ -0,0 +1,8 @@
+pub mod types;
+pub mod handler;
+
+pub use types::{PermissionAnswer, PermissionDecision, QueryAnswer, SessionListQuery, SessionObject};
+pub use handler::{
+    handle_answer_permission, handle_answer_query, handle_delete_session, handle_fork_session, handle_get_events,
+    handle_get_session, handle_get_trace, handle_list_permissions, handle_list_queries, handle_list_sessions,
+};
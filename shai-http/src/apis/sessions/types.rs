@@ -0,0 +1,194 @@
+use chrono::{DateTime, Utc};
+use openai_dive::v1::resources::chat::ChatMessage;
+use serde::{Deserialize, Serialize};
+use shai_core::agent::{PermissionResponse, UserResponse};
+
+use crate::session::{AgentSession, PendingPermission, PendingQuery, SessionFilter, SessionInfo, StoredEvent, TokenTotals};
+
+/// Answer of a client to a permission request
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionDecision {
+    /// Run this tool call
+    Allow,
+    /// Run this one and every later tool call of the session without asking
+    AllowAlways,
+    /// Do not run it, the agent is told the user rejected the call
+    Deny,
+}
+
+impl From<PermissionDecision> for PermissionResponse {
+    fn from(decision: PermissionDecision) -> Self {
+        match decision {
+            PermissionDecision::Allow => PermissionResponse::Allow,
+            PermissionDecision::AllowAlways => PermissionResponse::AllowAlways,
+            PermissionDecision::Deny => PermissionResponse::Deny,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionAnswer {
+    pub decision: PermissionDecision,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionList {
+    pub object: &'static str,
+    pub data: Vec<PendingPermission>,
+}
+
+/// Answer of a client to a question of the agent, of the kind of the question:
+/// `{"text": "..."}`, `{"choice": 1}`, `{"confirmation": true}` or `"cancel"`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryAnswer {
+    Text(String),
+    /// Index into the options of the question
+    Choice(usize),
+    Confirmation(bool),
+    Cancel,
+}
+
+impl From<QueryAnswer> for UserResponse {
+    fn from(answer: QueryAnswer) -> Self {
+        match answer {
+            QueryAnswer::Text(text) => UserResponse::Text(text),
+            QueryAnswer::Choice(index) => UserResponse::Choice(index),
+            QueryAnswer::Confirmation(confirmed) => UserResponse::Confirmation(confirmed),
+            QueryAnswer::Cancel => UserResponse::Cancel,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryList {
+    pub object: &'static str,
+    pub data: Vec<PendingQuery>,
+}
+
+/// A session as listed by GET /v1/sessions
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionObject {
+    pub id: String,
+    pub object: &'static str,
+    pub agent: String,
+    /// Start of the first user message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Agent state when loaded (running, paused...), "saved" when only in the store
+    pub state: String,
+    /// Whether the agent is running in memory
+    pub loaded: bool,
+    pub ephemeral: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    pub usage: TokenTotals,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<&AgentSession> for SessionObject {
+    fn from(session: &AgentSession) -> Self {
+        let activity = session.activity();
+        Self {
+            id: session.session_id.clone(),
+            object: "session",
+            agent: session.agent_name.clone(),
+            title: activity.title(),
+            state: activity.state().to_string(),
+            loaded: true,
+            ephemeral: session.is_ephemeral(),
+            namespace: session.namespace.clone(),
+            usage: activity.usage(),
+            created_at: activity.created_at(),
+            updated_at: activity.updated_at(),
+        }
+    }
+}
+
+impl From<&SessionInfo> for SessionObject {
+    fn from(saved: &SessionInfo) -> Self {
+        Self {
+            id: saved.session_id.clone(),
+            object: "session",
+            agent: saved.agent_name.clone().unwrap_or_else(|| "default".to_string()),
+            title: saved.title.clone(),
+            state: "saved".to_string(),
+            loaded: false,
+            ephemeral: false,
+            namespace: saved.namespace.clone(),
+            usage: saved.usage,
+            created_at: saved.created_at,
+            updated_at: saved.updated_at,
+        }
+    }
+}
+
+/// Filters of GET /v1/sessions
+#[derive(Debug, Default, Deserialize)]
+pub struct SessionListQuery {
+    /// Only the sessions of this agent
+    pub agent: Option<String>,
+    /// Only the sessions in this state (e.g. running, paused, saved)
+    pub state: Option<String>,
+    /// Only the loaded (true) or only the saved (false) sessions
+    pub loaded: Option<bool>,
+    /// At most this many sessions, most recently updated first
+    pub limit: Option<usize>,
+}
+
+impl SessionListQuery {
+    pub fn matches(&self, session: &SessionObject) -> bool {
+        self.agent.as_ref().is_none_or(|agent| *agent == session.agent)
+            && self.state.as_ref().is_none_or(|state| *state == session.state)
+            && self.loaded.is_none_or(|loaded| loaded == session.loaded)
+    }
+
+    /// Saved sessions worth reading from the store for a caller of `namespace`
+    pub fn store_filter(&self, namespace: Option<&str>) -> Option<SessionFilter> {
+        if self.loaded == Some(true) || self.state.as_ref().is_some_and(|state| state != "saved") {
+            return None;
+        }
+        Some(SessionFilter {
+            namespace: namespace.map(str::to_string),
+            agent: self.agent.clone(),
+            created_before: None,
+            limit: self.limit,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionList {
+    pub object: &'static str,
+    pub data: Vec<SessionObject>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionTrace {
+    pub object: &'static str,
+    pub session_id: String,
+    pub data: Vec<ChatMessage>,
+}
+
+/// Query of GET /v1/sessions/{id}/events
+#[derive(Debug, Default, Deserialize)]
+pub struct EventsQuery {
+    /// Only the last events
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionEvents {
+    pub object: &'static str,
+    pub session_id: String,
+    pub data: Vec<StoredEvent>,
+}
+
+/// Query of POST /v1/sessions/{id}/fork
+#[derive(Debug, Default, Deserialize)]
+pub struct ForkQuery {
+    /// Id of the new session, a random one when missing
+    pub session_id: Option<String>,
+}
@@ -2,34 +2,42 @@ use axum::{
     extract::{Path, State},
     response::{IntoResponse, Response, Sse},
 };
-use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, ToolCall as LlmToolCall, Function};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use openai_dive::v1::resources::chat::{
+    ChatMessage, ChatMessageContent, ChatMessageContentPart, ChatMessageImageContentPart,
+    ChatMessageTextContentPart, Function, ImageUrlType, ToolCall as LlmToolCall,
+};
 use tracing::info;
 use uuid::Uuid;
 
-use super::types::{MultiModalQuery, Message};
+use super::types::{MultiModalQuery, Message, UserMessage};
 use super::formatter::SimpleFormatter;
-use crate::{session_to_sse_stream, ApiJson, ErrorResponse, ServerState};
+use crate::session::{SessionError, SessionWorkspace};
+use crate::{session_to_sse_stream, ApiJson, Caller, ErrorResponse, ServerState};
 
 /// Handle multimodal query without explicit session id (ephemeral session)
 pub async fn handle_multimodal_query_stream(
     State(state): State<ServerState>,
+    caller: Caller,
     ApiJson(payload): ApiJson<MultiModalQuery>,
 ) -> Result<Response, ErrorResponse> {
-    handle_multimodal_query_stream_internal(state, None, payload).await
+    handle_multimodal_query_stream_internal(state, caller, None, payload).await
 }
 
 /// Handle multimodal query with provided session id (persistent session)
 pub async fn handle_multimodal_query_stream_with_session(
     State(state): State<ServerState>,
+    caller: Caller,
     Path(session_id): Path<String>,
     ApiJson(payload): ApiJson<MultiModalQuery>,
 ) -> Result<Response, ErrorResponse> {
-    handle_multimodal_query_stream_internal(state, Some(session_id), payload).await
+    handle_multimodal_query_stream_internal(state, caller, Some(session_id), payload).await
 }
 
 /// Shared implementation for multimodal query handlers
 async fn handle_multimodal_query_stream_internal(
     state: ServerState,
+    caller: Caller,
     session_id_param: Option<String>,
     payload: MultiModalQuery,
 ) -> Result<Response, ErrorResponse> {
@@ -45,30 +53,35 @@ async fn handle_multimodal_query_stream_internal(
         request_id, session_id, payload.model, is_ephemeral
     );
 
-    // Build trace from query
-    let trace = build_message_trace(&payload);
+    caller.check_agent(&payload.model)?;
 
     // Get or create session agent
     let agent_session = if is_ephemeral {
         // Ephemeral -> create new session
         state.session_manager
-            .create_new_session(&request_id.to_string(), &session_id, Some(payload.model.clone()), is_ephemeral)
+            .create_new_session(&request_id.to_string(), &session_id, Some(payload.model.clone()), is_ephemeral, caller.namespace(), caller.sudo(&payload.model))
             .await
-            .map_err(|e| ErrorResponse::internal_error(format!("Failed to create session: {}", e)))?
+            .map_err(|e| ErrorResponse::from_session_error(e, |e| ErrorResponse::internal_error(format!("Failed to create session: {}", e))))?
     } else {
         // Persistent -> get existing (from memory or disk) or create new
-        match state.session_manager.get_session(&request_id.to_string(), &session_id, payload.model.clone()).await {
+        match state.session_manager.get_session(&request_id.to_string(), &session_id, Some(payload.model.as_str()), caller.namespace(), |agent| caller.sudo(agent)).await {
             Ok(session) => session,
+            // a saved session waiting for a free slot or running another agent must not be replaced by a new one
+            Err(SessionError::Limit(message)) => return Err(ErrorResponse::too_many_requests(message)),
+            Err(SessionError::AgentMismatch(message)) => return Err(ErrorResponse::invalid_request(message)),
             Err(_) => {
                 // Doesn't exist in memory or disk, create it
                 state.session_manager
-                    .create_new_session(&request_id.to_string(), &session_id, Some(payload.model.clone()), is_ephemeral)
+                    .create_new_session(&request_id.to_string(), &session_id, Some(payload.model.clone()), is_ephemeral, caller.namespace(), caller.sudo(&payload.model))
                     .await
-                    .map_err(|e| ErrorResponse::internal_error(format!("Failed to create session: {}", e)))?
+                    .map_err(|e| ErrorResponse::from_session_error(e, |e| ErrorResponse::internal_error(format!("Failed to create session: {}", e))))?
             }
         }
     };
 
+    // Build trace from query, attachments go to the session workspace
+    let trace = build_message_trace(&payload, agent_session.workspace()).await?;
+
     // Create request session
     let request_session = agent_session
         .handle_request(&request_id.to_string(), trace)
@@ -86,7 +99,7 @@ async fn handle_multimodal_query_stream_internal(
 
 
 /// Build message trace from query
-fn build_message_trace(query: &MultiModalQuery) -> Vec<ChatMessage> {
+async fn build_message_trace(query: &MultiModalQuery, workspace: &SessionWorkspace) -> Result<Vec<ChatMessage>, ErrorResponse> {
     let mut trace = Vec::new();
 
     if let Some(messages) = &query.messages {
@@ -94,7 +107,7 @@ fn build_message_trace(query: &MultiModalQuery) -> Vec<ChatMessage> {
             match msg {
                 Message::User(user_msg) => {
                     trace.push(ChatMessage::User {
-                        content: ChatMessageContent::Text(user_msg.message.clone()),
+                        content: user_content(user_msg, workspace).await?,
                         name: None,
                     });
                 }
@@ -148,5 +161,66 @@ fn build_message_trace(query: &MultiModalQuery) -> Vec<ChatMessage> {
         }
     }
 
-    trace
+    Ok(trace)
+}
+
+/// Text of a user message with its attachments: images become image parts (dropped by
+/// LlmClient for models without vision), other files are written to the session workspace
+/// and listed in the text so the agent can read them
+async fn user_content(msg: &UserMessage, workspace: &SessionWorkspace) -> Result<ChatMessageContent, ErrorResponse> {
+    let Some(files) = msg.attached_files.as_ref().filter(|files| !files.is_empty()) else {
+        return Ok(ChatMessageContent::Text(msg.message.clone()));
+    };
+
+    let mut names: Vec<&String> = files.keys().collect();
+    names.sort();
+
+    let mut images = Vec::new();
+    let mut written = Vec::new();
+    for name in names {
+        // plain base64 or a data URL
+        let data = files[name].trim();
+        let data = data.split_once(";base64,").map(|(_, data)| data).unwrap_or(data);
+
+        let content = BASE64_STANDARD.decode(data)
+            .map_err(|e| ErrorResponse::invalid_request(format!("Attached file {} is not valid base64: {}", name, e)))?;
+
+        if let Some(mime_type) = image_mime_type(name) {
+            images.push(ChatMessageContentPart::Image(ChatMessageImageContentPart {
+                r#type: "image_url".to_string(),
+                image_url: ImageUrlType { url: format!("data:{};base64,{}", mime_type, data), detail: None },
+            }));
+            continue;
+        }
+
+        let path = workspace.write_file(name, &content).await
+            .map_err(|e| ErrorResponse::internal_error(format!("Failed to write attached file {}: {}", name, e)))?;
+        written.push(path);
+    }
+
+    let mut text = msg.message.clone();
+    if !written.is_empty() {
+        text.push_str("\n\nAttached files:");
+        for path in &written {
+            text.push_str(&format!("\n- {}", path.display()));
+        }
+    }
+
+    if images.is_empty() {
+        return Ok(ChatMessageContent::Text(text));
+    }
+    let mut parts = vec![ChatMessageContentPart::Text(ChatMessageTextContentPart { r#type: "text".to_string(), text })];
+    parts.extend(images);
+    Ok(ChatMessageContent::ContentPart(parts))
+}
+
+fn image_mime_type(name: &str) -> Option<&'static str> {
+    let extension = name.rsplit('.').next()?.to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
 }
@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing::warn;
+
+use crate::ErrorResponse;
+
+/// An API key accepted by the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyConfig {
+    /// Name of the client, shown in the logs
+    pub name: String,
+    /// Bearer token the client sends in the Authorization header
+    pub key: String,
+    /// Agents the key may run ("default" for the default agent), all of them when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub agents: Vec<String>,
+    /// Keys of a namespace only see the sessions created in it, defaults to the key name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Overrides the server `sudo` list for the sessions created with this key, true for every agent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sudo: Option<bool>,
+    /// Sessions of the key namespace loaded at once, overrides `max_sessions_per_key`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_sessions: Option<usize>,
+}
+
+impl ApiKeyConfig {
+    pub fn namespace(&self) -> String {
+        self.namespace.clone().unwrap_or_else(|| self.name.clone())
+    }
+}
+
+/// Authentication, CORS and permission settings of `shai serve`, read from ~/.config/shai/serve.config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// No key means no authentication, only allowed on a loopback address
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+    /// Origins allowed to call the server from a browser, "*" for any
+    #[serde(default)]
+    pub cors_origins: Vec<String>,
+    /// Agents running without permission checks, "*" for all of them. The sessions of the
+    /// others stream their permission requests and wait for an HTTP client to answer them.
+    /// When missing, none once API keys are configured and all of them otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sudo: Option<Vec<String>>,
+    /// Sessions a key namespace may have loaded at once, unlimited when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_sessions_per_key: Option<usize>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            api_keys: Vec::new(),
+            cors_origins: Vec::new(),
+            sudo: None,
+            max_sessions_per_key: None,
+        }
+    }
+}
+
+impl AuthConfig {
+    /// A missing file is an empty config
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("invalid server config {}: {}", path.display(), e).into())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.api_keys.is_empty()
+    }
+
+    /// Agents running without permission checks, see `sudo`
+    fn sudo_agents(&self) -> Vec<String> {
+        match &self.sudo {
+            Some(agents) => agents.clone(),
+            None if self.is_enabled() => vec![],
+            None => vec!["*".to_string()],
+        }
+    }
+
+    /// Key holding this token, compared in constant time
+    fn find(&self, token: &str) -> Option<&ApiKeyConfig> {
+        self.api_keys.iter().find(|k| constant_time_eq(k.key.as_bytes(), token.as_bytes()))
+    }
+
+    /// Session quota of each namespace, keys sharing a namespace get the smallest of theirs
+    pub fn session_quotas(&self) -> HashMap<String, usize> {
+        let mut quotas: HashMap<String, usize> = HashMap::new();
+        for key in &self.api_keys {
+            if let Some(quota) = key.max_sessions.or(self.max_sessions_per_key) {
+                quotas.entry(key.namespace())
+                    .and_modify(|q| *q = (*q).min(quota))
+                    .or_insert(quota);
+            }
+        }
+        quotas
+    }
+
+    pub fn cors_layer(&self) -> CorsLayer {
+        if self.cors_origins.iter().any(|origin| origin == "*") {
+            return CorsLayer::permissive();
+        }
+        let origins: Vec<HeaderValue> = self.cors_origins.iter()
+            .filter_map(|origin| origin.parse().ok())
+            .collect();
+        // without origins no browser page can reach the server
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::list(origins))
+            .allow_methods(Any)
+            .allow_headers(Any)
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Who is calling: the matched API key, or anyone when authentication is disabled
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub name: String,
+    /// None sees every session (authentication disabled)
+    pub namespace: Option<String>,
+    agents: Vec<String>,
+    sudo: Vec<String>,
+}
+
+impl Caller {
+    pub fn anonymous(auth: &AuthConfig) -> Self {
+        Self { name: "anonymous".to_string(), namespace: None, agents: vec![], sudo: auth.sudo_agents() }
+    }
+
+    fn from_key(auth: &AuthConfig, key: &ApiKeyConfig) -> Self {
+        let sudo = match key.sudo {
+            Some(true) => vec!["*".to_string()],
+            Some(false) => vec![],
+            None => auth.sudo_agents(),
+        };
+        Self {
+            name: key.name.clone(),
+            namespace: Some(key.namespace()),
+            agents: key.agents.clone(),
+            sudo,
+        }
+    }
+
+    /// Whether the sessions this caller creates with `agent` skip the permission checks
+    pub fn sudo(&self, agent: &str) -> bool {
+        self.sudo.iter().any(|a| a == "*" || a == agent)
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// Sessions of another namespace are reported as missing, not forbidden
+    pub fn can_access(&self, owner: Option<&str>) -> bool {
+        can_access(self.namespace(), owner)
+    }
+
+    pub fn check_agent(&self, agent: &str) -> Result<(), ErrorResponse> {
+        if self.agents.is_empty() || self.agents.iter().any(|a| a == agent) {
+            Ok(())
+        } else {
+            Err(ErrorResponse::forbidden(format!("API key {} may not use agent {}", self.name, agent)))
+        }
+    }
+}
+
+/// Whether a caller namespace (None: unrestricted) may see what an owner namespace created
+pub fn can_access(namespace: Option<&str>, owner: Option<&str>) -> bool {
+    namespace.is_none() || namespace == owner
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Caller {
+    type Rejection = ErrorResponse;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Caller>()
+            .cloned()
+            .ok_or_else(|| ErrorResponse::unauthorized("Missing API key".to_string()))
+    }
+}
+
+/// Token of an `Authorization: Bearer <token>` header, the scheme is case-insensitive (RFC 7235)
+fn bearer_token(value: &str) -> Option<&str> {
+    let (scheme, token) = value.trim().split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then_some(token.trim())
+}
+
+/// Check the bearer token and hand the matching Caller to the handlers
+pub async fn authenticate(
+    State(auth): State<Arc<AuthConfig>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if !auth.is_enabled() {
+        request.extensions_mut().insert(Caller::anonymous(&auth));
+        return next.run(request).await;
+    }
+
+    let token = request.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(bearer_token);
+
+    match token.and_then(|token| auth.find(token)) {
+        Some(key) => {
+            request.extensions_mut().insert(Caller::from_key(&auth, key));
+            next.run(request).await
+        }
+        None => {
+            warn!("rejected {} {}: invalid or missing API key", request.method(), request.uri().path());
+            ErrorResponse::unauthorized("Invalid or missing API key".to_string()).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str, sudo: Option<bool>) -> ApiKeyConfig {
+        ApiKeyConfig {
+            name: name.to_string(),
+            key: format!("sk-{}", name),
+            agents: vec![],
+            namespace: None,
+            sudo,
+            max_sessions: None,
+        }
+    }
+
+    #[test]
+    fn test_anonymous_caller_is_sudo_without_keys() {
+        let auth = AuthConfig::default();
+        let caller = Caller::anonymous(&auth);
+        assert!(caller.sudo("default"));
+        assert!(caller.sudo("reviewer"));
+        assert_eq!(caller.namespace(), None);
+    }
+
+    #[test]
+    fn test_key_callers_are_supervised_by_default() {
+        let auth = AuthConfig { api_keys: vec![key("ci", None)], ..Default::default() };
+        let caller = Caller::from_key(&auth, &auth.api_keys[0]);
+        assert!(!caller.sudo("default"));
+        assert_eq!(caller.name, "ci");
+        assert_eq!(caller.namespace(), Some("ci"));
+    }
+
+    #[test]
+    fn test_sudo_agents_and_key_override() {
+        let mut shared = key("web", Some(false));
+        shared.namespace = Some("apps".to_string());
+        let auth = AuthConfig {
+            api_keys: vec![key("ci", None), key("admin", Some(true)), shared],
+            sudo: Some(vec!["reviewer".to_string()]),
+            ..Default::default()
+        };
+
+        let ci = Caller::from_key(&auth, &auth.api_keys[0]);
+        assert!(ci.sudo("reviewer"));
+        assert!(!ci.sudo("default"));
+
+        let admin = Caller::from_key(&auth, &auth.api_keys[1]);
+        assert!(admin.sudo("default"));
+
+        let web = Caller::from_key(&auth, &auth.api_keys[2]);
+        assert!(!web.sudo("reviewer"));
+        assert_eq!(web.namespace(), Some("apps"));
+    }
+
+    #[test]
+    fn test_bearer_scheme_is_case_insensitive() {
+        assert_eq!(bearer_token("Bearer abc"), Some("abc"));
+        assert_eq!(bearer_token("bearer abc"), Some("abc"));
+        assert_eq!(bearer_token("BEARER  abc "), Some("abc"));
+        assert_eq!(bearer_token("Basic abc"), None);
+        assert_eq!(bearer_token("Bearerabc"), None);
+    }
+
+    #[test]
+    fn test_missing_sudo_list_is_parsed_as_unset() {
+        let auth: AuthConfig = serde_json::from_str(r#"{ "api_keys": [{ "name": "ci", "key": "sk-ci" }] }"#).unwrap();
+        assert!(auth.sudo.is_none());
+        assert!(!Caller::from_key(&auth, &auth.api_keys[0]).sudo("default"));
+    }
+}
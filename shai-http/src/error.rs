@@ -6,6 +6,8 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use tracing::error;
 
+use crate::session::SessionError;
+
 /// Error response structure for API errors
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
@@ -39,9 +41,30 @@ impl ErrorResponse {
         Self::new(message, "invalid_request".to_string(), None)
     }
 
+    pub fn unauthorized(message: String) -> Self {
+        Self::new(message, "unauthorized".to_string(), Some("invalid_api_key".to_string()))
+    }
+
+    pub fn forbidden(message: String) -> Self {
+        Self::new(message, "forbidden".to_string(), None)
+    }
+
     pub fn internal_error(message: String) -> Self {
         Self::new(message, "internal_error".to_string(), None)
     }
+
+    pub fn too_many_requests(message: String) -> Self {
+        Self::new(message, "rate_limit_exceeded".to_string(), Some("too_many_sessions".to_string()))
+    }
+
+    /// Session manager errors: reached limits are reported as such, the others through `other`
+    pub fn from_session_error(e: SessionError, other: impl FnOnce(SessionError) -> Self) -> Self {
+        match e {
+            SessionError::Limit(message) => Self::too_many_requests(message),
+            SessionError::AgentMismatch(message) => Self::invalid_request(message),
+            e => other(e),
+        }
+    }
 }
 
 impl IntoResponse for ErrorResponse {
@@ -49,6 +72,9 @@ impl IntoResponse for ErrorResponse {
         let status = match self.error.r#type.as_str() {
             "not_found" => StatusCode::NOT_FOUND,
             "invalid_request" => StatusCode::BAD_REQUEST,
+            "unauthorized" => StatusCode::UNAUTHORIZED,
+            "forbidden" => StatusCode::FORBIDDEN,
+            "rate_limit_exceeded" => StatusCode::TOO_MANY_REQUESTS,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
         (status, Json(self)).into_response()
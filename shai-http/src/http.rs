@@ -2,12 +2,17 @@ use axum::{
     routing::{get, post},
     Router,
 };
+use std::net::{SocketAddr, ToSocketAddrs};
 use std::sync::Arc;
-use tower_http::cors::CorsLayer;
+use std::time::Duration;
 use tracing::info;
 
-use crate::session::{SessionManager, SessionManagerConfig};
+use crate::auth::{self, AuthConfig};
+use crate::metrics::{self, Metrics};
+use crate::session::{SessionManager, SessionManagerConfig, StoreMode, WorkspaceMode};
 use crate::apis;
+use crate::apis::health::Readiness;
+use crate::apis::openai::response::ResponseStore;
 
 /// Configuration for the HTTP server
 #[derive(Clone, Debug)]
@@ -16,6 +21,10 @@ pub struct ServerConfig {
     pub address: String,
     /// Session manager configuration
     pub session_manager: SessionManagerConfig,
+    /// API keys and CORS origins
+    pub auth: AuthConfig,
+    /// Serve without API keys on a non-loopback address
+    pub allow_unauthenticated: bool,
 }
 
 impl ServerConfig {
@@ -24,6 +33,8 @@ impl ServerConfig {
         Self {
             address,
             session_manager: SessionManagerConfig::default(),
+            auth: AuthConfig::default(),
+            allow_unauthenticated: false,
         }
     }
 
@@ -38,12 +49,68 @@ impl ServerConfig {
         self.session_manager.max_sessions = max_sessions;
         self
     }
+
+    /// Set where the tools of each session work
+    pub fn with_workspace(mut self, workspace: WorkspaceMode) -> Self {
+        self.session_manager.workspace = workspace;
+        self
+    }
+
+    /// Set where the sessions are saved
+    pub fn with_store(mut self, store: StoreMode) -> Self {
+        self.session_manager.store = store;
+        self
+    }
+
+    /// Save and unload the sessions idle for this long (None = never)
+    pub fn with_idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.session_manager.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Stop and delete the sessions older than this (None = never)
+    pub fn with_max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.session_manager.max_age = max_age;
+        self
+    }
+
+    /// Let up to `max_queued` requests wait `timeout` for a session when the server is full
+    pub fn with_queue(mut self, max_queued: usize, timeout: Duration) -> Self {
+        self.session_manager.max_queued = max_queued;
+        self.session_manager.queue_timeout = timeout;
+        self
+    }
+
+    /// Set the API keys and CORS origins
+    pub fn with_auth(mut self, auth: AuthConfig) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Allow serving without API keys on a non-loopback address
+    pub fn with_allow_unauthenticated(mut self, allow: bool) -> Self {
+        self.allow_unauthenticated = allow;
+        self
+    }
+}
+
+/// Whether every address the server binds to is only reachable from this machine
+fn is_loopback(address: &str) -> bool {
+    address.to_socket_addrs()
+        .map(|mut addrs| addrs.all(|addr: SocketAddr| addr.ip().is_loopback()))
+        .unwrap_or(false)
 }
 
 /// Server state holding the session manager
 #[derive(Clone)]
 pub struct ServerState {
     pub session_manager: Arc<SessionManager>,
+    /// Response objects of the Responses API, for GET /v1/responses/{id}
+    pub responses: ResponseStore,
+    /// Served by GET /metrics
+    pub metrics: Metrics,
+    /// Cached result of GET /readyz
+    pub readiness: Readiness,
 }
 
 
@@ -51,8 +118,22 @@ pub struct ServerState {
 pub async fn start_server(
     config: ServerConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Create session manager
-    let session_manager = SessionManager::new(config.session_manager.clone());
+    if !config.auth.is_enabled() && !config.allow_unauthenticated && !is_loopback(&config.address) {
+        return Err(format!(
+            "refusing to serve on {} without API keys, add api_keys to the server config or pass --no-auth",
+            config.address
+        ).into());
+    }
+
+    // Create session manager, with the session quotas of the API keys
+    let mut manager_config = config.session_manager.clone();
+    manager_config.session_quotas.extend(config.auth.session_quotas());
+    let store = config.session_manager.store.open()
+        .map_err(|e| format!("failed to open the session store {}: {}", config.session_manager.store, e))?;
+    let metrics = Metrics::default();
+    let responses = ResponseStore::new();
+    let session_manager = Arc::new(SessionManager::new(manager_config, store, metrics.clone(), responses.clone()));
+    tokio::spawn(session_manager.clone().run_eviction());
 
     println!("✓ Session manager initialized");
     if let Some(max) = config.session_manager.max_sessions {
@@ -61,10 +142,27 @@ pub async fn start_server(
         println!("  Max sessions: \x1b[1munlimited\x1b[0m");
     }
     println!("  Default mode: \x1b[1m{}\x1b[0m", if config.session_manager.ephemeral { "ephemeral" } else { "persistent" });
+    println!("  Workspaces: \x1b[1m{}\x1b[0m", config.session_manager.workspace);
+    println!("  Session store: \x1b[1m{}\x1b[0m", config.session_manager.store);
+    match config.session_manager.idle_timeout {
+        Some(idle) => println!("  Idle timeout: \x1b[1m{}s\x1b[0m", idle.as_secs()),
+        None => println!("  Idle timeout: \x1b[1mnone\x1b[0m"),
+    }
+    if let Some(max_age) = config.session_manager.max_age {
+        println!("  Max age: \x1b[1m{}s\x1b[0m", max_age.as_secs());
+    }
+    if config.auth.is_enabled() {
+        println!("  Auth: \x1b[1m{} API key(s)\x1b[0m", config.auth.api_keys.len());
+    } else {
+        println!("  Auth: \x1b[1mdisabled\x1b[0m");
+    }
     println!();
 
     let state = ServerState {
-        session_manager: Arc::new(session_manager),
+        session_manager,
+        responses,
+        metrics: metrics.clone(),
+        readiness: Readiness::default(),
     };
 
     let app = Router::new()
@@ -77,7 +175,24 @@ pub async fn start_server(
         .route("/v1/responses/{response_id}/cancel", post(apis::openai::handle_cancel_response))
         // OpenAI-compatible Chat Completion API
         .route("/v1/chat/completions", post(apis::openai::handle_chat_completion))
-        .layer(CorsLayer::permissive())
+        // Sessions
+        .route("/v1/sessions", get(apis::sessions::handle_list_sessions))
+        .route("/v1/sessions/{session_id}", get(apis::sessions::handle_get_session).delete(apis::sessions::handle_delete_session))
+        .route("/v1/sessions/{session_id}/trace", get(apis::sessions::handle_get_trace))
+        .route("/v1/sessions/{session_id}/events", get(apis::sessions::handle_get_events))
+        .route("/v1/sessions/{session_id}/fork", post(apis::sessions::handle_fork_session))
+        .route("/v1/sessions/{session_id}/permissions", get(apis::sessions::handle_list_permissions))
+        .route("/v1/sessions/{session_id}/permissions/{request_id}", post(apis::sessions::handle_answer_permission))
+        .route("/v1/sessions/{session_id}/queries", get(apis::sessions::handle_list_queries))
+        .route("/v1/sessions/{session_id}/queries/{request_id}", post(apis::sessions::handle_answer_query))
+        // Monitoring
+        .route("/metrics", get(apis::health::handle_metrics))
+        .layer(axum::middleware::from_fn_with_state(Arc::new(config.auth.clone()), auth::authenticate))
+        // Probes of the orchestrator, without API key
+        .route("/healthz", get(apis::health::handle_healthz))
+        .route("/readyz", get(apis::health::handle_readyz))
+        .layer(config.auth.cors_layer())
+        .layer(axum::middleware::from_fn_with_state(metrics, metrics::track_requests))
         .with_state(state);
 
     let listener = tokio::net::TcpListener::bind(&config.address).await?;
@@ -91,6 +206,19 @@ pub async fn start_server(
     println!("  \x1b[1mPOST /v1/responses/:id/cancel\x1b[0m        - Cancel a response");
     println!("  \x1b[1mPOST /v1/multimodal\x1b[0m                   - Simple multimodal API (streaming)");
     println!("  \x1b[1mPOST /v1/multimodal/:session_id\x1b[0m      - Simple multimodal API (with session)");
+    println!("  \x1b[1mGET  /v1/sessions\x1b[0m                     - List sessions (?agent=&state=&loaded=&limit=)");
+    println!("  \x1b[1mGET  /v1/sessions/:id\x1b[0m                 - Get a session");
+    println!("  \x1b[1mDELETE /v1/sessions/:id\x1b[0m               - Stop and delete a session");
+    println!("  \x1b[1mGET  /v1/sessions/:id/trace\x1b[0m           - Messages of a session");
+    println!("  \x1b[1mGET  /v1/sessions/:id/events\x1b[0m          - Event log of a session (?limit=)");
+    println!("  \x1b[1mPOST /v1/sessions/:id/fork\x1b[0m            - Fork a session (?session_id=)");
+    println!("  \x1b[1mGET  /v1/sessions/:id/permissions\x1b[0m     - Pending permission requests");
+    println!("  \x1b[1mPOST /v1/sessions/:id/permissions/:req\x1b[0m - Approve or deny a permission request");
+    println!("  \x1b[1mGET  /v1/sessions/:id/queries\x1b[0m         - Pending questions of the agent");
+    println!("  \x1b[1mPOST /v1/sessions/:id/queries/:req\x1b[0m    - Answer a question of the agent");
+    println!("  \x1b[1mGET  /healthz\x1b[0m                        - Liveness probe");
+    println!("  \x1b[1mGET  /readyz\x1b[0m                         - Readiness probe (the LLM provider lists its models)");
+    println!("  \x1b[1mGET  /metrics\x1b[0m                        - Prometheus metrics");
 
     // List available agents
     use shai_core::config::agent::AgentConfig;
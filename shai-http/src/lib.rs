@@ -3,8 +3,12 @@ pub mod apis;
 pub mod error;
 pub mod session;
 pub mod streaming;
+pub mod auth;
+pub mod metrics;
 
 pub use error::{ApiJson, ErrorResponse};
-pub use session::{SessionManager, SessionManagerConfig, AgentSession};
+pub use session::{SessionError, SessionManager, SessionManagerConfig, AgentSession, SessionStore, StoreMode, WorkspaceMode};
 pub use streaming::{EventFormatter, event_to_sse_stream, session_to_sse_stream};
-pub use http::{ServerConfig, ServerState, start_server};
\ No newline at end of file
+pub use http::{ServerConfig, ServerState, start_server};
+pub use auth::{ApiKeyConfig, AuthConfig, Caller};
+pub use metrics::Metrics;
\ No newline at end of file
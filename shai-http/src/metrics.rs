@@ -0,0 +1,251 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use shai_core::agent::{AgentEvent, PublicAgentState};
+use shai_core::tools::ToolResult;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+/// Upper bounds in seconds of the latency histogram buckets, tool calls and LLM calls both
+/// range from milliseconds to minutes
+const LATENCY_BUCKETS: [f64; 13] = [0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+
+/// Counters and histograms of the server, rendered in the Prometheus text format by GET /metrics
+/// The HTTP requests are counted by `track_requests`, the rest comes from the agent events
+#[derive(Clone, Default)]
+pub struct Metrics {
+    inner: Arc<Mutex<Registry>>,
+}
+
+#[derive(Default)]
+struct Registry {
+    /// (method, route, status)
+    http_requests: BTreeMap<(String, String, u16), u64>,
+    /// agent
+    agent_steps: BTreeMap<String, u64>,
+    /// (tool, outcome)
+    tool_calls: BTreeMap<(String, &'static str), u64>,
+    /// tool
+    tool_duration: BTreeMap<String, Histogram>,
+    /// model
+    llm_duration: BTreeMap<String, Histogram>,
+    /// (model, input or output)
+    tokens: BTreeMap<(String, &'static str), u64>,
+    /// model
+    cached_tokens: BTreeMap<String, u64>,
+    /// tool
+    permission_denials: BTreeMap<String, u64>,
+}
+
+#[derive(Default)]
+struct Histogram {
+    /// observations per bucket of LATENCY_BUCKETS, not cumulated
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+impl Metrics {
+    pub fn count_request(&self, method: &str, route: &str, status: u16) {
+        let mut registry = self.inner.lock().unwrap();
+        *registry.http_requests.entry((method.to_string(), route.to_string(), status)).or_default() += 1;
+    }
+
+    /// Tracker of the agent events of a session
+    pub fn session(&self, agent: &str, model: Option<&str>) -> SessionMetrics {
+        SessionMetrics {
+            metrics: self.clone(),
+            agent: agent.to_string(),
+            model: model.unwrap_or("unknown").to_string(),
+            thinking_since: None,
+        }
+    }
+
+    /// Prometheus text exposition of the metrics, `active_sessions` being read at scrape time
+    pub fn render(&self, active_sessions: usize) -> String {
+        let registry = self.inner.lock().unwrap();
+        let mut out = String::new();
+
+        header(&mut out, "shai_sessions_active", "gauge", "Sessions loaded in memory");
+        let _ = writeln!(out, "shai_sessions_active {}", active_sessions);
+
+        header(&mut out, "shai_http_requests_total", "counter", "HTTP requests by method, route and status");
+        for ((method, route, status), count) in &registry.http_requests {
+            let _ = writeln!(out, "shai_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                escape(method), escape(route), status, count);
+        }
+
+        header(&mut out, "shai_agent_steps_total", "counter", "LLM steps of the agents");
+        for (agent, count) in &registry.agent_steps {
+            let _ = writeln!(out, "shai_agent_steps_total{{agent=\"{}\"}} {}", escape(agent), count);
+        }
+
+        header(&mut out, "shai_tool_calls_total", "counter", "Tool calls by tool and outcome (success, error, denied)");
+        for ((tool, outcome), count) in &registry.tool_calls {
+            let _ = writeln!(out, "shai_tool_calls_total{{tool=\"{}\",outcome=\"{}\"}} {}", escape(tool), outcome, count);
+        }
+
+        header(&mut out, "shai_tool_duration_seconds", "histogram", "Duration of the tool calls");
+        for (tool, histogram) in &registry.tool_duration {
+            write_histogram(&mut out, "shai_tool_duration_seconds", &format!("tool=\"{}\"", escape(tool)), histogram);
+        }
+
+        header(&mut out, "shai_llm_duration_seconds", "histogram", "Duration of the LLM calls, retries included");
+        for (model, histogram) in &registry.llm_duration {
+            write_histogram(&mut out, "shai_llm_duration_seconds", &format!("model=\"{}\"", escape(model)), histogram);
+        }
+
+        header(&mut out, "shai_llm_tokens_total", "counter", "Tokens sent to (input) and generated by (output) the LLMs");
+        for ((model, direction), count) in &registry.tokens {
+            let _ = writeln!(out, "shai_llm_tokens_total{{model=\"{}\",direction=\"{}\"}} {}", escape(model), direction, count);
+        }
+
+        header(&mut out, "shai_llm_cached_tokens_total", "counter", "Input tokens read from the provider prompt cache");
+        for (model, count) in &registry.cached_tokens {
+            let _ = writeln!(out, "shai_llm_cached_tokens_total{{model=\"{}\"}} {}", escape(model), count);
+        }
+
+        header(&mut out, "shai_permission_denials_total", "counter", "Tool calls denied by the user");
+        for (tool, count) in &registry.permission_denials {
+            let _ = writeln!(out, "shai_permission_denials_total{{tool=\"{}\"}} {}", escape(tool), count);
+        }
+
+        out
+    }
+}
+
+/// Feeds the metrics from the events of one session, in the session logging task
+pub struct SessionMetrics {
+    metrics: Metrics,
+    agent: String,
+    model: String,
+    /// Start of the running LLM call
+    thinking_since: Option<DateTime<Utc>>,
+}
+
+impl SessionMetrics {
+    pub fn track(&mut self, event: &AgentEvent) {
+        match event {
+            AgentEvent::StatusChanged { new_status: PublicAgentState::Processing { task_name, tools_exec_at }, .. }
+                if task_name == "next_step" =>
+            {
+                self.thinking_since = Some(*tools_exec_at);
+            }
+            AgentEvent::BrainResult { timestamp, .. } => {
+                let mut registry = self.metrics.inner.lock().unwrap();
+                *registry.agent_steps.entry(self.agent.clone()).or_default() += 1;
+                if let Some(since) = self.thinking_since.take() {
+                    let seconds = (*timestamp - since).num_milliseconds().max(0) as f64 / 1000.0;
+                    registry.llm_duration.entry(self.model.clone()).or_default().observe(seconds);
+                }
+            }
+            AgentEvent::ToolCallCompleted { duration, call, result } => {
+                let outcome = match result {
+                    ToolResult::Success { .. } => "success",
+                    ToolResult::Error { .. } => "error",
+                    ToolResult::Denied => "denied",
+                };
+                let mut registry = self.metrics.inner.lock().unwrap();
+                *registry.tool_calls.entry((call.tool_name.clone(), outcome)).or_default() += 1;
+                let seconds = duration.num_milliseconds().max(0) as f64 / 1000.0;
+                registry.tool_duration.entry(call.tool_name.clone()).or_default().observe(seconds);
+                if let ToolResult::Denied = result {
+                    *registry.permission_denials.entry(call.tool_name.clone()).or_default() += 1;
+                }
+            }
+            AgentEvent::TokenUsage { input_tokens, output_tokens, cached_tokens } => {
+                let mut registry = self.metrics.inner.lock().unwrap();
+                *registry.tokens.entry((self.model.clone(), "input")).or_default() += *input_tokens as u64;
+                *registry.tokens.entry((self.model.clone(), "output")).or_default() += *output_tokens as u64;
+                *registry.cached_tokens.entry(self.model.clone()).or_default() += *cached_tokens as u64;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Middleware counting the requests by route pattern, so that session ids do not become labels
+pub async fn track_requests(State(metrics): State<Metrics>, request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request.extensions().get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+    metrics.count_request(&method, &route, response.status().as_u16());
+    response
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn write_histogram(out: &mut String, name: &str, labels: &str, histogram: &Histogram) {
+    let mut cumulated = 0;
+    for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+        cumulated += count;
+        let _ = writeln!(out, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, bound, cumulated);
+    }
+    let _ = writeln!(out, "{}_bucket{{{},le=\"+Inf\"}} {}", name, labels, histogram.count);
+    let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, histogram.sum);
+    let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, histogram.count);
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let metrics = Metrics::default();
+        {
+            let mut registry = metrics.inner.lock().unwrap();
+            let histogram = registry.tool_duration.entry("bash".to_string()).or_default();
+            histogram.observe(0.02);
+            histogram.observe(0.07);
+            histogram.observe(0.08);
+            histogram.observe(400.0);
+        }
+
+        let out = metrics.render(0);
+        assert!(out.contains("shai_tool_duration_seconds_bucket{tool=\"bash\",le=\"0.01\"} 0\n"));
+        assert!(out.contains("shai_tool_duration_seconds_bucket{tool=\"bash\",le=\"0.05\"} 1\n"));
+        assert!(out.contains("shai_tool_duration_seconds_bucket{tool=\"bash\",le=\"0.1\"} 3\n"));
+        assert!(out.contains("shai_tool_duration_seconds_bucket{tool=\"bash\",le=\"300\"} 3\n"));
+        assert!(out.contains("shai_tool_duration_seconds_bucket{tool=\"bash\",le=\"+Inf\"} 4\n"));
+        assert!(out.contains("shai_tool_duration_seconds_count{tool=\"bash\"} 4\n"));
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        let metrics = Metrics::default();
+        metrics.count_request("GET", "/a\"b\\c\nd", 200);
+        metrics.count_request("GET", "/a\"b\\c\nd", 200);
+
+        let out = metrics.render(2);
+        assert!(out.contains("shai_sessions_active 2\n"));
+        assert!(out.contains(r#"shai_http_requests_total{method="GET",route="/a\"b\\c\nd",status="200"} 2"#));
+        // every sample stays on its own line
+        assert!(out.lines().all(|line| line.starts_with('#') || line.starts_with("shai_")));
+    }
+}
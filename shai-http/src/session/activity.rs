@@ -0,0 +1,121 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shai_core::agent::{AgentEvent, PublicAgentState};
+use std::sync::{Arc, Mutex};
+
+/// Tokens used by a session since it was created
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct TokenTotals {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// part of input_tokens read from the provider prompt cache
+    pub cached_tokens: u64,
+}
+
+impl TokenTotals {
+    /// Count the tokens of a TokenUsage event, other events are ignored
+    pub fn add(&mut self, event: &AgentEvent) {
+        if let AgentEvent::TokenUsage { input_tokens, output_tokens, cached_tokens } = event {
+            self.input_tokens += *input_tokens as u64;
+            self.output_tokens += *output_tokens as u64;
+            self.cached_tokens += *cached_tokens as u64;
+        }
+    }
+}
+
+/// Last known state, title, token totals and timestamps of a session, shown by GET /v1/sessions
+#[derive(Clone)]
+pub struct SessionActivity {
+    inner: Arc<Mutex<ActivityState>>,
+}
+
+struct ActivityState {
+    state: &'static str,
+    title: Option<String>,
+    usage: TokenTotals,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl Default for SessionActivity {
+    fn default() -> Self {
+        Self::restore(Utc::now(), None, TokenTotals::default())
+    }
+}
+
+impl SessionActivity {
+    /// Activity of a saved session, keeping its creation date, title and token totals
+    pub fn restore(created_at: DateTime<Utc>, title: Option<String>, usage: TokenTotals) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(ActivityState {
+                state: "starting",
+                title,
+                usage,
+                created_at,
+                updated_at: Utc::now(),
+            })),
+        }
+    }
+
+    /// Follow the agent events
+    pub fn track(&self, event: &AgentEvent) {
+        let mut inner = self.inner.lock().unwrap();
+        match event {
+            AgentEvent::StatusChanged { new_status, .. } => {
+                inner.state = state_name(new_status);
+            }
+            AgentEvent::TokenUsage { .. } => {
+                inner.usage.add(event);
+            }
+            _ => {}
+        }
+        inner.updated_at = Utc::now();
+    }
+
+    /// Whether both belong to the same session instance, a session id can be loaded again
+    /// once unloaded
+    pub fn is_same(&self, other: &SessionActivity) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    pub fn state(&self) -> &'static str {
+        self.inner.lock().unwrap().state
+    }
+
+    pub fn title(&self) -> Option<String> {
+        self.inner.lock().unwrap().title.clone()
+    }
+
+    /// Title the session from its first request, a session keeps its first title
+    pub fn set_title(&self, title: Option<String>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.title.is_none() {
+            inner.title = title;
+        }
+    }
+
+    pub fn usage(&self) -> TokenTotals {
+        self.inner.lock().unwrap().usage
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.inner.lock().unwrap().created_at
+    }
+
+    /// Time of the last agent event
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.inner.lock().unwrap().updated_at
+    }
+}
+
+pub(crate) fn state_name(state: &PublicAgentState) -> &'static str {
+    match state {
+        PublicAgentState::Starting => "starting",
+        PublicAgentState::Running => "running",
+        PublicAgentState::Processing { .. } => "processing",
+        PublicAgentState::Paused => "paused",
+        PublicAgentState::Completed { .. } => "completed",
+        PublicAgentState::Cancelled => "cancelled",
+        PublicAgentState::Failed { .. } => "failed",
+    }
+}
@@ -1,9 +1,11 @@
+use std::sync::Arc;
 use shai_core::agent::AgentController;
 use tokio::sync::OwnedMutexGuard;
 use tracing::{info, warn};
 
 use crate::session::logger::colored_session_id;
-use crate::session::persist::SessionPersist;
+use crate::session::{AgentSession, SessionActivity, SessionStore};
+use super::store::blocking;
 
 
 pub enum RequestLifecycle {
@@ -11,19 +13,32 @@ pub enum RequestLifecycle {
         controller_guard: OwnedMutexGuard<AgentController>,
         request_id: String,
         session_id: String,
+        namespace: Option<String>,
+        agent_name: String,
+        activity: SessionActivity,
+        store: Arc<dyn SessionStore>,
     },
     Ephemeral {
         controller_guard: OwnedMutexGuard<AgentController>,
         request_id: String,
         session_id: String,
+        namespace: Option<String>,
+        agent_name: String,
+        activity: SessionActivity,
+        store: Arc<dyn SessionStore>,
     },
 }
 
 impl RequestLifecycle {
-    pub fn new(ephemeral: bool, controller_guard: OwnedMutexGuard<AgentController>, request_id: String, session_id: String) -> Self {
-        match ephemeral {
-            true => Self::Ephemeral { controller_guard, request_id, session_id },
-            false => Self::Background { controller_guard, request_id, session_id },
+    pub fn new(session: &AgentSession, controller_guard: OwnedMutexGuard<AgentController>, request_id: String) -> Self {
+        let session_id = session.session_id.clone();
+        let namespace = session.namespace.clone();
+        let agent_name = session.agent_name.clone();
+        let activity = session.activity().clone();
+        let store = session.store().clone();
+        match session.ephemeral {
+            true => Self::Ephemeral { controller_guard, request_id, session_id, namespace, agent_name, activity, store },
+            false => Self::Background { controller_guard, request_id, session_id, namespace, agent_name, activity, store },
         }
     }
 }
@@ -31,20 +46,26 @@ impl RequestLifecycle {
 impl Drop for RequestLifecycle {
     fn drop(&mut self) {
         match self {
-            Self::Background { controller_guard, request_id, session_id } => {
+            Self::Background { controller_guard, request_id, session_id, namespace, agent_name, activity, store } => {
                 info!(
                     "[{}] - {} Stream completed, releasing controller lock (background session)",
                     request_id,
                     colored_session_id(session_id)
                 );
 
-                // Save session to disk (async)
+                // Save session to the store (async)
                 let ctrl = controller_guard.clone();
                 let sid = session_id.clone();
+                let ns = namespace.clone();
+                let agent = agent_name.clone();
+                let usage = activity.usage();
+                let store = store.clone();
                 tokio::spawn(async move {
                     match ctrl.get_trace().await {
                         Ok(trace) => {
-                            if let Err(e) = SessionPersist::save_session(&sid, trace) {
+                            let id = sid.clone();
+                            let saved = blocking(&store, move |store| store.save_session(&id, ns.as_deref(), Some(&agent), usage, trace)).await;
+                            if let Err(e) = saved {
                                 warn!("Failed to save session {}: {}", sid, e);
                             }
                         }
@@ -54,7 +75,7 @@ impl Drop for RequestLifecycle {
                     }
                 });
             }
-            Self::Ephemeral { controller_guard, request_id, session_id } => {
+            Self::Ephemeral { controller_guard, request_id, session_id, namespace, agent_name, activity, store } => {
                 info!(
                     "[{}] - {} Stream completed, destroying agent (ephemeral session)",
                     request_id,
@@ -64,11 +85,17 @@ impl Drop for RequestLifecycle {
                 // Clone before moving into async task
                 let ctrl = controller_guard.clone();
                 let sid = session_id.clone();
+                let ns = namespace.clone();
+                let agent = agent_name.clone();
+                let usage = activity.usage();
+                let store = store.clone();
                 tokio::spawn(async move {
-                    // Save session to disk
+                    // Save session to the store
                     match ctrl.get_trace().await {
                         Ok(trace) => {
-                            if let Err(e) = SessionPersist::save_session(&sid, trace) {
+                            let id = sid.clone();
+                            let saved = blocking(&store, move |store| store.save_session(&id, ns.as_deref(), Some(&agent), usage, trace)).await;
+                            if let Err(e) = saved {
                                 warn!("Failed to save session {}: {}", sid, e);
                             }
                         }
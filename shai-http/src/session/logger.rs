@@ -1,7 +1,7 @@
 use std::hash::{Hash, Hasher};
 use std::collections::hash_map::DefaultHasher;
 use shai_core::agent::AgentEvent;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 fn color_for_session(session_id: &str) -> u8 {
     let mut hasher = DefaultHasher::new();
@@ -54,6 +54,10 @@ pub fn log_event(event: &AgentEvent, session_id: &str) {
             info!("{} - Completed: success={} msg={}", 
                 session_id, success, message);
         }
+        AgentEvent::LlmRetry { attempt, max_retries, delay, reason } => {
+            warn!("{} - {}, retrying in {:?} ({}/{})", 
+                session_id, reason, delay, attempt, max_retries);
+        }
         _ => {}
     }
 }
\ No newline at end of file
@@ -1,48 +1,186 @@
+use chrono::{DateTime, Utc};
 use shai_core::agent::{Agent, AgentError};
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use tracing::{error, info};
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{Mutex, MutexGuard, Notify};
+use tracing::{error, info, warn};
 use openai_dive::v1::resources::chat::ChatMessage;
 
 use shai_core::agent::AgentBuilder;
 use crate::session::{log_event, logger::colored_session_id};
-use crate::session::persist::SessionPersist;
+use crate::apis::openai::response::ResponseStore;
+use crate::auth::can_access;
+use crate::metrics::Metrics;
 
-use super::AgentSession;
+use super::store;
+use super::{
+    AgentSession, PendingPermissions, PendingQueries, SessionActivity, SessionData, SessionFilter, SessionInfo, SessionStore,
+    SessionWorkspace, StoreError, StoreMode, StoredEvent, TokenTotals, WorkspaceMode,
+};
+
+/// How often idle and expired sessions are looked for
+const EVICTION_INTERVAL: Duration = Duration::from_secs(30);
 
 /// Configuration for the session manager
 #[derive(Clone, Debug)]
 pub struct SessionManagerConfig {
     /// Maximum number of concurrent sessions (None = unlimited)
     pub max_sessions: Option<usize>,
+    /// Requests waiting for a free session when max_sessions is reached, the next ones are refused
+    pub max_queued: usize,
+    /// How long a queued request waits for a free session
+    pub queue_timeout: Duration,
+    /// Maximum number of concurrent sessions per API key namespace
+    pub session_quotas: HashMap<String, usize>,
+    /// Background sessions without activity for this long are saved and unloaded, they are
+    /// loaded again on their next request (None = kept until the agent stops)
+    pub idle_timeout: Option<Duration>,
+    /// Sessions older than this are stopped and deleted, in memory and in the store (None = no limit)
+    pub max_age: Option<Duration>,
     /// Whether sessions are ephemeral or background (ephemeral session is destroyed after a single query)
     pub ephemeral: bool,
+    /// Where the tools of each session work
+    pub workspace: WorkspaceMode,
+    /// Where the sessions are saved
+    pub store: StoreMode,
 }
 
 impl Default for SessionManagerConfig {
     fn default() -> Self {
         Self {
             max_sessions: Some(100),
+            max_queued: 32,
+            queue_timeout: Duration::from_secs(30),
+            session_quotas: HashMap::new(),
+            idle_timeout: Some(Duration::from_secs(30 * 60)),
+            max_age: None,
             ephemeral: false,
+            workspace: WorkspaceMode::default(),
+            store: StoreMode::default(),
         }
     }
 }
 
+/// Why the manager could not hand out a session
+#[derive(Debug)]
+pub enum SessionError {
+    /// A session limit was reached: the server is full (even after waiting in the queue)
+    /// or the namespace used its quota
+    Limit(String),
+    /// The request names another agent than the one the session runs
+    AgentMismatch(String),
+    Agent(AgentError),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Limit(message) | Self::AgentMismatch(message) => write!(f, "{}", message),
+            Self::Agent(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<AgentError> for SessionError {
+    fn from(e: AgentError) -> Self {
+        Self::Agent(e)
+    }
+}
+
+type Sessions = HashMap<String, Arc<AgentSession>>;
+
+/// A request waiting for a free session, counted until dropped
+struct QueueTicket(Arc<AtomicUsize>);
+
+impl Drop for QueueTicket {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 /// Session manager - manages multiple agent sessions by ID
 /// Handles creation, deletion, and access control for sessions
 pub struct SessionManager {
-    sessions: Arc<Mutex<HashMap<String, Arc<AgentSession>>>>,
-    max_sessions: Option<usize>,
-    ephemeral: bool
+    sessions: Arc<Mutex<Sessions>>,
+    config: SessionManagerConfig,
+    store: Arc<dyn SessionStore>,
+    metrics: Metrics,
+    /// Last response objects of the sessions, removed with them
+    responses: ResponseStore,
+    /// Signaled when a session leaves the manager, for the queued requests
+    slot_freed: Arc<Notify>,
+    queued: Arc<AtomicUsize>,
 }
 
 impl SessionManager {
-    pub fn new(config: SessionManagerConfig) -> Self {
+    /// `store` is the store opened from `config.store`, `metrics` are fed from the agent events
+    pub fn new(config: SessionManagerConfig, store: Arc<dyn SessionStore>, metrics: Metrics, responses: ResponseStore) -> Self {
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
-            max_sessions: config.max_sessions,
-            ephemeral: config.ephemeral
+            config,
+            store,
+            metrics,
+            responses,
+            slot_freed: Arc::new(Notify::new()),
+            queued: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Wait until one more session of `namespace` may be loaded and return the locked sessions
+    /// A full server queues the request up to queue_timeout, a namespace over its quota is refused
+    async fn reserve_slot(&self, namespace: Option<&str>) -> Result<MutexGuard<'_, Sessions>, SessionError> {
+        let deadline = tokio::time::Instant::now() + self.config.queue_timeout;
+        let mut ticket = None;
+        loop {
+            // registered before looking at the sessions so that no release is missed
+            let slot_freed = self.slot_freed.notified();
+            tokio::pin!(slot_freed);
+            slot_freed.as_mut().enable();
+
+            let sessions = self.sessions.lock().await;
+
+            if let Some(namespace) = namespace {
+                if let Some(quota) = self.config.session_quotas.get(namespace) {
+                    let used = sessions.values().filter(|s| s.namespace.as_deref() == Some(namespace)).count();
+                    if used >= *quota {
+                        return Err(SessionError::Limit(format!(
+                            "Maximum number of sessions reached for {}: {}",
+                            namespace, quota
+                        )));
+                    }
+                }
+            }
+
+            match self.config.max_sessions {
+                Some(max) if sessions.len() >= max => {}
+                _ => return Ok(sessions),
+            }
+            drop(sessions);
+
+            if ticket.is_none() {
+                if self.queued.fetch_add(1, Ordering::SeqCst) >= self.config.max_queued {
+                    self.queued.fetch_sub(1, Ordering::SeqCst);
+                    return Err(SessionError::Limit(format!(
+                        "Maximum number of sessions reached: {}",
+                        self.config.max_sessions.unwrap_or_default()
+                    )));
+                }
+                ticket = Some(QueueTicket(self.queued.clone()));
+            }
+
+            if tokio::time::timeout_at(deadline, slot_freed).await.is_err() {
+                return Err(SessionError::Limit(format!(
+                    "Maximum number of sessions reached: {}, no session was freed within {}s",
+                    self.config.max_sessions.unwrap_or_default(),
+                    self.config.queue_timeout.as_secs()
+                )));
+            }
         }
     }
 
@@ -52,30 +190,68 @@ impl SessionManager {
         session_id: &str,
         agent_name: Option<String>,
         ephemeral: bool,
+        namespace: Option<String>,
+        sudo: bool,
         trace: Option<Vec<ChatMessage>>,
+        activity: SessionActivity,
+        workspace: SessionWorkspace,
     ) -> Result<Arc<AgentSession>, AgentError> {
-        info!("[{}] - {} Creating new session", http_request_id, colored_session_id(session_id));
+        info!("[{}] - {} Creating new session (sudo={})", http_request_id, colored_session_id(session_id), sudo);
 
         // Build the agent with optional trace
         let mut builder = AgentBuilder::create(agent_name.clone().filter(|name| name != "default"))
             .await
-            .map_err(|e| AgentError::ExecutionError(format!("Failed to create agent: {}", e)))?
-            .sudo();
+            .map_err(|e| AgentError::ExecutionError(format!("Failed to create agent: {}", e)))?;
+
+        // without sudo, tools needing permission wait for POST /v1/sessions/{id}/permissions/{request_id}
+        if sudo {
+            builder = builder.sudo();
+        }
 
         if let Some(trace) = trace {
             builder = builder.with_traces(trace);
         }
 
+        if workspace.is_isolated() {
+            builder = builder.workspace(workspace.root())?;
+        }
+
+        let mut metrics = self.metrics.session(agent_name.as_deref().unwrap_or("default"), builder.model.as_deref());
         let mut agent = builder.build();
 
         let controller = agent.controller();
-        let event_rx = agent.watch();
+        let mut event_for_logger = agent.watch();
 
-        // Spawn logging task alongside agent
-        let mut event_for_logger = event_rx.resubscribe();
+        // Spawn logging task alongside agent, it also keeps track of the pending permission requests
+        // and questions and of the session activity, fills the event log of the store and feeds the
+        // metrics. The clients get the events from it once tracked, so that a request they see
+        // can be answered right away
+        let (session_tx, event_rx) = broadcast::channel(1024);
         let sid_for_logger = session_id.to_string();
+        let permissions = PendingPermissions::new(session_id);
+        let permissions_for_logger = permissions.clone();
+        let queries = PendingQueries::new(session_id);
+        let queries_for_logger = queries.clone();
+        let activity_for_logger = activity.clone();
+        let store_for_logger = self.store.clone();
         let logging_task = tokio::spawn(async move {
-            while let Ok(event) = event_for_logger.recv().await {
+            loop {
+                let event = match event_for_logger.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("{} - {} events were not logged", colored_session_id(&sid_for_logger), skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                permissions_for_logger.track(&event);
+                queries_for_logger.track(&event);
+                activity_for_logger.track(&event);
+                metrics.track(&event);
+                let _ = session_tx.send(event.clone());
+                if let Some(stored) = StoredEvent::from_event(&event) {
+                    store_for_logger.log_event(&sid_for_logger, &stored);
+                }
                 log_event(&event, &sid_for_logger);
             }
         });
@@ -83,6 +259,10 @@ impl SessionManager {
         // Spawn agent task with cleanup logic
         let sessions_for_cleanup = self.sessions.clone();
         let sid_for_cleanup = session_id.to_string();
+        let activity_for_cleanup = activity.clone();
+        let slot_freed = self.slot_freed.clone();
+        // without a store the session cannot come back once its agent stopped
+        let workspace_to_remove = (!self.store.is_persistent()).then(|| self.config.workspace.clone());
         let agent_task = tokio::spawn(async move {
             match agent.run().await {
                 Ok(_) => {
@@ -92,8 +272,20 @@ impl SessionManager {
                     error!("{} - Agent execution error: {}", colored_session_id(&sid_for_cleanup), e);
                 }
             }
-            sessions_for_cleanup.lock().await.remove(&sid_for_cleanup);
-            info!("{} - Session removed from manager", colored_session_id(&sid_for_cleanup));
+            // the id may already belong to another instance, loaded again after this one was unloaded
+            let removed = {
+                let mut sessions = sessions_for_cleanup.lock().await;
+                let current = sessions.get(&sid_for_cleanup).is_some_and(|s| s.activity().is_same(&activity_for_cleanup));
+                if current {
+                    sessions.remove(&sid_for_cleanup);
+                    slot_freed.notify_waiters();
+                    info!("{} - Session removed from manager", colored_session_id(&sid_for_cleanup));
+                }
+                current
+            };
+            if let Some(mode) = workspace_to_remove.filter(|_| removed) {
+                SessionWorkspace::remove(&sid_for_cleanup, &mode).await;
+            }
         });
 
         let session = Arc::new(AgentSession::new(
@@ -104,60 +296,103 @@ impl SessionManager {
             agent_task,
             agent_name,
             ephemeral,
+            namespace,
+            permissions,
+            queries,
+            activity,
+            workspace,
+            self.store.clone(),
         ));
 
         Ok(session)
     }
 
     /// Get an existing session by ID
-    /// If not in memory, attempts to load from disk using the provided agent_name
-    /// Returns error if session doesn't exist in memory or on disk
+    /// If not in memory, attempts to load it from the store with the agent it was saved with,
+    /// `sudo` telling whether that agent runs without permission requests
+    /// Returns error if session doesn't exist in memory or in the store, or belongs to another namespace,
+    /// and AgentMismatch if `agent_name` is given and is not the agent of the session
     pub async fn get_session(
         &self,
         http_request_id: &str,
         session_id: &str,
-        agent_name: String,
-    ) -> Result<Arc<AgentSession>, AgentError> {
+        agent_name: Option<&str>,
+        namespace: Option<&str>,
+        sudo: impl FnOnce(&str) -> bool,
+    ) -> Result<Arc<AgentSession>, SessionError> {
+        let not_found = || SessionError::Agent(AgentError::ExecutionError(format!("Session not found: {}", session_id)));
+        let check_agent = |session_agent: &str| match agent_name {
+            Some(name) if name != session_agent => Err(SessionError::AgentMismatch(format!(
+                "Session {} runs agent '{}', not '{}'",
+                session_id, session_agent, name
+            ))),
+            _ => Ok(()),
+        };
+
         // First check in-memory sessions
         {
             let sessions = self.sessions.lock().await;
             if let Some(session) = sessions.get(session_id) {
+                if !can_access(namespace, session.namespace.as_deref()) {
+                    return Err(not_found());
+                }
+                check_agent(&session.agent_name)?;
                 info!("[{}] - {} Using existing in-memory session", http_request_id, colored_session_id(&session_id));
                 return Ok(session.clone());
             }
         }
 
-        // Try to load from disk
-        match SessionPersist::load_session(session_id) {
+        // Try to load from the store
+        match self.load_saved(session_id).await {
+            Ok(session_data) if !can_access(namespace, session_data.info.namespace.as_deref()) => Err(not_found()),
             Ok(session_data) => {
-                info!("[{}] - {} Loading session from disk", http_request_id, colored_session_id(session_id));
+                let saved_agent = session_data.info.agent_name.clone().unwrap_or_else(|| "default".to_string());
+                check_agent(&saved_agent)?;
+
+                let workspace = self.prepare_workspace(session_id).await?;
+                let new_workspace = workspace.is_new();
+                let loaded: Result<Arc<AgentSession>, SessionError> = async {
+                    let mut sessions = self.reserve_slot(session_data.info.namespace.as_deref()).await?;
+
+                    // loaded by a concurrent request while waiting
+                    if let Some(session) = sessions.get(session_id) {
+                        return Ok(session.clone());
+                    }
 
-                // Restore the session with the saved trace
-                let session = self.create_session(
-                    &http_request_id.to_string(),
-                    session_id,
-                    Some(agent_name),
-                    false, // Loaded sessions are not ephemeral
-                    Some(session_data.trace), // Initialize with saved trace
-                ).await?;
+                    info!("[{}] - {} Loading session from the store", http_request_id, colored_session_id(session_id));
 
-                // Store in manager
-                let mut sessions = self.sessions.lock().await;
-                sessions.insert(session_id.to_string(), session.clone());
+                    // Restore the session with the saved trace
+                    let session = self.create_session(
+                        &http_request_id.to_string(),
+                        session_id,
+                        Some(saved_agent.clone()),
+                        false, // Loaded sessions are not ephemeral
+                        session_data.info.namespace,
+                        sudo(&saved_agent),
+                        Some(session_data.trace), // Initialize with saved trace
+                        SessionActivity::restore(session_data.info.created_at, session_data.info.title, session_data.info.usage),
+                        workspace,
+                    ).await?;
 
-                Ok(session)
+                    // Store in manager
+                    sessions.insert(session_id.to_string(), session.clone());
+
+                    Ok(session)
+                }.await;
+
+                if loaded.is_err() {
+                    self.discard_workspace(session_id, new_workspace).await;
+                }
+                loaded
             }
             Err(e) => {
-                error!("Failed to load session {} from disk: {}", session_id, e);
-                Err(AgentError::ExecutionError(format!(
-                    "Session not found: {}",
-                    session_id
-                )))
+                error!("Failed to load session {} from the store: {}", session_id, e);
+                Err(not_found())
             }
         }
     }
 
-    /// Create a new session with the given ID
+    /// Create a new session with the given ID, owned by `namespace`
     /// Returns error if session already exists
     pub async fn create_new_session(
         &self,
@@ -165,48 +400,349 @@ impl SessionManager {
         session_id: &str,
         agent_name: Option<String>,
         ephemeral: bool,
-    ) -> Result<Arc<AgentSession>, AgentError> {
-        let mut sessions = self.sessions.lock().await;
-
-        // Check if session already exists
-        if sessions.contains_key(session_id) {
-            return Err(AgentError::ExecutionError(format!(
-                "Session already exists: {}",
-                session_id
-            )));
-        }
-
-        // Check max sessions limit
-        if let Some(max) = self.max_sessions {
-            if sessions.len() >= max {
-                return Err(AgentError::ExecutionError(format!(
-                    "Maximum number of sessions reached: {}",
-                    max
-                )));
-            }
-        }
+        namespace: Option<&str>,
+        sudo: bool,
+    ) -> Result<Arc<AgentSession>, SessionError> {
+        let already_exists = || SessionError::Agent(AgentError::ExecutionError(format!(
+            "Session already exists: {}",
+            session_id
+        )));
 
-         // Check max sessions limit
-        if self.ephemeral && !ephemeral {
-            return Err(AgentError::ExecutionError(format!(
+        if self.config.ephemeral && !ephemeral {
+            return Err(SessionError::Agent(AgentError::ExecutionError(format!(
                 "Only Ephemeral session are authorized on this server"
-            )));
+            ))));
+        }
+
+        // A saved session of another namespace must not be overwritten
+        if let Ok(saved) = self.load_saved(session_id).await {
+            if !can_access(namespace, saved.info.namespace.as_deref()) {
+                return Err(already_exists());
+            }
         }
 
-        let session = self.create_session(&http_request_id.to_string(), session_id, agent_name, ephemeral, None).await?;
-        sessions.insert(session_id.to_string(), session.clone());
+        let workspace = self.prepare_workspace(session_id).await?;
+        let new_workspace = workspace.is_new();
+        let created: Result<Arc<AgentSession>, SessionError> = async {
+            // Wait for a free session if the server is full
+            let mut sessions = self.reserve_slot(namespace).await?;
 
-        Ok(session)
+            // Check if session already exists
+            if sessions.contains_key(session_id) {
+                return Err(already_exists());
+            }
+
+            let session = self.create_session(&http_request_id.to_string(), session_id, agent_name, ephemeral, namespace.map(str::to_string), sudo, None, SessionActivity::default(), workspace).await?;
+            sessions.insert(session_id.to_string(), session.clone());
+
+            Ok(session)
+        }.await;
+
+        if created.is_err() {
+            self.discard_workspace(session_id, new_workspace).await;
+        }
+        created
     }
 
     /// Cancel a session (stop the agent)
-    pub async fn cancel_session(&self, http_request_id: &String, session_id: &str) -> Result<(), AgentError> {
-        if let Some(session) = self.sessions.lock().await.get(session_id) {
+    pub async fn cancel_session(&self, http_request_id: &String, session_id: &str, namespace: Option<&str>) -> Result<(), AgentError> {
+        if let Some(session) = self.find_session(session_id, namespace).await {
             session.cancel(http_request_id).await?;
         }
         Ok(())
     }
 
+    /// In-memory session of `namespace`, without loading it from the store
+    pub async fn find_session(&self, session_id: &str, namespace: Option<&str>) -> Option<Arc<AgentSession>> {
+        self.sessions.lock().await.get(session_id).cloned()
+            .filter(|session| can_access(namespace, session.namespace.as_deref()))
+    }
+
+    /// In-memory sessions visible to `namespace`
+    pub async fn loaded_sessions(&self, namespace: Option<&str>) -> Vec<Arc<AgentSession>> {
+        self.sessions.lock().await.values()
+            .filter(|session| can_access(namespace, session.namespace.as_deref()))
+            .cloned()
+            .collect()
+    }
+
+    /// Sessions of the store matching `filter` (its namespace being the caller one), that are
+    /// not loaded in memory
+    pub async fn saved_sessions(&self, filter: &SessionFilter) -> Vec<SessionInfo> {
+        let filter = filter.clone();
+        let saved = store::blocking(&self.store, move |store| store.list_sessions(&filter)).await;
+        let sessions = self.sessions.lock().await;
+        saved
+            .into_iter()
+            .filter(|saved| !sessions.contains_key(&saved.session_id))
+            .collect()
+    }
+
+    /// Trace, agent and activity of a session, read from the running agent when loaded
+    /// and from the store otherwise, without loading it
+    pub async fn snapshot_session(&self, session_id: &str, namespace: Option<&str>) -> Result<SessionData, AgentError> {
+        let not_found = || AgentError::ExecutionError(format!("Session not found: {}", session_id));
+
+        if let Some(session) = self.find_session(session_id, namespace).await {
+            let activity = session.activity();
+            return Ok(SessionData {
+                info: SessionInfo {
+                    session_id: session.session_id.clone(),
+                    created_at: activity.created_at(),
+                    updated_at: activity.updated_at(),
+                    namespace: session.namespace.clone(),
+                    agent_name: Some(session.agent_name.clone()),
+                    title: activity.title(),
+                    usage: activity.usage(),
+                },
+                trace: session.trace().await?,
+            });
+        }
+
+        match self.load_saved(session_id).await {
+            Ok(saved) if can_access(namespace, saved.info.namespace.as_deref()) => Ok(saved),
+            _ => Err(not_found()),
+        }
+    }
+
+    /// Last `limit` events of the event log of a session, oldest first
+    pub async fn session_events(&self, session_id: &str, namespace: Option<&str>, limit: Option<usize>) -> Result<Vec<StoredEvent>, AgentError> {
+        let visible = match self.find_session(session_id, namespace).await {
+            Some(_) => true,
+            None => self.load_saved(session_id).await
+                .is_ok_and(|saved| can_access(namespace, saved.info.namespace.as_deref())),
+        };
+        if !visible {
+            return Err(AgentError::ExecutionError(format!("Session not found: {}", session_id)));
+        }
+        let session_id = session_id.to_string();
+        Ok(store::blocking(&self.store, move |store| store.events(&session_id, limit)).await)
+    }
+
+    /// Delete a session: stop its agent if loaded and remove it from the store
+    /// Returns error if the session is neither in memory nor in the store for `namespace`
+    pub async fn delete_session(&self, http_request_id: &String, session_id: &str, namespace: Option<&str>) -> Result<(), AgentError> {
+        let loaded = {
+            let mut sessions = self.sessions.lock().await;
+            match sessions.get(session_id) {
+                Some(session) if can_access(namespace, session.namespace.as_deref()) => sessions.remove(session_id),
+                _ => None,
+            }
+        };
+        let saved = self.load_saved(session_id).await.ok()
+            .filter(|saved| can_access(namespace, saved.info.namespace.as_deref()));
+
+        if loaded.is_none() && saved.is_none() {
+            return Err(AgentError::ExecutionError(format!("Session not found: {}", session_id)));
+        }
+
+        // the agent may already have stopped on its own
+        if let Some(session) = loaded {
+            self.slot_freed.notify_waiters();
+            if let Err(e) = session.terminate(http_request_id).await {
+                error!("[{}] - {} Failed to terminate agent: {}", http_request_id, colored_session_id(session_id), e);
+            }
+        }
+        self.delete_saved(session_id).await;
+        self.responses.remove(session_id).await;
+        SessionWorkspace::remove(session_id, &self.config.workspace).await;
+        info!("[{}] - {} Session deleted", http_request_id, colored_session_id(session_id));
+        Ok(())
+    }
+
+    /// Start a new background session from the trace of `source`, in the same namespace and
+    /// with the same agent. The fork is saved right away and has its own token totals
+    pub async fn fork_session(
+        &self,
+        http_request_id: &str,
+        source: SessionData,
+        new_session_id: &str,
+        sudo: bool,
+    ) -> Result<Arc<AgentSession>, SessionError> {
+        let already_exists = || SessionError::Agent(AgentError::ExecutionError(format!(
+            "Session already exists: {}",
+            new_session_id
+        )));
+
+        if self.load_saved(new_session_id).await.is_ok() {
+            return Err(already_exists());
+        }
+
+        // the fork starts from the files of its source
+        let workspace = SessionWorkspace::fork(&source.info.session_id, new_session_id, &self.config.workspace)
+            .await
+            .map_err(|e| AgentError::ExecutionError(format!("Failed to fork workspace ({}): {}", self.config.workspace, e)))?;
+        let new_workspace = workspace.is_new();
+        let trace = source.trace.clone();
+        let forked: Result<Arc<AgentSession>, SessionError> = async {
+            let mut sessions = self.reserve_slot(source.info.namespace.as_deref()).await?;
+
+            if sessions.contains_key(new_session_id) {
+                return Err(already_exists());
+            }
+
+            info!("[{}] - {} Forking session {}", http_request_id, colored_session_id(new_session_id), source.info.session_id);
+
+            let session = self.create_session(
+                &http_request_id.to_string(),
+                new_session_id,
+                source.info.agent_name,
+                false,
+                source.info.namespace,
+                sudo,
+                Some(source.trace),
+                SessionActivity::default(),
+                workspace,
+            ).await?;
+            sessions.insert(new_session_id.to_string(), session.clone());
+
+            Ok(session)
+        }.await;
+
+        let session = match forked {
+            Ok(session) => session,
+            Err(e) => {
+                self.discard_workspace(new_session_id, new_workspace).await;
+                return Err(e);
+            }
+        };
+
+        let saved = self.save(new_session_id, session.namespace.clone(), Some(session.agent_name.clone()), TokenTotals::default(), trace).await;
+        if let Err(e) = saved {
+            error!("Failed to save forked session {}: {}", new_session_id, e);
+        }
+
+        Ok(session)
+    }
+
+    /// Unload the idle sessions and remove the expired ones until the server stops
+    pub async fn run_eviction(self: Arc<Self>) {
+        if self.config.idle_timeout.is_none() && self.config.max_age.is_none() {
+            return;
+        }
+        let mut interval = tokio::time::interval(EVICTION_INTERVAL);
+        loop {
+            interval.tick().await;
+            self.evict().await;
+        }
+    }
+
+    async fn evict(&self) {
+        let now = Utc::now();
+        let older_than = |time: DateTime<Utc>, limit: Option<Duration>| {
+            limit.is_some_and(|limit| (now - time).to_std().unwrap_or_default() > limit)
+        };
+        // unloading a session that cannot be saved would lose it
+        let idle_timeout = self.config.idle_timeout.filter(|_| self.store.is_persistent());
+
+        // sessions running a request or held by a handler are left for the next round
+        let evicted: Vec<(Arc<AgentSession>, bool)> = {
+            let mut sessions = self.sessions.lock().await;
+            let ids: Vec<(String, bool)> = sessions.iter()
+                .filter(|(_, session)| Arc::strong_count(session) == 1 && !session.is_busy())
+                .filter_map(|(id, session)| {
+                    let activity = session.activity();
+                    if older_than(activity.created_at(), self.config.max_age) {
+                        Some((id.clone(), true))
+                    } else if !session.is_ephemeral() && older_than(activity.updated_at(), idle_timeout) {
+                        Some((id.clone(), false))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            ids.into_iter()
+                .filter_map(|(id, expired)| sessions.remove(&id).map(|session| (session, expired)))
+                .collect()
+        };
+
+        if !evicted.is_empty() {
+            self.slot_freed.notify_waiters();
+        }
+
+        let eviction_id = "eviction".to_string();
+        for (session, expired) in evicted {
+            let sid = colored_session_id(&session.session_id);
+            if expired {
+                self.delete_saved(&session.session_id).await;
+                self.responses.remove(&session.session_id).await;
+                info!("{} - Session expired", sid);
+            } else {
+                match session.trace().await {
+                    Ok(trace) => {
+                        let activity = session.activity();
+                        let saved = self.save(&session.session_id, session.namespace.clone(), Some(session.agent_name.clone()), activity.usage(), trace).await;
+                        if let Err(e) = saved {
+                            warn!("Failed to save session {}: {}", session.session_id, e);
+                        }
+                    }
+                    Err(e) => warn!("Failed to get trace for session {}: {}", session.session_id, e),
+                }
+                info!("{} - Session unloaded after being idle", sid);
+            }
+            if let Err(e) = session.terminate(&eviction_id).await {
+                warn!("{} - Failed to terminate agent: {}", sid, e);
+            }
+            if expired {
+                SessionWorkspace::remove(&session.session_id, &self.config.workspace).await;
+            }
+        }
+
+        // saved sessions past their age
+        if let Some(max_age) = self.config.max_age.and_then(|max_age| chrono::Duration::from_std(max_age).ok()) {
+            let filter = SessionFilter { created_before: Some(now - max_age), ..Default::default() };
+            for saved in self.saved_sessions(&filter).await {
+                self.delete_saved(&saved.session_id).await;
+                self.responses.remove(&saved.session_id).await;
+                SessionWorkspace::remove(&saved.session_id, &self.config.workspace).await;
+                info!("{} - Saved session expired", colored_session_id(&saved.session_id));
+            }
+        }
+    }
+
+    /// Directory of a session about to start, prepared before waiting for a slot so that the
+    /// sessions lock is not held while a template is copied or a worktree added
+    async fn prepare_workspace(&self, session_id: &str) -> Result<SessionWorkspace, AgentError> {
+        SessionWorkspace::create(session_id, &self.config.workspace)
+            .await
+            .map_err(|e| AgentError::ExecutionError(format!("Failed to create workspace ({}): {}", self.config.workspace, e)))
+    }
+
+    /// Remove the directory of a session that failed to start if it was created for it, unless
+    /// a concurrent request started a session with the same id meanwhile
+    async fn discard_workspace(&self, session_id: &str, new_workspace: bool) {
+        if new_workspace && !self.sessions.lock().await.contains_key(session_id) {
+            SessionWorkspace::remove(session_id, &self.config.workspace).await;
+        }
+    }
+
+    // the store calls run on the blocking thread pool, never under the sessions lock
+
+    async fn load_saved(&self, session_id: &str) -> Result<SessionData, StoreError> {
+        let session_id = session_id.to_string();
+        store::blocking(&self.store, move |store| store.load_session(&session_id)).await
+    }
+
+    async fn save(
+        &self,
+        session_id: &str,
+        namespace: Option<String>,
+        agent_name: Option<String>,
+        usage: TokenTotals,
+        trace: Vec<ChatMessage>,
+    ) -> Result<(), StoreError> {
+        let session_id = session_id.to_string();
+        store::blocking(&self.store, move |store| {
+            store.save_session(&session_id, namespace.as_deref(), agent_name.as_deref(), usage, trace)
+        })
+        .await
+    }
+
+    async fn delete_saved(&self, session_id: &str) {
+        let session_id = session_id.to_string();
+        store::blocking(&self.store, move |store| store.delete_session(&session_id)).await
+    }
+
     /// Get the number of active sessions
     pub async fn session_count(&self) -> usize {
         self.sessions.lock().await.len()
@@ -2,11 +2,17 @@ mod lifecycle;
 mod session;
 mod manager;
 mod logger;
-mod persist;
+mod store;
+mod permissions;
+mod workspace;
+mod activity;
 
 pub use logger::log_event;
 pub use lifecycle::{RequestLifecycle};
 pub use session::{AgentSession, RequestSession};
-pub use manager::{SessionManager, SessionManagerConfig};
-pub use persist::{SessionPersist, SessionData};
+pub use manager::{SessionError, SessionManager, SessionManagerConfig};
+pub use store::{session_title, FileStore, NoStore, SessionData, SessionFilter, SessionInfo, SessionStore, SqliteStore, StoreError, StoreMode, StoredEvent};
+pub use permissions::{PendingPermission, PendingPermissions, PendingQueries, PendingQuery};
+pub use workspace::{SessionWorkspace, WorkspaceMode};
+pub use activity::{SessionActivity, TokenTotals};
 
@@ -0,0 +1,154 @@
+use serde::Serialize;
+use serde_json::Value;
+use shai_core::agent::{AgentEvent, PermissionRequest, UserRequest};
+use shai_core::tools::mcp::USER_RESPONSE_TIMEOUT;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// A permission request as shown to HTTP clients, in the `permission_required` SSE event
+/// and in GET /v1/sessions/{id}/permissions
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingPermission {
+    pub request_id: String,
+    pub session_id: String,
+    pub tool: String,
+    pub operation: String,
+    pub arguments: Value,
+    /// What the tool would do (e.g. the diff of an edit), when it can tell
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+}
+
+impl PendingPermission {
+    pub fn new(session_id: &str, request_id: &str, request: &PermissionRequest) -> Self {
+        Self {
+            request_id: request_id.to_string(),
+            session_id: session_id.to_string(),
+            tool: request.tool_name.clone(),
+            operation: request.operation.clone(),
+            arguments: request.call.parameters.clone(),
+            preview: request.preview.as_ref().map(|preview| preview.to_string()),
+        }
+    }
+}
+
+/// Permission requests of a session waiting for an HTTP client to answer them
+#[derive(Clone)]
+pub struct PendingPermissions {
+    session_id: String,
+    requests: Arc<Mutex<HashMap<String, PermissionRequest>>>,
+}
+
+impl PendingPermissions {
+    pub fn new(session_id: &str) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            requests: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Follow the agent events: a request is pending until answered or until its tool call ends
+    /// (cancelled, or the agent stopped waiting)
+    pub fn track(&self, event: &AgentEvent) {
+        let mut requests = self.requests.lock().unwrap();
+        match event {
+            AgentEvent::PermissionRequired { request_id, request } => {
+                requests.insert(request_id.clone(), request.clone());
+            }
+            AgentEvent::ToolCallCompleted { call, .. } => {
+                requests.retain(|_, request| request.call.tool_call_id != call.tool_call_id);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn list(&self) -> Vec<PendingPermission> {
+        self.requests.lock().unwrap()
+            .iter()
+            .map(|(id, request)| PendingPermission::new(&self.session_id, id, request))
+            .collect()
+    }
+
+    /// Remove a request to answer it, None if it is not (or no longer) pending
+    pub fn take(&self, request_id: &str) -> Option<PermissionRequest> {
+        self.requests.lock().unwrap().remove(request_id)
+    }
+}
+
+/// A question of the agent (MCP sampling approval or elicitation) as shown to HTTP clients, in
+/// the `user_input_required` SSE event and in GET /v1/sessions/{id}/queries
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingQuery {
+    pub request_id: String,
+    pub session_id: String,
+    pub request: UserRequest,
+}
+
+/// Questions of a session waiting for an HTTP client to answer them
+#[derive(Clone)]
+pub struct PendingQueries {
+    session_id: String,
+    requests: Arc<Mutex<HashMap<String, (UserRequest, Instant)>>>,
+}
+
+impl PendingQueries {
+    pub fn new(session_id: &str) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            requests: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// A question is pending until answered or until the agent stops waiting for it
+    pub fn track(&self, event: &AgentEvent) {
+        if let AgentEvent::UserInputRequired { request_id, request } = event {
+            let mut requests = self.requests.lock().unwrap();
+            requests.retain(|_, (_, asked)| asked.elapsed() < USER_RESPONSE_TIMEOUT);
+            requests.insert(request_id.clone(), (request.clone(), Instant::now()));
+        }
+    }
+
+    pub fn list(&self) -> Vec<PendingQuery> {
+        self.requests.lock().unwrap()
+            .iter()
+            .filter(|(_, (_, asked))| asked.elapsed() < USER_RESPONSE_TIMEOUT)
+            .map(|(id, (request, _))| PendingQuery {
+                request_id: id.clone(),
+                session_id: self.session_id.clone(),
+                request: request.clone(),
+            })
+            .collect()
+    }
+
+    /// Remove a question to answer it, None if it is not (or no longer) pending
+    pub fn take(&self, request_id: &str) -> Option<UserRequest> {
+        self.requests.lock().unwrap()
+            .remove(request_id)
+            .filter(|(_, asked)| asked.elapsed() < USER_RESPONSE_TIMEOUT)
+            .map(|(request, _)| request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_queries() {
+        let queries = PendingQueries::new("session");
+        queries.track(&AgentEvent::UserInputRequired {
+            request_id: "q1".to_string(),
+            request: UserRequest::Confirmation { prompt: "Let the server sample?".to_string() },
+        });
+
+        let pending = queries.list();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].request_id, "q1");
+        assert_eq!(pending[0].session_id, "session");
+
+        assert!(queries.take("q1").is_some());
+        assert!(queries.take("q1").is_none());
+        assert!(queries.list().is_empty());
+    }
+}
@@ -1,4 +1,5 @@
-use shai_core::agent::{AgentController, AgentError, AgentEvent};
+use shai_core::agent::{AgentController, AgentError, AgentEvent, PermissionResponse, UserResponse};
+use shai_core::tools::{AnyTool, ExternalTool};
 use openai_dive::v1::resources::chat::ChatMessage;
 use std::sync::Arc;
 use tokio::sync::{broadcast::Receiver, Mutex};
@@ -6,7 +7,7 @@ use tokio::task::JoinHandle;
 use tracing::info;
 use crate::session::logger::colored_session_id;
 
-use super::RequestLifecycle;
+use super::{session_title, PendingPermission, PendingPermissions, PendingQueries, PendingQuery, RequestLifecycle, SessionActivity, SessionStore, SessionWorkspace};
 
 
 /// Represents a single HTTP request session with automatic lifecycle management
@@ -23,6 +24,15 @@ pub struct RequestSession {
 /// - In ephemeral mode (ephemeral=true), the entire session stops and is deleted once the query ends or the client disconnect
 pub struct AgentSession {
     controller: Arc<Mutex<AgentController>>,
+    /// Unguarded handle to answer permission requests and questions while a request holds the controller
+    remote: AgentController,
+    permissions: PendingPermissions,
+    queries: PendingQueries,
+    activity: SessionActivity,
+    workspace: SessionWorkspace,
+    /// Where the session is saved after each request
+    store: Arc<dyn SessionStore>,
+    /// Agent events relayed by the logging task after it tracked them
     event_rx: Receiver<AgentEvent>,
     logging_task: JoinHandle<()>,
     agent_task: JoinHandle<()>,
@@ -30,6 +40,8 @@ pub struct AgentSession {
     pub session_id: String,
     pub agent_name: String,
     pub ephemeral: bool,
+    /// API key namespace the session was created in, None when authentication is disabled
+    pub namespace: Option<String>,
 }
 
 impl AgentSession {
@@ -41,10 +53,22 @@ impl AgentSession {
         logging_task: JoinHandle<()>,
         agent_name: Option<String>,
         ephemeral: bool,
+        namespace: Option<String>,
+        permissions: PendingPermissions,
+        queries: PendingQueries,
+        activity: SessionActivity,
+        workspace: SessionWorkspace,
+        store: Arc<dyn SessionStore>,
     ) -> Self {
         let agent_name_display = agent_name.unwrap_or_else(|| "default".to_string());
 
         Self {
+            remote: controller.clone(),
+            permissions,
+            queries,
+            activity,
+            workspace,
+            store,
             controller: Arc::new(Mutex::new(controller)),
             event_rx,
             logging_task,
@@ -52,6 +76,7 @@ impl AgentSession {
             session_id,
             agent_name: agent_name_display,
             ephemeral: ephemeral,
+            namespace,
         }
     }
 
@@ -62,6 +87,27 @@ impl AgentSession {
         ctrl.terminate().await
     }
 
+    /// Stop the agent now, without waiting for the request holding the session
+    pub async fn terminate(&self, http_request_id: &String) -> Result<(), AgentError> {
+        info!("[{}] - {} terminating session", http_request_id, colored_session_id(&self.session_id));
+        self.remote.terminate().await
+    }
+
+    /// Current trace of the agent, also while a request is running
+    pub async fn trace(&self) -> Result<Vec<ChatMessage>, AgentError> {
+        self.remote.get_trace().await
+    }
+
+    /// State, token totals and timestamps of the session
+    pub fn activity(&self) -> &SessionActivity {
+        &self.activity
+    }
+
+    /// Where the session is saved
+    pub fn store(&self) -> &Arc<dyn SessionStore> {
+        &self.store
+    }
+
     /// Subscribe to events from this session (read-only, non-blocking)
     /// Used for GET /v1/responses/{response_id} to observe an ongoing session
     pub fn watch(&self) -> Receiver<AgentEvent> {
@@ -75,15 +121,70 @@ impl AgentSession {
         controller_guard.wait_turn(None).await?;
         info!("[{}] - {} handling request", http_request_id, colored_session_id(&self.session_id));
 
+        self.activity.set_title(session_title(&trace));
         controller_guard.send_trace(trace).await?;
 
         let event_rx = self.event_rx.resubscribe();
         let controller = controller_guard.clone();
-        let lifecycle = RequestLifecycle::new(self.ephemeral, controller_guard, http_request_id.clone(), self.session_id.clone());
+        let lifecycle = RequestLifecycle::new(self, controller_guard, http_request_id.clone());
 
         Ok(RequestSession{controller, event_rx, lifecycle})
     }
 
+    /// Add the tools a client runs itself, they replace the agent tools of the same name
+    pub async fn add_external_tools(&self, tools: Vec<ExternalTool>) -> Result<(), AgentError> {
+        if tools.is_empty() {
+            return Ok(());
+        }
+        let names = tools.iter().map(|tool| tool.name.clone()).collect();
+        self.remote.remove_tools(names).await?;
+        self.remote.add_tools(tools.into_iter().map(|tool| Box::new(tool) as Box<dyn AnyTool>).collect()).await?;
+        Ok(())
+    }
+
+    /// Where the files attached by the clients are written, and where the tools work when isolated
+    pub fn workspace(&self) -> &SessionWorkspace {
+        &self.workspace
+    }
+
+    /// Permission requests waiting for an answer
+    pub fn pending_permissions(&self) -> Vec<PendingPermission> {
+        self.permissions.list()
+    }
+
+    /// Answer a pending permission request, AllowAlways also lifts the permission checks
+    /// for the rest of the session
+    pub async fn respond_permission(&self, http_request_id: &String, request_id: &str, response: PermissionResponse) -> Result<(), AgentError> {
+        let request = self.permissions.take(request_id).ok_or_else(|| {
+            AgentError::ExecutionError(format!("Permission request not found: {}", request_id))
+        })?;
+        info!("[{}] - {} permission {:?} for {}", http_request_id, colored_session_id(&self.session_id), response, request.tool_name);
+
+        if matches!(response, PermissionResponse::AllowAlways) {
+            self.remote.sudo().await?;
+        }
+        self.remote.response_permission_request(request_id.to_string(), response).await
+    }
+
+    /// Questions of the agent waiting for an answer
+    pub fn pending_queries(&self) -> Vec<PendingQuery> {
+        self.queries.list()
+    }
+
+    /// Answer a pending question of the agent
+    pub async fn respond_query(&self, http_request_id: &String, request_id: &str, response: UserResponse) -> Result<(), AgentError> {
+        self.queries.take(request_id).ok_or_else(|| {
+            AgentError::ExecutionError(format!("Query not found: {}", request_id))
+        })?;
+        info!("[{}] - {} answer {:?} to query {}", http_request_id, colored_session_id(&self.session_id), response, request_id);
+        self.remote.response_user_query(request_id.to_string(), response).await
+    }
+
+    /// Whether a request is running on the session
+    pub fn is_busy(&self) -> bool {
+        self.controller.try_lock().is_err()
+    }
+
     pub fn is_ephemeral(&self) -> bool {
         self.ephemeral
     }
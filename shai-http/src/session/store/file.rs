@@ -0,0 +1,152 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+use chrono::Utc;
+use openai_dive::v1::resources::chat::ChatMessage;
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+use super::{session_title, SessionData, SessionFilter, SessionInfo, SessionStore, StoreError};
+use crate::session::TokenTotals;
+
+/// One JSON file per session in a directory. Listing reads every file and there is no event log,
+/// the SQLite store is meant for servers with many sessions
+pub struct FileStore {
+    folder: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(folder: PathBuf) -> Self {
+        Self { folder }
+    }
+
+    /// Get the file path for a specific session
+    fn session_file_path(&self, session_id: &str) -> PathBuf {
+        self.folder.join(format!("{}.json", session_id))
+    }
+}
+
+impl SessionStore for FileStore {
+    /// Save a session to disk (atomic write using temp file)
+    fn save_session(
+        &self,
+        session_id: &str,
+        namespace: Option<&str>,
+        agent_name: Option<&str>,
+        usage: TokenTotals,
+        trace: Vec<ChatMessage>,
+    ) -> Result<(), StoreError> {
+        // Create directory if it doesn't exist
+        if let Err(e) = fs::create_dir_all(&self.folder) {
+            error!("Failed to create session directory: {}", e);
+            return Err(e.into());
+        }
+
+        let file_path = self.session_file_path(session_id);
+
+        // Load existing data to preserve created_at, or create new
+        let (created_at, updated_at) = if file_path.exists() {
+            match fs::read_to_string(&file_path) {
+                Ok(content) => {
+                    match serde_json::from_str::<SessionData>(&content) {
+                        Ok(existing) => (existing.info.created_at, Utc::now()),
+                        Err(_) => (Utc::now(), Utc::now()),
+                    }
+                }
+                Err(_) => (Utc::now(), Utc::now()),
+            }
+        } else {
+            (Utc::now(), Utc::now())
+        };
+
+        let session_data = SessionData {
+            info: SessionInfo {
+                session_id: session_id.to_string(),
+                created_at,
+                updated_at,
+                namespace: namespace.map(str::to_string),
+                agent_name: agent_name.map(str::to_string),
+                title: session_title(&trace),
+                usage,
+            },
+            trace,
+        };
+
+        // Serialize to JSON
+        let json = serde_json::to_string_pretty(&session_data)?;
+
+        // Atomic write: write to temp file, then rename
+        let temp_path = self.folder.join(format!("{}.tmp", Uuid::new_v4()));
+        fs::write(&temp_path, json)?;
+        fs::rename(&temp_path, &file_path)?;
+
+        debug!("Session saved to disk: {}", file_path.display());
+        Ok(())
+    }
+
+    /// Load a single session from disk by session_id
+    fn load_session(&self, session_id: &str) -> Result<SessionData, StoreError> {
+        let file_path = self.session_file_path(session_id);
+
+        // If file doesn't exist, return error
+        if !file_path.exists() {
+            debug!("Session file does not exist: {}", file_path.display());
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("Session file not found: {}", session_id),
+            )
+            .into());
+        }
+
+        // Read and parse the session file
+        let content = fs::read_to_string(&file_path)?;
+        let session_data: SessionData = serde_json::from_str(&content)?;
+
+        debug!("Loaded session from disk: {}", session_id);
+        Ok(session_data)
+    }
+
+    /// Read every session file, skipping the ones that fail to parse
+    fn list_sessions(&self, filter: &SessionFilter) -> Vec<SessionInfo> {
+        let Ok(entries) = fs::read_dir(&self.folder) else {
+            return vec![];
+        };
+
+        let mut sessions: Vec<SessionInfo> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let parsed = fs::read_to_string(&path)
+                    .map_err(StoreError::from)
+                    .and_then(|content| Ok(serde_json::from_str::<SessionData>(&content)?));
+                match parsed {
+                    Ok(session_data) => Some(session_data.info),
+                    Err(e) => {
+                        warn!("Skipping session file {}: {}", path.display(), e);
+                        None
+                    }
+                }
+            })
+            .filter(|info| filter.matches(info))
+            .collect();
+
+        sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        if let Some(limit) = filter.limit {
+            sessions.truncate(limit);
+        }
+        sessions
+    }
+
+    /// Delete a session file from disk
+    fn delete_session(&self, session_id: &str) {
+        let file_path = self.session_file_path(session_id);
+
+        if file_path.exists() {
+            match fs::remove_file(&file_path) {
+                Ok(_) => debug!("Deleted session file: {}", file_path.display()),
+                Err(e) => error!("Failed to delete session file {:?}: {}", file_path, e),
+            }
+        }
+    }
+}
@@ -0,0 +1,287 @@
+mod file;
+mod sqlite;
+
+pub use file::FileStore;
+pub use sqlite::SqliteStore;
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, ChatMessageContentPart};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use shai_core::agent::AgentEvent;
+use shai_core::tools::ToolResult;
+
+use super::activity::state_name;
+use super::TokenTotals;
+
+pub type StoreError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Everything known about a saved session but its trace, what listing sessions reads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// API key namespace owning the session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Agent the session was running
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_name: Option<String>,
+    /// Start of the first user message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub usage: TokenTotals,
+}
+
+/// A saved session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionData {
+    #[serde(flatten)]
+    pub info: SessionInfo,
+    pub trace: Vec<ChatMessage>,
+}
+
+/// Which saved sessions to list, the most recently updated come first
+#[derive(Debug, Clone, Default)]
+pub struct SessionFilter {
+    /// Only the sessions of this namespace (None = any)
+    pub namespace: Option<String>,
+    /// Only the sessions of this agent
+    pub agent: Option<String>,
+    /// Only the sessions created before this date
+    pub created_before: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+impl SessionFilter {
+    pub fn matches(&self, info: &SessionInfo) -> bool {
+        self.namespace.as_ref().is_none_or(|namespace| info.namespace.as_ref() == Some(namespace))
+            && self.agent.as_ref().is_none_or(|agent| info.agent_name.as_ref() == Some(agent))
+            && self.created_before.is_none_or(|date| info.created_at < date)
+    }
+}
+
+/// An agent event of the event log of a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredEvent {
+    pub created_at: DateTime<Utc>,
+    /// status, user_input, tool_call, tool_result, permission_required, token_usage, llm_retry,
+    /// error or completed
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub data: serde_json::Value,
+}
+
+impl StoredEvent {
+    /// Record of an agent event, None for the events whose content is already in the trace
+    pub fn from_event(event: &AgentEvent) -> Option<Self> {
+        let (kind, data) = match event {
+            AgentEvent::StatusChanged { new_status, .. } => ("status", json!({ "state": state_name(new_status) })),
+            AgentEvent::UserInput { input } => ("user_input", json!({ "input": input })),
+            AgentEvent::ToolCallStarted { call, .. } => ("tool_call", json!({
+                "tool_call_id": call.tool_call_id,
+                "tool": call.tool_name,
+                "parameters": call.parameters,
+            })),
+            AgentEvent::ToolCallCompleted { duration, call, result } => {
+                let (status, error) = match result {
+                    ToolResult::Success { .. } => ("success", None),
+                    ToolResult::Error { error, .. } => ("error", Some(error)),
+                    ToolResult::Denied => ("denied", None),
+                };
+                ("tool_result", json!({
+                    "tool_call_id": call.tool_call_id,
+                    "tool": call.tool_name,
+                    "status": status,
+                    "error": error,
+                    "duration_ms": duration.num_milliseconds(),
+                }))
+            }
+            AgentEvent::PermissionRequired { request_id, request } => ("permission_required", json!({
+                "request_id": request_id,
+                "tool": request.tool_name,
+            })),
+            AgentEvent::UserInputRequired { request_id, request } => ("user_input_required", json!({
+                "request_id": request_id,
+                "request": request,
+            })),
+            AgentEvent::TokenUsage { input_tokens, output_tokens, cached_tokens } => ("token_usage", json!({
+                "input_tokens": input_tokens,
+                "output_tokens": output_tokens,
+                "cached_tokens": cached_tokens,
+            })),
+            AgentEvent::LlmRetry { attempt, max_retries, reason, .. } => ("llm_retry", json!({
+                "attempt": attempt,
+                "max_retries": max_retries,
+                "reason": reason,
+            })),
+            AgentEvent::Error { error } => ("error", json!({ "error": error })),
+            AgentEvent::Completed { success, message } => ("completed", json!({ "success": success, "message": message })),
+            _ => return None,
+        };
+        Some(Self { created_at: Utc::now(), kind: kind.to_string(), data })
+    }
+}
+
+/// Where the sessions are saved, to be loaded again after the agent stopped or the server restarted
+pub trait SessionStore: Send + Sync {
+    /// Whether the sessions are kept at all, unloading one from memory would lose it otherwise
+    fn is_persistent(&self) -> bool {
+        true
+    }
+
+    /// Save a session, an existing one keeps its creation date
+    fn save_session(
+        &self,
+        session_id: &str,
+        namespace: Option<&str>,
+        agent_name: Option<&str>,
+        usage: TokenTotals,
+        trace: Vec<ChatMessage>,
+    ) -> Result<(), StoreError>;
+
+    /// Load a single session, an error if it does not exist or failed to load
+    fn load_session(&self, session_id: &str) -> Result<SessionData, StoreError>;
+
+    /// Saved sessions matching `filter`, the ones that fail to load are skipped
+    fn list_sessions(&self, filter: &SessionFilter) -> Vec<SessionInfo>;
+
+    /// Delete a session and its event log
+    fn delete_session(&self, session_id: &str);
+
+    /// Append to the event log of a session, stores without event logs drop it
+    fn log_event(&self, _session_id: &str, _event: &StoredEvent) {}
+
+    /// Last `limit` events of a session, oldest first
+    fn events(&self, _session_id: &str, _limit: Option<usize>) -> Vec<StoredEvent> {
+        vec![]
+    }
+}
+
+/// Run calls to `store` on the blocking thread pool, the stores read and write files and databases
+pub async fn blocking<T, F>(store: &Arc<dyn SessionStore>, call: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce(&dyn SessionStore) -> T + Send + 'static,
+{
+    let store = store.clone();
+    tokio::task::spawn_blocking(move || call(store.as_ref()))
+        .await
+        .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
+/// Store of a server not saving its sessions
+pub struct NoStore;
+
+impl SessionStore for NoStore {
+    fn is_persistent(&self) -> bool {
+        false
+    }
+
+    fn save_session(&self, _: &str, _: Option<&str>, _: Option<&str>, _: TokenTotals, _: Vec<ChatMessage>) -> Result<(), StoreError> {
+        Ok(())
+    }
+
+    fn load_session(&self, _session_id: &str) -> Result<SessionData, StoreError> {
+        Err(io::Error::other("Session persistence is not enabled").into())
+    }
+
+    fn list_sessions(&self, _filter: &SessionFilter) -> Vec<SessionInfo> {
+        vec![]
+    }
+
+    fn delete_session(&self, _session_id: &str) {}
+}
+
+/// Which store keeps the sessions
+#[derive(Debug, Clone)]
+pub enum StoreMode {
+    /// Sessions are lost once their agent stops
+    None,
+    /// One JSON file per session in a directory
+    File(PathBuf),
+    /// A SQLite database, with the event logs of the sessions
+    Sqlite(PathBuf),
+}
+
+impl Default for StoreMode {
+    /// Files in SHAI_SESSION_PERSIST_FOLDER (.shai/sessions by default), none when
+    /// SHAI_SESSION_PERSIST_ENABLE is set to anything but true
+    fn default() -> Self {
+        let enabled = std::env::var("SHAI_SESSION_PERSIST_ENABLE")
+            .map(|v| v.to_lowercase() == "true")
+            .unwrap_or(true);
+        if !enabled {
+            return Self::None;
+        }
+        std::env::var("SHAI_SESSION_PERSIST_FOLDER")
+            .map(|folder| Self::File(PathBuf::from(folder)))
+            .unwrap_or_else(|_| Self::File(PathBuf::from(".shai/sessions")))
+    }
+}
+
+impl FromStr for StoreMode {
+    type Err = String;
+
+    /// `none`, `file[:<dir>]` or `sqlite[:<path>]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "none" => Ok(Self::None),
+            None if s == "file" => Ok(Self::File(PathBuf::from(".shai/sessions"))),
+            None if s == "sqlite" => Ok(Self::Sqlite(PathBuf::from(".shai/sessions.db"))),
+            Some(("file", dir)) if !dir.is_empty() => Ok(Self::File(PathBuf::from(dir))),
+            Some(("sqlite", path)) if !path.is_empty() => Ok(Self::Sqlite(PathBuf::from(path))),
+            _ => Err(format!("invalid session store '{}', expected none, file[:<dir>] or sqlite[:<path>]", s)),
+        }
+    }
+}
+
+impl fmt::Display for StoreMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::File(dir) => write!(f, "file:{}", dir.display()),
+            Self::Sqlite(path) => write!(f, "sqlite:{}", path.display()),
+        }
+    }
+}
+
+impl StoreMode {
+    pub fn open(&self) -> Result<Arc<dyn SessionStore>, StoreError> {
+        let store: Arc<dyn SessionStore> = match self {
+            Self::None => Arc::new(NoStore),
+            Self::File(dir) => Arc::new(FileStore::new(dir.clone())),
+            Self::Sqlite(path) => Arc::new(SqliteStore::open(path)?),
+        };
+        Ok(store)
+    }
+}
+
+/// First line of the first user message, shortened
+pub fn session_title(trace: &[ChatMessage]) -> Option<String> {
+    const MAX_CHARS: usize = 80;
+
+    let text = trace.iter().find_map(|message| match message {
+        ChatMessage::User { content: ChatMessageContent::Text(text), .. } => Some(text.as_str()),
+        ChatMessage::User { content: ChatMessageContent::ContentPart(parts), .. } => {
+            parts.iter().find_map(|part| match part {
+                ChatMessageContentPart::Text(part) => Some(part.text.as_str()),
+                _ => None,
+            })
+        }
+        _ => None,
+    })?;
+
+    let line = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+    if line.chars().count() <= MAX_CHARS {
+        return Some(line.to_string());
+    }
+    Some(format!("{}…", line.chars().take(MAX_CHARS).collect::<String>().trim_end()))
+}
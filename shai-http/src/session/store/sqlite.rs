@@ -0,0 +1,351 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use openai_dive::v1::resources::chat::ChatMessage;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use tracing::{debug, error, warn};
+
+use super::{session_title, SessionData, SessionFilter, SessionInfo, SessionStore, StoredEvent, StoreError};
+use crate::session::TokenTotals;
+
+/// Dates are stored as milliseconds since the epoch
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+    session_id TEXT PRIMARY KEY,
+    namespace TEXT,
+    agent_name TEXT,
+    title TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    cached_tokens INTEGER NOT NULL DEFAULT 0,
+    trace TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS sessions_by_update ON sessions (updated_at);
+CREATE INDEX IF NOT EXISTS sessions_by_namespace ON sessions (namespace, updated_at);
+CREATE INDEX IF NOT EXISTS sessions_by_agent ON sessions (agent_name, updated_at);
+CREATE INDEX IF NOT EXISTS sessions_by_creation ON sessions (created_at);
+
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS events_by_session ON events (session_id, id);
+";
+
+/// Events kept per session, the oldest ones are deleted past it
+const MAX_EVENTS_PER_SESSION: i64 = 10_000;
+
+/// Columns read by `session_info`
+const INFO_COLUMNS: &str =
+    "session_id, namespace, agent_name, title, created_at, updated_at, input_tokens, output_tokens, cached_tokens";
+
+/// Sessions and their event logs in a SQLite database. The database is in WAL mode and
+/// writers wait for each other, so several servers can share it
+/// The calls block, the event log is written by a thread of its own so that logging an event
+/// never waits for the database
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+    event_log: Sender<EventWrite>,
+}
+
+/// What the event log thread writes, in order
+enum EventWrite {
+    Append(String, StoredEvent),
+    Delete(String),
+}
+
+impl SqliteStore {
+    /// Open the database, creating it and its tables if needed
+    pub fn open(path: &Path) -> Result<Self, StoreError> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+
+        let conn = connect(path)?;
+        conn.execute_batch(SCHEMA)?;
+
+        let (event_log, writes) = mpsc::channel();
+        let event_conn = connect(path)?;
+        thread::Builder::new()
+            .name("shai-event-log".to_string())
+            .spawn(move || write_events(event_conn, writes))?;
+
+        debug!("Session database opened: {}", path.display());
+        Ok(Self { conn: Mutex::new(conn), event_log })
+    }
+
+    fn send(&self, write: EventWrite) {
+        if self.event_log.send(write).is_err() {
+            warn!("The event log thread of the session database stopped");
+        }
+    }
+}
+
+fn connect(path: &Path) -> Result<Connection, StoreError> {
+    let conn = Connection::open(path)?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+    conn.busy_timeout(Duration::from_secs(5))?;
+    Ok(conn)
+}
+
+/// Write the event log until the store is dropped, the queued writes go in a single transaction
+fn write_events(mut conn: Connection, writes: Receiver<EventWrite>) {
+    while let Ok(first) = writes.recv() {
+        let batch: Vec<EventWrite> = std::iter::once(first).chain(writes.try_iter()).collect();
+        let written = conn.transaction().and_then(|tx| {
+            let mut appended = Vec::new();
+            for write in &batch {
+                match write {
+                    EventWrite::Append(session_id, event) => {
+                        tx.execute(
+                            "INSERT INTO events (session_id, created_at, kind, data) VALUES (?1, ?2, ?3, ?4)",
+                            params![session_id, event.created_at.timestamp_millis(), event.kind, event.data.to_string()],
+                        )?;
+                        appended.push(session_id);
+                    }
+                    EventWrite::Delete(session_id) => {
+                        tx.execute("DELETE FROM events WHERE session_id = ?1", params![session_id])?;
+                        appended.retain(|appended| *appended != session_id);
+                    }
+                }
+            }
+            appended.sort();
+            appended.dedup();
+            for session_id in appended {
+                tx.execute(
+                    "DELETE FROM events WHERE session_id = ?1 AND id <= (
+                        SELECT id FROM events WHERE session_id = ?1 ORDER BY id DESC LIMIT 1 OFFSET ?2
+                     )",
+                    params![session_id, MAX_EVENTS_PER_SESSION],
+                )?;
+            }
+            tx.commit()
+        });
+        if let Err(e) = written {
+            warn!("Failed to write {} entries of the event log: {}", batch.len(), e);
+        }
+    }
+}
+
+impl SessionStore for SqliteStore {
+    fn save_session(
+        &self,
+        session_id: &str,
+        namespace: Option<&str>,
+        agent_name: Option<&str>,
+        usage: TokenTotals,
+        trace: Vec<ChatMessage>,
+    ) -> Result<(), StoreError> {
+        let title = session_title(&trace);
+        let trace = serde_json::to_string(&trace)?;
+
+        // a single upsert, so concurrent saves of a session never lose its creation date
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO sessions (session_id, namespace, agent_name, title, created_at, updated_at,
+                                   input_tokens, output_tokens, cached_tokens, trace)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT (session_id) DO UPDATE SET
+                namespace = excluded.namespace,
+                agent_name = excluded.agent_name,
+                title = excluded.title,
+                updated_at = excluded.updated_at,
+                input_tokens = excluded.input_tokens,
+                output_tokens = excluded.output_tokens,
+                cached_tokens = excluded.cached_tokens,
+                trace = excluded.trace",
+            params![
+                session_id,
+                namespace,
+                agent_name,
+                title,
+                Utc::now().timestamp_millis(),
+                usage.input_tokens as i64,
+                usage.output_tokens as i64,
+                usage.cached_tokens as i64,
+                trace,
+            ],
+        )?;
+
+        debug!("Session saved to database: {}", session_id);
+        Ok(())
+    }
+
+    fn load_session(&self, session_id: &str) -> Result<SessionData, StoreError> {
+        let row = self.conn.lock().unwrap()
+            .query_row(
+                &format!("SELECT {}, trace FROM sessions WHERE session_id = ?1", INFO_COLUMNS),
+                params![session_id],
+                |row| Ok((session_info(row)?, row.get::<_, String>(9)?)),
+            )
+            .optional()?;
+
+        let Some((info, trace)) = row else {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("Session not found in database: {}", session_id),
+            )
+            .into());
+        };
+
+        debug!("Loaded session from database: {}", session_id);
+        Ok(SessionData { info, trace: serde_json::from_str(&trace)? })
+    }
+
+    fn list_sessions(&self, filter: &SessionFilter) -> Vec<SessionInfo> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if let Some(namespace) = &filter.namespace {
+            values.push(Value::Text(namespace.clone()));
+            conditions.push(format!("namespace = ?{}", values.len()));
+        }
+        if let Some(agent) = &filter.agent {
+            values.push(Value::Text(agent.clone()));
+            conditions.push(format!("agent_name = ?{}", values.len()));
+        }
+        if let Some(date) = filter.created_before {
+            values.push(Value::Integer(date.timestamp_millis()));
+            conditions.push(format!("created_at < ?{}", values.len()));
+        }
+
+        let mut sql = format!("SELECT {} FROM sessions", INFO_COLUMNS);
+        if !conditions.is_empty() {
+            sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+        sql.push_str(" ORDER BY updated_at DESC");
+        if let Some(limit) = filter.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let sessions = conn.prepare(&sql).and_then(|mut statement| {
+            statement.query_map(params_from_iter(values), session_info)?.collect()
+        });
+        match sessions {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                warn!("Failed to list the sessions of the database: {}", e);
+                vec![]
+            }
+        }
+    }
+
+    fn delete_session(&self, session_id: &str) {
+        // after the events still queued for the session
+        self.send(EventWrite::Delete(session_id.to_string()));
+        let deleted = self.conn.lock().unwrap()
+            .execute("DELETE FROM sessions WHERE session_id = ?1", params![session_id]);
+        match deleted {
+            Ok(_) => debug!("Deleted session from database: {}", session_id),
+            Err(e) => error!("Failed to delete session {} from database: {}", session_id, e),
+        }
+    }
+
+    fn log_event(&self, session_id: &str, event: &StoredEvent) {
+        self.send(EventWrite::Append(session_id.to_string(), event.clone()));
+    }
+
+    fn events(&self, session_id: &str, limit: Option<usize>) -> Vec<StoredEvent> {
+        let limit = limit.map(|limit| limit as i64).unwrap_or(-1);
+        let conn = self.conn.lock().unwrap();
+        let events = conn
+            .prepare(
+                "SELECT created_at, kind, data FROM (
+                    SELECT id, created_at, kind, data FROM events WHERE session_id = ?1 ORDER BY id DESC LIMIT ?2
+                 ) ORDER BY id",
+            )
+            .and_then(|mut statement| {
+                statement
+                    .query_map(params![session_id, limit], |row| {
+                        Ok(StoredEvent {
+                            created_at: date(row.get(0)?),
+                            kind: row.get(1)?,
+                            data: serde_json::from_str(&row.get::<_, String>(2)?).unwrap_or_default(),
+                        })
+                    })?
+                    .collect()
+            });
+        match events {
+            Ok(events) => events,
+            Err(e) => {
+                warn!("Failed to read the events of session {}: {}", session_id, e);
+                vec![]
+            }
+        }
+    }
+}
+
+/// SessionInfo of a row starting with INFO_COLUMNS
+fn session_info(row: &Row) -> rusqlite::Result<SessionInfo> {
+    Ok(SessionInfo {
+        session_id: row.get(0)?,
+        namespace: row.get(1)?,
+        agent_name: row.get(2)?,
+        title: row.get(3)?,
+        created_at: date(row.get(4)?),
+        updated_at: date(row.get(5)?),
+        usage: TokenTotals {
+            input_tokens: row.get::<_, i64>(6)? as u64,
+            output_tokens: row.get::<_, i64>(7)? as u64,
+            cached_tokens: row.get::<_, i64>(8)? as u64,
+        },
+    })
+}
+
+fn date(millis: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(millis).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openai_dive::v1::resources::chat::ChatMessageContent;
+
+    fn user(text: &str) -> ChatMessage {
+        ChatMessage::User { content: ChatMessageContent::Text(text.to_string()), name: None }
+    }
+
+    #[test]
+    fn test_save_load_list_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SqliteStore::open(&dir.path().join("sessions.db")).unwrap();
+
+        let usage = TokenTotals { input_tokens: 12, output_tokens: 3, cached_tokens: 1 };
+        store.save_session("a", Some("web"), Some("coder"), usage, vec![user("fix the build\nplease")]).unwrap();
+        store.save_session("b", None, Some("default"), TokenTotals::default(), vec![]).unwrap();
+
+        let loaded = store.load_session("a").unwrap();
+        assert_eq!(loaded.info.namespace.as_deref(), Some("web"));
+        assert_eq!(loaded.info.agent_name.as_deref(), Some("coder"));
+        assert_eq!(loaded.info.title.as_deref(), Some("fix the build"));
+        assert_eq!(loaded.info.usage.input_tokens, 12);
+        assert_eq!(loaded.info.usage.cached_tokens, 1);
+        assert_eq!(loaded.trace.len(), 1);
+
+        // saving again keeps the creation date
+        store.save_session("a", Some("web"), Some("coder"), usage, vec![user("fix the build"), user("and test it")]).unwrap();
+        let saved_again = store.load_session("a").unwrap();
+        assert_eq!(saved_again.info.created_at, loaded.info.created_at);
+        assert_eq!(saved_again.trace.len(), 2);
+
+        assert_eq!(store.list_sessions(&SessionFilter::default()).len(), 2);
+        let web = store.list_sessions(&SessionFilter { namespace: Some("web".to_string()), ..Default::default() });
+        assert_eq!(web.iter().map(|s| s.session_id.as_str()).collect::<Vec<_>>(), vec!["a"]);
+        let limited = store.list_sessions(&SessionFilter { limit: Some(1), ..Default::default() });
+        assert_eq!(limited.len(), 1);
+
+        store.delete_session("a");
+        assert!(store.load_session("a").is_err());
+        assert_eq!(store.list_sessions(&SessionFilter::default()).len(), 1);
+    }
+}
@@ -0,0 +1,309 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use tracing::warn;
+
+/// Where the tools of each session work
+#[derive(Debug, Clone, Default)]
+pub enum WorkspaceMode {
+    /// The server working directory, shared by every session (tools are not confined)
+    #[default]
+    Shared,
+    /// An empty temporary directory
+    Temp,
+    /// A copy of a template directory
+    Template(PathBuf),
+    /// A git worktree of a repository, detached at its HEAD
+    Worktree(PathBuf),
+}
+
+impl FromStr for WorkspaceMode {
+    type Err = String;
+
+    /// `shared`, `temp`, `template:<dir>` or `worktree[:<repo>]` (the working directory by default)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "shared" => Ok(Self::Shared),
+            None if s == "temp" => Ok(Self::Temp),
+            None if s == "worktree" => Ok(Self::Worktree(PathBuf::from("."))),
+            Some(("template", dir)) if !dir.is_empty() => Ok(Self::Template(PathBuf::from(dir))),
+            Some(("worktree", repo)) if !repo.is_empty() => Ok(Self::Worktree(PathBuf::from(repo))),
+            _ => Err(format!("invalid workspace '{}', expected shared, temp, template:<dir> or worktree[:<repo>]", s)),
+        }
+    }
+}
+
+impl fmt::Display for WorkspaceMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shared => write!(f, "shared"),
+            Self::Temp => write!(f, "temp"),
+            Self::Template(dir) => write!(f, "template:{}", dir.display()),
+            Self::Worktree(repo) => write!(f, "worktree:{}", repo.display()),
+        }
+    }
+}
+
+/// Directory of a session, named after the session id so that it survives the session being
+/// unloaded and loaded again. It is removed by `SessionWorkspace::remove` once the session is
+/// gone for good. In an isolated workspace the agent tools are confined to it, otherwise it
+/// only holds the files attached by the clients
+pub struct SessionWorkspace {
+    root: PathBuf,
+    isolated: bool,
+    /// Whether the directory was created for this instance rather than reopened
+    new: bool,
+}
+
+impl SessionWorkspace {
+    /// Prepare the directory of a session, or reopen the one it had before being unloaded
+    /// Copying a template or adding a worktree runs on the blocking thread pool
+    pub async fn create(session_id: &str, mode: &WorkspaceMode) -> io::Result<Self> {
+        let (session_id, mode) = (session_id.to_string(), mode.clone());
+        tokio::task::spawn_blocking(move || Self::create_blocking(&session_id, &mode)).await?
+    }
+
+    fn create_blocking(session_id: &str, mode: &WorkspaceMode) -> io::Result<Self> {
+        let root = Self::path(session_id);
+        let isolated = !matches!(mode, WorkspaceMode::Shared);
+        let new = isolated && !root.exists();
+
+        if new {
+            match mode {
+                // created on the first attachment
+                WorkspaceMode::Shared => {}
+                WorkspaceMode::Temp => std::fs::create_dir_all(&root)?,
+                WorkspaceMode::Template(template) => copy_dir(template, &root, None)?,
+                WorkspaceMode::Worktree(repository) => {
+                    std::fs::create_dir_all(root.parent().unwrap_or(&root))?;
+                    git(repository, &["worktree", "add", "--detach"], &root)?;
+                }
+            }
+        }
+        Ok(Self { root, isolated, new })
+    }
+
+    /// Prepare the directory of a fork as a copy of the directory of its source. A worktree is
+    /// added at the commit of the source worktree, then gets its uncommitted changes. A source
+    /// without a directory yet gets a fresh one like `create`
+    pub async fn fork(source_id: &str, session_id: &str, mode: &WorkspaceMode) -> io::Result<Self> {
+        let (source_id, session_id, mode) = (source_id.to_string(), session_id.to_string(), mode.clone());
+        tokio::task::spawn_blocking(move || Self::fork_blocking(&source_id, &session_id, &mode)).await?
+    }
+
+    fn fork_blocking(source_id: &str, session_id: &str, mode: &WorkspaceMode) -> io::Result<Self> {
+        let source = Self::path(source_id);
+        let root = Self::path(session_id);
+        if matches!(mode, WorkspaceMode::Shared) || !source.exists() || root.exists() {
+            return Self::create_blocking(session_id, mode);
+        }
+
+        let copied = match mode {
+            WorkspaceMode::Worktree(repository) => {
+                let head = run(Command::new("git").arg("-C").arg(&source).args(["rev-parse", "HEAD"]))?;
+                std::fs::create_dir_all(root.parent().unwrap_or(&root))?;
+                run(Command::new("git").arg("-C").arg(repository).args(["worktree", "add", "--detach"]).arg(&root).arg(&head))?;
+                // the checkout is replaced by the files of the source, deleted files included
+                for entry in std::fs::read_dir(&root)? {
+                    let entry = entry?;
+                    if entry.file_name() == ".git" {
+                        continue;
+                    }
+                    if entry.file_type()?.is_dir() {
+                        std::fs::remove_dir_all(entry.path())?;
+                    } else {
+                        std::fs::remove_file(entry.path())?;
+                    }
+                }
+                copy_dir(&source, &root, Some(".git"))
+            }
+            _ => copy_dir(&source, &root, None),
+        };
+        let workspace = Self { root, isolated: true, new: true };
+        if let Err(e) = copied {
+            Self::remove_blocking(session_id, mode);
+            return Err(e);
+        }
+        Ok(workspace)
+    }
+
+    /// Delete the directory of a session that will not be loaded again, on the blocking thread pool
+    pub async fn remove(session_id: &str, mode: &WorkspaceMode) {
+        let (session_id, mode) = (session_id.to_string(), mode.clone());
+        if let Err(e) = tokio::task::spawn_blocking(move || Self::remove_blocking(&session_id, &mode)).await {
+            warn!("Failed to remove workspace: {}", e);
+        }
+    }
+
+    fn remove_blocking(session_id: &str, mode: &WorkspaceMode) {
+        let root = Self::path(session_id);
+        if !root.exists() {
+            return;
+        }
+        if let WorkspaceMode::Worktree(repository) = mode {
+            if let Err(e) = git(repository, &["worktree", "remove", "--force"], &root) {
+                warn!("Failed to remove worktree {}: {}", root.display(), e);
+            }
+        }
+        if let Err(e) = std::fs::remove_dir_all(&root) {
+            if e.kind() != io::ErrorKind::NotFound {
+                warn!("Failed to remove workspace {}: {}", root.display(), e);
+            }
+        }
+        if let WorkspaceMode::Worktree(repository) = mode {
+            let _ = Command::new("git").arg("-C").arg(repository).args(["worktree", "prune"]).output();
+        }
+    }
+
+    fn path(session_id: &str) -> PathBuf {
+        std::env::temp_dir().join("shai-sessions").join(dir_name(session_id))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether the agent tools are confined to the root
+    pub fn is_isolated(&self) -> bool {
+        self.isolated
+    }
+
+    /// Whether the directory was created rather than reopened, and must be removed if the
+    /// session fails to start
+    pub fn is_new(&self) -> bool {
+        self.new
+    }
+
+    /// Write an attachment under its file name (directories are stripped), returns its path
+    pub async fn write_file(&self, name: &str, content: &[u8]) -> io::Result<PathBuf> {
+        tokio::fs::create_dir_all(&self.root).await?;
+        let path = self.root.join(safe_file_name(name));
+        tokio::fs::write(&path, content).await?;
+        Ok(path)
+    }
+}
+
+/// Run `git -C <repository> <args> <path>`
+fn git(repository: &Path, args: &[&str], path: &Path) -> io::Result<()> {
+    run(Command::new("git").arg("-C").arg(repository).args(args).arg(path)).map(|_| ())
+}
+
+/// Run a command, returns its trimmed stdout or its stderr as the error
+fn run(command: &mut Command) -> io::Result<String> {
+    let output = command.output()?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()))
+    }
+}
+
+/// Copy a template directory, symlinks are skipped so none can lead out of the copy.
+/// `skip` names an entry of `from` left out (not of its subdirectories)
+fn copy_dir(from: &Path, to: &Path, skip: Option<&str>) -> io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        if skip.is_some_and(|skip| entry.file_name() == skip) {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        let target = to.join(entry.file_name());
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target, None)?;
+        } else if file_type.is_file() {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Directory name of a session id, ids with other characters than letters, digits, `-` and `_`
+/// are hex encoded behind a `%` so that two ids never share a directory
+fn dir_name(session_id: &str) -> String {
+    if !session_id.is_empty() && session_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return session_id.to_string();
+    }
+    let mut name = String::from("%");
+    for byte in session_id.bytes() {
+        name.push_str(&format!("{:02x}", byte));
+    }
+    name
+}
+
+/// Last path component, so a client cannot write outside the workspace
+fn safe_file_name(name: &str) -> String {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    match name {
+        "" | "." | ".." => "attachment".to_string(),
+        name => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session_id() -> String {
+        format!("test-{}", uuid::Uuid::new_v4())
+    }
+
+    fn git_in(dir: &Path, args: &[&str]) {
+        run(Command::new("git").arg("-C").arg(dir).args(args)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fork_copies_the_temp_workspace() {
+        let (source_id, fork_id) = (session_id(), session_id());
+        let source = SessionWorkspace::create(&source_id, &WorkspaceMode::Temp).await.unwrap();
+        std::fs::create_dir_all(source.root().join("src")).unwrap();
+        std::fs::write(source.root().join("src/main.rs"), "fn main() {}").unwrap();
+
+        let fork = SessionWorkspace::fork(&source_id, &fork_id, &WorkspaceMode::Temp).await.unwrap();
+        assert!(fork.is_new());
+        assert_eq!(std::fs::read_to_string(fork.root().join("src/main.rs")).unwrap(), "fn main() {}");
+
+        // the fork is a copy, not the same directory
+        std::fs::write(fork.root().join("src/main.rs"), "fn main() { todo!() }").unwrap();
+        assert_eq!(std::fs::read_to_string(source.root().join("src/main.rs")).unwrap(), "fn main() {}");
+
+        SessionWorkspace::remove(&source_id, &WorkspaceMode::Temp).await;
+        SessionWorkspace::remove(&fork_id, &WorkspaceMode::Temp).await;
+    }
+
+    #[tokio::test]
+    async fn test_fork_worktree_keeps_commits_and_changes_of_the_source() {
+        let repository = tempfile::tempdir().unwrap();
+        git_in(repository.path(), &["init", "-q"]);
+        git_in(repository.path(), &["config", "user.email", "test@example.com"]);
+        git_in(repository.path(), &["config", "user.name", "test"]);
+        std::fs::write(repository.path().join("a.txt"), "a").unwrap();
+        std::fs::write(repository.path().join("b.txt"), "b").unwrap();
+        git_in(repository.path(), &["add", "."]);
+        git_in(repository.path(), &["commit", "-qm", "init"]);
+
+        let mode = WorkspaceMode::Worktree(repository.path().to_path_buf());
+        let (source_id, fork_id) = (session_id(), session_id());
+        let source = SessionWorkspace::create(&source_id, &mode).await.unwrap();
+        std::fs::write(source.root().join("c.txt"), "c").unwrap();
+        git_in(source.root(), &["add", "c.txt"]);
+        git_in(source.root(), &["commit", "-qm", "add c"]);
+        std::fs::write(source.root().join("a.txt"), "changed").unwrap();
+        std::fs::remove_file(source.root().join("b.txt")).unwrap();
+
+        let fork = SessionWorkspace::fork(&source_id, &fork_id, &mode).await.unwrap();
+        let head = |dir: &Path| run(Command::new("git").arg("-C").arg(dir).args(["rev-parse", "HEAD"])).unwrap();
+        assert_eq!(head(fork.root()), head(source.root()));
+        assert_eq!(std::fs::read_to_string(fork.root().join("a.txt")).unwrap(), "changed");
+        assert!(!fork.root().join("b.txt").exists());
+        assert_eq!(std::fs::read_to_string(fork.root().join("c.txt")).unwrap(), "c");
+        // still a worktree of the repository
+        let status = run(Command::new("git").arg("-C").arg(fork.root()).args(["status", "--porcelain"])).unwrap();
+        assert!(status.contains("a.txt") && status.contains("b.txt"));
+
+        SessionWorkspace::remove(&source_id, &mode).await;
+        SessionWorkspace::remove(&fork_id, &mode).await;
+    }
+}
@@ -6,9 +6,9 @@ use shai_core::agent::{AgentEvent, PublicAgentState};
 use std::convert::Infallible;
 use tokio::sync::broadcast::Receiver;
 use tokio_stream::wrappers::BroadcastStream;
-use tracing::error;
+use tracing::{error, warn};
 
-use crate::session::RequestSession;
+use crate::session::{PendingPermission, PendingQuery, RequestSession};
 
 /// Trait for formatting AgentEvents into API-specific response formats
 #[async_trait]
@@ -28,6 +28,12 @@ pub trait EventFormatter: Send {
     fn event_name(&self, _output: &Self::Output) -> &str {
         "message"
     }
+
+    /// Whether the formatter ended the response on its own (e.g. handing tool calls to the client)
+    /// before the agent completed
+    fn is_done(&self) -> bool {
+        false
+    }
 }
 
 /// Internal helper to create SSE stream with optional lifecycle
@@ -55,10 +61,38 @@ where
 
                 loop {
                     match rx.next().await {
+                        // same event for every API, answered through POST /v1/sessions/{id}/permissions/{request_id}
+                        Some(Ok(AgentEvent::PermissionRequired { request_id, request })) => {
+                            let pending = PendingPermission::new(&session_id, &request_id, &request);
+                            match serde_json::to_string(&pending) {
+                                Ok(json) => {
+                                    let sse_event = Event::default().event("permission_required").data(json);
+                                    return Some((Ok(sse_event), (rx, fmt, done, lifecycle)));
+                                }
+                                Err(e) => {
+                                    error!("[{}] Failed to serialize permission request: {}", session_id, e);
+                                    continue;
+                                }
+                            }
+                        }
+                        // MCP sampling approvals and elicitations, answered through POST /v1/sessions/{id}/queries/{request_id}
+                        Some(Ok(AgentEvent::UserInputRequired { request_id, request })) => {
+                            let pending = PendingQuery { request_id, session_id: session_id.clone(), request };
+                            match serde_json::to_string(&pending) {
+                                Ok(json) => {
+                                    let sse_event = Event::default().event("user_input_required").data(json);
+                                    return Some((Ok(sse_event), (rx, fmt, done, lifecycle)));
+                                }
+                                Err(e) => {
+                                    error!("[{}] Failed to serialize query: {}", session_id, e);
+                                    continue;
+                                }
+                            }
+                        }
                         Some(Ok(event)) => {
                             let is_terminal = is_terminal_event(&event, stop_on_pause);
                             let formatted = fmt.format_event(event, &session_id).await;
-                            let new_done = if is_terminal { true } else { done };
+                            let new_done = is_terminal || fmt.is_done() || done;
 
                             if let Some(output) = formatted {
                                 match serde_json::to_string(&output) {
@@ -78,9 +112,10 @@ where
                                 continue;
                             }
                         }
+                        // lagged behind the agent, the missed events are skipped
                         Some(Err(e)) => {
-                            error!("[{}] Error receiving event: {}", session_id, e);
-                            return None;
+                            warn!("[{}] Error receiving event: {}", session_id, e);
+                            continue;
                         }
                         None => {
                             return None;
@@ -96,7 +131,7 @@ where
 /// Watches events, formats them, and stops on completion or client disconnect
 ///
 /// # Parameters
-/// * `stop_on_pause` - If true, only stops on Completed. If false, stops on Completed or StatusChanged to Paused.
+/// * `stop_on_pause` - If true, stops on Completed or StatusChanged to Paused. If false, only stops on Completed.
 pub fn event_to_sse_stream<F>(
     event_rx: Receiver<AgentEvent>,
     formatter: F,
@@ -113,7 +148,7 @@ where
 /// Same as sse_stream but keeps lifecycle in scope for session cleanup
 ///
 /// # Parameters
-/// * `stop_on_pause` - If true, only stops on Completed. If false, stops on Completed or StatusChanged to Paused.
+/// * `stop_on_pause` - If true, stops on Completed or StatusChanged to Paused. If false, only stops on Completed.
 pub fn session_to_sse_stream<F>(
     request_session: RequestSession,
     formatter: F,
@@ -133,8 +168,8 @@ where
 /// Check if an event signals the end of the stream
 ///
 /// # Parameters
-/// * `stop_on_pause` - If true, only Completed is terminal. If false, both Completed and Paused are terminal.
-fn is_terminal_event(event: &AgentEvent, stop_on_pause: bool) -> bool {
+/// * `stop_on_pause` - If true, both Completed and Paused are terminal. If false, only Completed is terminal.
+pub(crate) fn is_terminal_event(event: &AgentEvent, stop_on_pause: bool) -> bool {
     match event {
         AgentEvent::Completed { .. } => true,
         AgentEvent::StatusChanged {
@@ -0,0 +1,291 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use openai_dive::v1::resources::chat::{ChatCompletionParameters, ChatCompletionResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// On-disk cache of chat responses, only used by the call sites that ask for it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheConfig {
+    /// Directory holding one json file per response, `~/.cache/shai/responses` by default
+    #[serde(default = "default_dir")]
+    pub dir: PathBuf,
+    /// Age after which an entry is ignored and removed
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+    /// Oldest entries are evicted once the directory grows past this size
+    #[serde(default = "default_max_size_mb")]
+    pub max_size_mb: u64,
+}
+
+fn default_dir() -> PathBuf {
+    std::env::var("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(|_| std::env::temp_dir())
+        .join("shai")
+        .join("responses")
+}
+fn default_ttl_secs() -> u64 { 7 * 24 * 3600 }
+fn default_max_size_mb() -> u64 { 50 }
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            dir: default_dir(),
+            ttl_secs: default_ttl_secs(),
+            max_size_mb: default_max_size_mb(),
+        }
+    }
+}
+
+/// How a call site opts into the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Only requests sent with a temperature of 0
+    Deterministic,
+    /// Any request, for call sites where a previous answer is as good as a new one.
+    /// Never for agent loop steps, the same trace must be able to move on
+    Force,
+}
+
+impl CacheMode {
+    pub fn accepts(&self, request: &ChatCompletionParameters) -> bool {
+        match self {
+            CacheMode::Force => true,
+            // no temperature means the provider default, which is not 0
+            CacheMode::Deterministic => request.temperature == Some(0.0),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    created_at: u64,
+    response: ChatCompletionResponse,
+}
+
+/// Minimum delay between two scans of the cache directory
+const EVICT_INTERVAL_SECS: u64 = 60;
+
+/// Cache errors are never surfaced, a broken entry is a miss.
+/// File IO runs on the blocking pool.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    config: CacheConfig,
+    last_evict: Arc<AtomicU64>,
+}
+
+impl ResponseCache {
+    pub fn new(config: CacheConfig) -> Self {
+        Self { config, last_evict: Arc::new(AtomicU64::new(0)) }
+    }
+
+    pub fn config(&self) -> &CacheConfig {
+        &self.config
+    }
+
+    /// Hash of the request with its object keys sorted and null fields dropped, so two requests
+    /// differing only by field order or unset options share an entry. `context` holds what
+    /// the request does not say but changes the answer (provider, default reasoning...)
+    pub fn key(request: &ChatCompletionParameters, context: Value) -> String {
+        let mut value = serde_json::to_value(request).unwrap_or(Value::Null);
+        if let Value::Object(map) = &mut value {
+            // transport options, the answer is the same
+            for field in ["stream", "stream_options", "user", "store", "metadata"] {
+                map.remove(field);
+            }
+        }
+        let normalized = normalize(json!({ "context": context, "request": value }));
+        format!("{:032x}", fnv1a_128(normalized.to_string().as_bytes()))
+    }
+
+    pub async fn get(&self, key: &str) -> Option<ChatCompletionResponse> {
+        let (cache, key) = (self.clone(), key.to_string());
+        tokio::task::spawn_blocking(move || cache.read(&key)).await.ok().flatten()
+    }
+
+    pub async fn put(&self, key: &str, response: &ChatCompletionResponse) {
+        let (cache, key, response) = (self.clone(), key.to_string(), response.clone());
+        let _ = tokio::task::spawn_blocking(move || cache.write(&key, &response)).await;
+    }
+
+    fn read(&self, key: &str) -> Option<ChatCompletionResponse> {
+        let path = self.path(key);
+        let entry: CacheEntry = std::fs::read(&path).ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())?;
+        if now_secs().saturating_sub(entry.created_at) > self.config.ttl_secs {
+            let _ = std::fs::remove_file(&path);
+            return None;
+        }
+        Some(entry.response)
+    }
+
+    fn write(&self, key: &str, response: &ChatCompletionResponse) {
+        if std::fs::create_dir_all(&self.config.dir).is_err() {
+            return;
+        }
+        let entry = CacheEntry { created_at: now_secs(), response: response.clone() };
+        let Ok(content) = serde_json::to_vec(&entry) else {
+            return;
+        };
+        // write then rename so a concurrent reader never sees a partial entry
+        let tmp = self.config.dir.join(format!("{}.{}.tmp", key, uuid::Uuid::new_v4()));
+        if std::fs::write(&tmp, content).is_ok() && std::fs::rename(&tmp, self.path(key)).is_err() {
+            let _ = std::fs::remove_file(&tmp);
+        }
+
+        // scanning the directory on every write would cost more than the cache saves
+        let now = now_secs();
+        let last = self.last_evict.load(Ordering::Relaxed);
+        if now.saturating_sub(last) >= EVICT_INTERVAL_SECS
+            && self.last_evict.compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed).is_ok()
+        {
+            self.evict();
+        }
+    }
+
+    /// Drop expired entries, then the oldest ones until the directory fits in max_size_mb
+    pub fn evict(&self) {
+        let Ok(dir) = std::fs::read_dir(&self.config.dir) else {
+            return;
+        };
+        let ttl = Duration::from_secs(self.config.ttl_secs);
+        let mut entries: Vec<(PathBuf, SystemTime, u64)> = dir
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok()?;
+                Some((entry.path(), meta.modified().ok()?, meta.len()))
+            })
+            .collect();
+
+        entries.retain(|(path, modified, _)| {
+            let expired = modified.elapsed().map(|age| age > ttl).unwrap_or(false);
+            if expired {
+                let _ = std::fs::remove_file(path);
+            }
+            !expired
+        });
+
+        let max = self.config.max_size_mb * 1024 * 1024;
+        let mut total: u64 = entries.iter().map(|(_, _, size)| size).sum();
+        entries.sort_by_key(|(_, modified, _)| *modified);
+        for (path, _, size) in entries {
+            if total <= max {
+                break;
+            }
+            if std::fs::remove_file(&path).is_ok() {
+                total -= size;
+            }
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.config.dir.join(format!("{}.json", key))
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Sorted keys and no nulls, independent of the serde_json map implementation
+fn normalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut fields: Vec<(String, Value)> = map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, normalize(v)))
+                .collect();
+            fields.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(fields.into_iter().collect())
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(normalize).collect()),
+        other => other,
+    }
+}
+
+/// Stable across builds and platforms unlike the std hasher, the cache outlives the binary
+fn fnv1a_128(bytes: &[u8]) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    bytes.iter().fold(OFFSET, |hash, byte| (hash ^ *byte as u128).wrapping_mul(PRIME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openai_dive::v1::resources::chat::{ChatCompletionParametersBuilder, ChatMessage, ChatMessageContent};
+
+    fn request(text: &str, temperature: Option<f32>) -> ChatCompletionParameters {
+        let mut request = ChatCompletionParametersBuilder::default()
+            .model("gpt-4o-mini".to_string())
+            .messages(vec![ChatMessage::User { content: ChatMessageContent::Text(text.to_string()), name: None }])
+            .build()
+            .unwrap();
+        request.temperature = temperature;
+        request
+    }
+
+    fn response(text: &str) -> ChatCompletionResponse {
+        serde_json::from_value(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "gpt-4o-mini",
+            "choices": [{ "index": 0, "message": { "role": "assistant", "content": text }, "finish_reason": "stop" }]
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_key_normalization() {
+        let context = json!({ "provider": "openai" });
+        let key = ResponseCache::key(&request("ls -la", Some(0.0)), context.clone());
+        let mut streamed = request("ls -la", Some(0.0));
+        streamed.stream = Some(false);
+        assert_eq!(key, ResponseCache::key(&streamed, context.clone()));
+        assert_ne!(key, ResponseCache::key(&request("ls -l", Some(0.0)), context));
+        assert_ne!(key, ResponseCache::key(&request("ls -la", Some(0.0)), json!({ "provider": "anthropic" })));
+        assert_eq!(normalize(json!({ "b": 1, "a": { "d": null, "c": [ { "f": 2, "e": 3 } ] } })).to_string(),
+                   r#"{"a":{"c":[{"e":3,"f":2}]},"b":1}"#);
+    }
+
+    #[test]
+    fn test_mode() {
+        assert!(CacheMode::Deterministic.accepts(&request("hi", Some(0.0))));
+        assert!(!CacheMode::Deterministic.accepts(&request("hi", Some(0.1))));
+        assert!(!CacheMode::Deterministic.accepts(&request("hi", None)));
+        assert!(CacheMode::Force.accepts(&request("hi", Some(0.7))));
+    }
+
+    #[tokio::test]
+    async fn test_get_put_ttl_and_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ResponseCache::new(CacheConfig { dir: dir.path().to_path_buf(), ttl_secs: 3600, max_size_mb: 1 });
+
+        assert!(cache.get("a").await.is_none());
+        cache.put("a", &response("first")).await;
+        assert_eq!(cache.get("a").await.unwrap().id.as_deref(), Some("chatcmpl-1"));
+
+        // expired entry is a miss and gets removed
+        let expired = CacheEntry { created_at: now_secs() - 7200, response: response("old") };
+        std::fs::write(dir.path().join("b.json"), serde_json::to_vec(&expired).unwrap()).unwrap();
+        assert!(cache.get("b").await.is_none());
+        assert!(!dir.path().join("b.json").exists());
+
+        // oldest entries go first once over the limit, the directory is scanned at most once a minute
+        let big = response(&"x".repeat(400 * 1024));
+        for key in ["c", "d", "e"] {
+            cache.put(key, &big).await;
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(cache.get("c").await.is_some());
+        cache.evict();
+        assert!(cache.get("a").await.is_none() && cache.get("c").await.is_none());
+        assert!(cache.get("d").await.is_some() && cache.get("e").await.is_some());
+    }
+}
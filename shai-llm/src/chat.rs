@@ -11,6 +11,9 @@ use reqwest::{Method, RequestBuilder};
 use reqwest_eventsource::{Event, EventSource, RequestBuilderExt};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+
+use crate::provider::LlmError;
+use crate::retry::HttpError;
 use std::collections::HashMap;
 use std::pin::Pin;
 
@@ -90,31 +93,14 @@ impl ChatClient {
         request
     }
 
-    /// Check status code and handle errors
+    /// Check status code and handle errors, keeping the status and Retry-After for the retry layer
     async fn check_status_code(
         result: Result<reqwest::Response, reqwest::Error>,
-    ) -> Result<reqwest::Response, APIError> {
+    ) -> Result<reqwest::Response, LlmError> {
         match result {
-            Ok(response) => {
-                if response.status().is_success() {
-                    Ok(response)
-                } else {
-                    let status = response.status();
-                    let error_text = response.text().await.unwrap_or_default();
-                    
-                    match status.as_u16() {
-                        400 => Err(APIError::InvalidRequestError(error_text)),
-                        401 => Err(APIError::AuthenticationError(error_text)),
-                        403 => Err(APIError::PermissionError(error_text)),
-                        404 => Err(APIError::NotFoundError(error_text)),
-                        429 => Err(APIError::RateLimitError(error_text)),
-                        500 => Err(APIError::UnknownError(500, error_text)),
-                        503 => Err(APIError::UnknownError(503, error_text)),
-                        _ => Err(APIError::UnknownError(status.as_u16(), error_text)),
-                    }
-                }
-            }
-            Err(error) => Err(APIError::ParseError(error.to_string())),
+            Ok(response) if response.status().is_success() => Ok(response),
+            Ok(response) => Err(HttpError::from_response(response, "API error").await.into()),
+            Err(error) => Err(error.into()),
         }
     }
 
@@ -123,7 +109,7 @@ impl ChatClient {
         &self,
         parameters: &ChatCompletionParameters,
         hooks: &H,
-    ) -> Result<ChatCompletionResponse, APIError> {
+    ) -> Result<ChatCompletionResponse, LlmError> {
         // Serialize to JSON and apply before_send hook
         let mut json = serde_json::to_value(parameters)
             .map_err(|e| APIError::ParseError(e.to_string()))?;
@@ -161,19 +147,35 @@ impl ChatClient {
         &self,
         parameters: &ChatCompletionParameters,
         hooks: H,
-    ) -> Result<Pin<Box<dyn Stream<Item = Result<ChatCompletionChunkResponse, APIError>> + Send>>, APIError> {
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<ChatCompletionChunkResponse, APIError>> + Send>>, LlmError> {
         // Serialize to JSON and apply before_send hook
         let mut json = serde_json::to_value(parameters)
             .map_err(|e| APIError::ParseError(e.to_string()))?;
         json = hooks.before_send(json).await?;
 
         // Create event source for streaming
-        let event_source = self
+        let mut event_source = self
             .build_request(Method::POST, "/chat/completions", "application/json")
             .json(&json)
             .eventsource()
             .map_err(|e| APIError::ParseError(e.to_string()))?;
 
+        // The request is only sent once the event source is polled, wait for the connection so that
+        // a refused request (429, 5xx...) is returned here with its status for the retry layer
+        match event_source.next().await {
+            // Event::Open, an event source opens before any message
+            Some(Ok(_)) => {}
+            Some(Err(reqwest_eventsource::Error::InvalidStatusCode(_, response))) => {
+                event_source.close();
+                return Err(HttpError::from_response(response, "API error").await.into());
+            }
+            Some(Err(e)) => {
+                event_source.close();
+                return Err(APIError::StreamError(e.to_string()).into());
+            }
+            None => return Err(APIError::StreamError("event stream closed before opening".to_string()).into()),
+        }
+
         // Return stream that processes events
         let stream = async_stream::stream! {
             let mut event_source = event_source;
@@ -212,4 +214,52 @@ impl ChatClient {
     }
 }
 
-// Note: types are already imported above, no need to re-export
\ No newline at end of file
+// Note: types are already imported above, no need to re-export
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::{classify, RetryReason};
+    use openai_dive::v1::resources::chat::{ChatCompletionParametersBuilder, ChatMessage, ChatMessageContent};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_stream_refused_request_keeps_status_and_retry_after() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            let body = r#"{"error":{"message":"rate limited"}}"#;
+            let response = format!(
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 7\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let client = ChatClient::new("test".to_string(), format!("http://{}", addr));
+        let parameters = ChatCompletionParametersBuilder::default()
+            .model("test")
+            .messages(vec![ChatMessage::User {
+                content: ChatMessageContent::Text("hello".to_string()),
+                name: None,
+            }])
+            .build()
+            .unwrap();
+
+        let error = match client.chat_completion_stream(&parameters, NoHooks).await {
+            Ok(_) => panic!("a refused request must fail before returning the stream"),
+            Err(error) => error,
+        };
+        let http = error.downcast_ref::<HttpError>().expect("HttpError");
+        assert_eq!(http.status, 429);
+        assert_eq!(http.retry_after, Some(Duration::from_secs(7)));
+        assert!(http.message.contains("rate limited"));
+        assert!(matches!(classify(&error), Some((RetryReason::RateLimited, Some(d))) if d == Duration::from_secs(7)));
+    }
+}
@@ -3,25 +3,39 @@ use crate::ToolCallMethod;
 
 // llm/client.rs
 use super::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo};
+use super::retry::{self, RetryConfig};
+use super::reasoning::{ReasoningConfig, ReasoningRequest};
+use super::model_info::{ModelInfo, ModelRegistry};
+use super::cache::{CacheConfig, CacheMode, ResponseCache};
+use super::hooks::JsonHooksConfig;
 use super::providers::{
     openai::OpenAIProvider,
     openai_compatible::OpenAICompatibleProvider,
     openrouter::OpenRouterProvider,
     ovhcloud::OvhCloudProvider,
     anthropic::AnthropicProvider,
+    gemini::GeminiProvider,
     ollama::OllamaProvider,
-    mistral::MistralProvider
+    mistral::MistralProvider,
+    router::RouterProvider,
+    replay::{ReplayConfig, ReplayMode, ReplayProvider},
 };
 use openai_dive::v1::resources::chat::ChatCompletionParametersBuilder;
 use openai_dive::v1::resources::{
-    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatMessage, ChatMessageContent},
+    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatMessage, ChatMessageContent, ChatMessageContentPart, ChatMessageTextContentPart},
     model::ListModelResponse,
+    shared::Usage,
 };
 use regex::Regex;
 
 #[derive(Debug)]
 pub struct LlmClient {
     provider: Box<dyn LlmProvider>,
+    retry: RetryConfig,
+    reasoning: Option<ReasoningConfig>,
+    models: ModelRegistry,
+    cache: Option<ResponseCache>,
+    extract_think: bool,
 }
 
 /// Provider Factory related method
@@ -29,111 +43,135 @@ impl LlmClient {
     /// Create an OpenAI provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env_openai() -> Option<Self> {
-        OpenAIProvider::from_env().map(|provider| Self {
-            provider: Box::new(provider),
-        })
+        OpenAIProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
     }
 
     /// Create an Anthropic provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env_anthropic() -> Option<Self> {
-        AnthropicProvider::from_env().map(|provider| Self {
-            provider: Box::new(provider),
-        })
+        AnthropicProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
+    }
+
+    /// Create a Gemini provider from environment variables
+    /// Returns None if required environment variables are not set
+    pub fn from_env_gemini() -> Option<Self> {
+        GeminiProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
     }
 
     /// Create an Ollama provider from environment variables
     /// Always returns Some since Ollama has a default base URL
     pub fn from_env_ollama() -> Option<Self> {
-        OllamaProvider::from_env().map(|provider| Self {
-            provider: Box::new(provider),
-        })
+        OllamaProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
     }
 
     /// Create an OpenRouter provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env_openrouter() -> Option<Self> {
-        OpenRouterProvider::from_env().map(|provider| Self {
-            provider: Box::new(provider),
-        })
+        OpenRouterProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
     }
 
     /// Create an OpenAI Compatible provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env_openai_compatible() -> Option<Self> {
-        OpenAICompatibleProvider::from_env().map(|provider| Self {
-            provider: Box::new(provider),
-        })
+        OpenAICompatibleProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
     }
 
     /// Create an OVH Cloud provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env_ovhcloud() -> Option<Self> {
-        OvhCloudProvider::from_env().map(|provider| Self {
-            provider: Box::new(provider),
-        })
+        OvhCloudProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
     }
 
     /// Create a Mistral provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env_mistral() -> Option<Self> {
-        MistralProvider::from_env().map(|provider| Self {
-            provider: Box::new(provider),
-        })
+        MistralProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
     }
 
     pub fn openai(api_key: String) -> Self {
-        Self {
-            provider: Box::new(OpenAIProvider::new(api_key)),
-        }
+        Self::from_provider(Box::new(OpenAIProvider::new(api_key)))
     }
 
     pub fn compatible(api_key: String, base_url: String) -> Self {
-        Self {
-            provider: Box::new(OpenAICompatibleProvider::new(api_key, base_url)),
-        }
+        Self::from_provider(Box::new(OpenAICompatibleProvider::new(api_key, base_url)))
     }
 
     pub fn openrouter(api_key: String) -> Self {
-        Self {
-            provider: Box::new(OpenRouterProvider::new(api_key)),
-        }
+        Self::from_provider(Box::new(OpenRouterProvider::new(api_key)))
     }
 
     pub fn ovhcloud(api_key: String, base_url: Option<String>) -> Self {
-        Self {
-            provider: Box::new(OvhCloudProvider::new(api_key, base_url)),
-        }
+        Self::from_provider(Box::new(OvhCloudProvider::new(api_key, base_url)))
     }
 
     pub fn anthropic(api_key: String) -> Self {
-        Self {
-            provider: Box::new(AnthropicProvider::new(api_key)),
-        }
+        Self::from_provider(Box::new(AnthropicProvider::new(api_key)))
+    }
+
+    pub fn gemini(api_key: String, base_url: Option<String>) -> Self {
+        Self::from_provider(Box::new(GeminiProvider::new(api_key, base_url)))
     }
 
     pub fn ollama(base_url: String) -> Self {
+        Self::from_provider(Box::new(OllamaProvider::new(Some(base_url))))
+    }
+
+    /// Wrap any provider with the default retry policy
+    pub fn from_provider(provider: Box<dyn LlmProvider>) -> Self {
         Self {
-            provider: Box::new(OllamaProvider::new(Some(base_url))),
+            provider,
+            retry: RetryConfig::default(),
+            reasoning: None,
+            models: ModelRegistry::bundled(),
+            cache: None,
+            extract_think: true,
         }
     }
 
+    /// Client over fallback chains, each target keeps its own retry policy and hooks
+    pub fn router(router: RouterProvider) -> Self {
+        let mut client = Self::from_provider(Box::new(router)).with_retry(RetryConfig::disabled());
+        client.extract_think = false;
+        client
+    }
+
+    /// Client serving the exchanges of a cassette, no provider behind it
+    pub fn replay(replay: ReplayConfig) -> Result<Self, LlmError> {
+        let mut client = Self::from_provider(Box::new(ReplayProvider::replay(replay)?));
+        client.retry.initial_backoff_ms = 0;
+        Ok(client)
+    }
+
     pub fn mistral(api_key: String) -> Self {
-        Self {
-            provider: Box::new(MistralProvider::new(api_key)),
-        }
+        Self::from_provider(Box::new(MistralProvider::new(api_key)))
     }
 
 
     /// Get all available LLM clients from environment variables
-    /// Returns clients in order of preference for testing
-    pub fn first_from_env() -> Option<Self> {
+    /// Returns clients in order of preference for testing.
+    /// With SHAI_REPLAY_CASSETTE set, exchanges are recorded to or replayed from the cassette
+    /// (see ReplayConfig), replaying needs no provider environment variables.
+    /// A cassette that cannot be read is an error, not a missing provider.
+    pub fn first_from_env() -> Result<Self, LlmError> {
+        let replay = ReplayConfig::from_env();
+        if let Some(replay) = replay.clone().filter(|r| r.mode == ReplayMode::Replay) {
+            return Self::replay(replay);
+        }
+        let client = Self::first_provider_from_env().ok_or("No LLM provider available")?;
+        match replay {
+            Some(replay) => client.with_replay(replay),
+            None => Ok(client),
+        }
+    }
+
+    fn first_provider_from_env() -> Option<Self> {
         if let Ok(provider) = std::env::var("SHAI_PROVIDER") {
             match provider.as_str() {
                 "ovhcloud" => return Self::from_env_ovhcloud(),
                 "openai" => return Self::from_env_openai(),
                 "mistral" => return Self::from_env_mistral(),
                 "anthropic" => return Self::from_env_anthropic(),
+                "gemini" => return Self::from_env_gemini(),
                 "openrouter" => return Self::from_env_openrouter(),
                 "openai_compatible" => return Self::from_env_openai_compatible(),
                 "ollama" => return Self::from_env_ollama(),
@@ -153,6 +191,9 @@ impl LlmClient {
         if let Some(client) = Self::from_env_anthropic() {
             return Some(client);
         }
+        if let Some(client) = Self::from_env_gemini() {
+            return Some(client);
+        }
         if let Some(client) = Self::from_env_openrouter() {
             return Some(client);
         }
@@ -174,6 +215,7 @@ impl LlmClient {
             OpenAICompatibleProvider::info(),
             OpenRouterProvider::info(),
             AnthropicProvider::info(),
+            GeminiProvider::info(),
             OpenAIProvider::info(),
         ]
     }
@@ -191,6 +233,12 @@ impl LlmClient {
                     .ok_or("ANTHROPIC_API_KEY not found")?;
                 Ok(Self::anthropic(api_key.clone()))
             },
+            "gemini" => {
+                let api_key = env_values.get("GEMINI_API_KEY")
+                    .ok_or("GEMINI_API_KEY not found")?;
+                let base_url = env_values.get("GEMINI_BASE_URL").cloned();
+                Ok(Self::gemini(api_key.clone(), base_url))
+            },
             "ollama" => {
                 let base_url = env_values.get("OLLAMA_BASE_URL")
                     .cloned()
@@ -243,6 +291,105 @@ impl LlmClient {
         self.provider.name()
     }
 
+    /// Model to request for a route (e.g. ROUTE_FAST): the route name if the provider
+    /// routes on it, `fallback` otherwise
+    pub fn route_model(&self, route: &str, fallback: &str) -> String {
+        if self.provider.has_route(route) {
+            route.to_string()
+        } else {
+            fallback.to_string()
+        }
+    }
+
+    /// Retry policy applied to chat and chat_stream
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn retry_config(&self) -> &RetryConfig {
+        &self.retry
+    }
+
+    /// Record the exchanges of this client to a cassette, or serve them from one instead of
+    /// the provider. Replayed errors are retried without waiting.
+    pub fn with_replay(self, replay: ReplayConfig) -> Result<Self, LlmError> {
+        let Self { provider, mut retry, reasoning, models, cache, extract_think } = self;
+        if replay.mode == ReplayMode::Replay {
+            retry.initial_backoff_ms = 0;
+        }
+        let provider = ReplayProvider::wrap(provider, replay)?;
+        Ok(Self { provider: Box::new(provider), retry, reasoning, models, cache, extract_think })
+    }
+
+    /// Declarative request tweaks of the provider config, fails on providers that cannot
+    /// apply them. `extract_think` is honoured by every provider.
+    pub fn with_json_hooks(mut self, hooks: Option<JsonHooksConfig>) -> Result<Self, LlmError> {
+        let Some(hooks) = hooks else {
+            return Ok(self);
+        };
+        self.extract_think = hooks.extract_think;
+        if hooks.touches_request() {
+            self.provider.set_json_hooks(hooks)?;
+        }
+        Ok(self)
+    }
+
+    /// Response cache used by chat_cached, chat_cached is a plain chat without it
+    pub fn with_cache(mut self, cache: Option<CacheConfig>) -> Self {
+        self.cache = cache.map(ResponseCache::new);
+        self
+    }
+
+    /// Model table used by model_info, the bundled one by default
+    pub fn with_model_registry(mut self, models: ModelRegistry) -> Self {
+        self.models = models;
+        self
+    }
+
+    /// Context window, capabilities and pricing of a model: the bundled table completed by
+    /// the provider metadata when it has some, then the user overrides
+    pub async fn model_info(&self, model: &str) -> ModelInfo {
+        let provided = self.provider.models_info().await
+            .ok()
+            .and_then(|infos| infos.into_iter().find(|info| info.id == model));
+        self.models.resolve(model, provided)
+    }
+
+    /// Reasoning config for requests that do not set their own
+    pub fn with_reasoning(mut self, reasoning: Option<ReasoningConfig>) -> Self {
+        self.reasoning = reasoning;
+        self
+    }
+
+    /// Default reasoning config then provider specific translation
+    fn prepare_reasoning(&self, mut request: ChatCompletionParameters) -> ChatCompletionParameters {
+        if let (None, Some(reasoning)) = (request.reasoning(), &self.reasoning) {
+            request = request.with_reasoning(reasoning.clone());
+        }
+        self.provider.adapt_reasoning(request)
+    }
+
+    /// Images only reach the models the registry does not list as blind. When the registry does
+    /// not know whether the model reads images, they are sent and the request without them is
+    /// returned as a fallback for a provider that rejects them
+    fn prepare_images(&self, request: ChatCompletionParameters) -> (ChatCompletionParameters, Option<ChatCompletionParameters>) {
+        match self.models.resolve(&request.model, None).vision {
+            Some(false) => (request.strip_images(), None),
+            Some(true) => (request, None),
+            None if request.has_images() => {
+                let fallback = self.prepare_reasoning(request.clone().strip_images());
+                (request, Some(fallback))
+            }
+            None => (request, None),
+        }
+    }
+
+    /// A request refused as invalid may be refused for its images
+    fn is_image_rejection(error: &LlmError) -> bool {
+        matches!(retry::error_status(error), Some(400 | 415 | 422))
+    }
+
     /// Get a reference to the underlying provider (for testing)
     pub fn provider(&self) -> &dyn LlmProvider {
         &*self.provider
@@ -252,28 +399,75 @@ impl LlmClient {
 /// Higher level chat client
 impl LlmClient {
     pub async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
-        let request = request
-            .fix_mistral_alternating();
+        let (request, fallback) = self.prepare_images(request.fix_mistral_alternating());
+        let request = self.prepare_reasoning(request);
+
+        let mut result = retry::with_retry(&self.retry, self.provider_name(), || self.provider.chat(request.clone())).await;
+        let request = match (&result, fallback) {
+            (Err(error), Some(fallback)) if Self::is_image_rejection(error) => {
+                result = retry::with_retry(&self.retry, self.provider_name(), || self.provider.chat(fallback.clone())).await;
+                fallback
+            }
+            _ => request,
+        };
+        let response = result.inspect_err(|error| {
+            crate::logging::log_llm_error(&request, error, self.provider_name());
+        })?;
+
+        if self.extract_think {
+            Ok(response.extract_think_content())
+        } else {
+            Ok(response)
+        }
+    }
+
+    /// Only establishing the stream is retried, an error in the middle of the stream is returned as is
+    pub async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+        let (request, fallback) = self.prepare_images(request.fix_mistral_alternating());
+        let request = self.prepare_reasoning(request);
+
+        let result = retry::with_retry(&self.retry, self.provider_name(), || self.provider.chat_stream(request.clone())).await;
+        match (result, fallback) {
+            (Err(error), Some(fallback)) if Self::is_image_rejection(&error) => {
+                retry::with_retry(&self.retry, self.provider_name(), || self.provider.chat_stream(fallback.clone())).await
+            }
+            (result, _) => result,
+        }
+    }
 
-        let response = self.provider
-            .chat(request.clone())
-            .await
-            .inspect_err(|error| {
-                crate::logging::log_llm_error(&request, error, self.provider_name());
-            })?
-            .extract_think_content();
+    /// Chat through the response cache when the client has one and `mode` accepts the request
+    pub async fn chat_cached(&self, request: ChatCompletionParameters, mode: CacheMode) -> Result<ChatCompletionResponse, LlmError> {
+        let Some(cache) = self.cache.as_ref().filter(|_| mode.accepts(&request)) else {
+            return self.chat(request).await;
+        };
+
+        let key = ResponseCache::key(&request, serde_json::json!({
+            "provider": self.provider_name(),
+            "reasoning": self.reasoning,
+        }));
+        if let Some(response) = cache.get(&key).await {
+            return Ok(response);
+        }
 
+        let response = self.chat(request).await?;
+        cache.put(&key, &response).await;
         Ok(response)
     }
 
-    pub async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
-        let request = request
-            .fix_mistral_alternating();
 
-        self.provider.chat_stream(request).await
-    }
+}
 
+pub trait CachedTokens {
+    /// Prompt tokens served from the provider cache
+    fn cached_tokens(&self) -> u32;
+}
 
+impl CachedTokens for Usage {
+    fn cached_tokens(&self) -> u32 {
+        serde_json::to_value(&self.prompt_tokens_details).ok()
+            .and_then(|details| details["cached_tokens"].as_u64())
+            .unwrap_or(0) as u32
+    }
 }
 
 pub trait ExtractThinkContent {
@@ -299,6 +493,41 @@ impl ExtractThinkContent for ChatCompletionResponse {
     }
 }
 
+pub trait StripImages {
+    /// Replace the image parts of the messages by a short note, for models without vision
+    fn strip_images(self) -> ChatCompletionParameters;
+
+    /// Whether a user message holds an image part
+    fn has_images(&self) -> bool;
+}
+
+impl StripImages for ChatCompletionParameters {
+    fn has_images(&self) -> bool {
+        self.messages.iter().any(|message| matches!(
+            message,
+            ChatMessage::User { content: ChatMessageContent::ContentPart(parts), .. }
+                if parts.iter().any(|part| matches!(part, ChatMessageContentPart::Image(_)))
+        ))
+    }
+
+    fn strip_images(mut self) -> ChatCompletionParameters {
+        for message in &mut self.messages {
+            let ChatMessage::User { content: ChatMessageContent::ContentPart(parts), .. } = message else {
+                continue;
+            };
+            for part in parts.iter_mut() {
+                if matches!(part, ChatMessageContentPart::Image(_)) {
+                    *part = ChatMessageContentPart::Text(ChatMessageTextContentPart {
+                        r#type: "text".to_string(),
+                        text: "[image omitted: this model cannot read images]".to_string(),
+                    });
+                }
+            }
+        }
+        self
+    }
+}
+
 pub trait FixMistralAlternating {
     /// Mistral enforces alternating of user/assistant which is problematic in multiturn 
     /// conversation where assistant or toolcall can be cancelled by the user...
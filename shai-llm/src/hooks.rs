@@ -0,0 +1,140 @@
+// Declarative JsonHooks for OpenAI compatible servers (vLLM, LM Studio, gateways...)
+use std::collections::HashMap;
+use async_trait::async_trait;
+use openai_dive::v1::error::APIError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::chat::JsonHooks;
+
+/// Request tweaks for a provider, applied in this order: rename, remove, merge patch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonHooksConfig {
+    /// Extra HTTP headers sent with every request
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// Request fields renamed before sending, e.g. `{"max_completion_tokens": "max_tokens"}`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub rename: HashMap<String, String>,
+    /// Request fields the server rejects, e.g. `["parallel_tool_calls"]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<String>,
+    /// JSON merge patch (RFC 7396) applied to the request body, a null value deletes the field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_patch: Option<Value>,
+    /// Move `<think>` blocks of the answer to reasoning_content
+    #[serde(default = "default_extract_think")]
+    pub extract_think: bool,
+}
+
+fn default_extract_think() -> bool { true }
+
+impl Default for JsonHooksConfig {
+    fn default() -> Self {
+        Self {
+            headers: HashMap::new(),
+            rename: HashMap::new(),
+            remove: Vec::new(),
+            merge_patch: None,
+            extract_think: default_extract_think(),
+        }
+    }
+}
+
+impl JsonHooksConfig {
+    /// Whether the config changes what is sent, extract_think is handled by LlmClient
+    pub fn touches_request(&self) -> bool {
+        !self.headers.is_empty() || !self.rename.is_empty() || !self.remove.is_empty() || self.merge_patch.is_some()
+    }
+
+    pub fn apply(&self, mut json: Value) -> Value {
+        if let Value::Object(map) = &mut json {
+            for (from, to) in &self.rename {
+                if let Some(value) = map.remove(from) {
+                    map.insert(to.clone(), value);
+                }
+            }
+            for field in &self.remove {
+                map.remove(field);
+            }
+        }
+        if let Some(patch) = &self.merge_patch {
+            merge_patch(&mut json, patch);
+        }
+        json
+    }
+}
+
+#[async_trait]
+impl JsonHooks for JsonHooksConfig {
+    async fn before_send(&self, json: Value) -> Result<Value, APIError> {
+        Ok(self.apply(json))
+    }
+}
+
+/// RFC 7396
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let target = target.as_object_mut().unwrap();
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply() {
+        let hooks: JsonHooksConfig = serde_json::from_value(json!({
+            "rename": { "max_completion_tokens": "max_tokens" },
+            "remove": ["parallel_tool_calls"],
+            "merge_patch": { "chat_template_kwargs": { "enable_thinking": false }, "temperature": null }
+        })).unwrap();
+        assert!(hooks.extract_think && hooks.touches_request());
+
+        let request = hooks.apply(json!({
+            "model": "qwen3",
+            "max_completion_tokens": 512,
+            "parallel_tool_calls": true,
+            "temperature": 0.3
+        }));
+        assert_eq!(request, json!({
+            "model": "qwen3",
+            "max_tokens": 512,
+            "chat_template_kwargs": { "enable_thinking": false }
+        }));
+        assert!(!JsonHooksConfig::default().touches_request());
+    }
+
+    #[test]
+    fn test_merge_patch() {
+        // examples from RFC 7396 appendix A
+        for (target, patch, expected) in [
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "b"}), json!({"b": "c"}), json!({"a": "b", "b": "c"})),
+            (json!({"a": "b", "b": "c"}), json!({"a": null}), json!({"b": "c"})),
+            (json!({"a": [{"b": "c"}]}), json!({"a": [1]}), json!({"a": [1]})),
+            (json!({"a": "foo"}), json!("bar"), json!("bar")),
+            (json!({"e": null}), json!({"a": 1}), json!({"e": null, "a": 1})),
+            (json!([1, 2]), json!({"a": "b", "c": null}), json!({"a": "b"})),
+            (json!({}), json!({"a": {"bb": {"ccc": null}}}), json!({"a": {"bb": {}}})),
+        ] {
+            let mut target = target;
+            merge_patch(&mut target, &patch);
+            assert_eq!(target, expected);
+        }
+    }
+}
@@ -4,9 +4,20 @@ pub mod provider;
 pub mod chat;
 pub mod tool;
 pub mod logging;
+pub mod retry;
+pub mod reasoning;
+pub mod model_info;
+pub mod cache;
+pub mod hooks;
 
 // Re-export our client
-pub use client::LlmClient;
+pub use client::{LlmClient, CachedTokens};
+pub use retry::{RetryConfig, RetryNotice, observe_retries};
+pub use reasoning::{ReasoningConfig, ReasoningEffort, ReasoningRequest, ReasoningState};
+pub use providers::replay::{ReplayConfig, ReplayMode};
+pub use model_info::{ModelInfo, ModelPricing, ModelRegistry};
+pub use cache::{CacheConfig, CacheMode};
+pub use hooks::JsonHooksConfig;
 
 pub use tool::{
     ToolDescription, 
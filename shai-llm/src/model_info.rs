@@ -0,0 +1,188 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::provider::LlmError;
+
+/// Bundled table of well known models, prices are the provider list prices
+const BUNDLED_MODELS: &str = include_str!("models.json");
+
+/// Prices in USD per million tokens
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    /// Price of prompt tokens read from the cache, `input` when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read: Option<f64>,
+}
+
+/// What is known about a model, every field is optional so entries from the bundled table,
+/// the provider `/models` metadata and the user overrides can be merged
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vision: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub functions: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<ModelPricing>,
+}
+
+impl ModelInfo {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self { id: id.into(), ..Self::default() }
+    }
+
+    /// Fields set in `other` win
+    pub fn merge(self, other: ModelInfo) -> ModelInfo {
+        ModelInfo {
+            id: self.id,
+            context_window: other.context_window.or(self.context_window),
+            max_output_tokens: other.max_output_tokens.or(self.max_output_tokens),
+            vision: other.vision.or(self.vision),
+            reasoning: other.reasoning.or(self.reasoning),
+            functions: other.functions.or(self.functions),
+            pricing: other.pricing.or(self.pricing),
+        }
+    }
+
+    pub fn supports_vision(&self) -> bool {
+        self.vision.unwrap_or(false)
+    }
+
+    pub fn supports_reasoning(&self) -> bool {
+        self.reasoning.unwrap_or(false)
+    }
+
+    /// Share of the context window used by a prompt, in percent
+    pub fn context_usage(&self, prompt_tokens: u32) -> Option<f64> {
+        self.context_window
+            .filter(|window| *window > 0)
+            .map(|window| prompt_tokens as f64 * 100.0 / window as f64)
+    }
+
+    /// Cost in USD, `cached_tokens` are part of `input_tokens`
+    pub fn cost(&self, input_tokens: u32, output_tokens: u32, cached_tokens: u32) -> Option<f64> {
+        let pricing = self.pricing.as_ref()?;
+        let cached = cached_tokens.min(input_tokens) as f64;
+        let uncached = input_tokens as f64 - cached;
+        let cost = uncached * pricing.input
+            + cached * pricing.cache_read.unwrap_or(pricing.input)
+            + output_tokens as f64 * pricing.output;
+        Some(cost / 1_000_000.0)
+    }
+}
+
+/// Model ids are matched case insensitively, without a `vendor/` or `models/` prefix,
+/// against the longest table entry the id starts with (`claude-sonnet-4-20250514` gets
+/// the `claude-sonnet-4` entry)
+fn normalize(model: &str) -> String {
+    model.rsplit('/').next().unwrap_or(model).to_lowercase()
+}
+
+fn lookup<'a>(models: &'a [ModelInfo], model: &str) -> Option<&'a ModelInfo> {
+    let model = normalize(model);
+    models.iter()
+        .filter(|info| model.starts_with(&normalize(&info.id)))
+        .max_by_key(|info| info.id.len())
+}
+
+/// Bundled model table with user overrides on top
+#[derive(Debug, Clone)]
+pub struct ModelRegistry {
+    bundled: Vec<ModelInfo>,
+    overrides: Vec<ModelInfo>,
+}
+
+impl Default for ModelRegistry {
+    fn default() -> Self {
+        Self::bundled()
+    }
+}
+
+impl ModelRegistry {
+    pub fn bundled() -> Self {
+        Self {
+            bundled: serde_json::from_str(BUNDLED_MODELS).expect("bundled models.json is valid"),
+            overrides: Vec::new(),
+        }
+    }
+
+    pub fn with_overrides(mut self, overrides: Vec<ModelInfo>) -> Self {
+        self.overrides.extend(overrides);
+        self
+    }
+
+    /// Overrides from a json file holding a list of ModelInfo, a missing file is not an error
+    pub fn with_overrides_file(self, path: &Path) -> Result<Self, LlmError> {
+        if !path.exists() {
+            return Ok(self);
+        }
+        let content = std::fs::read_to_string(path)?;
+        let overrides: Vec<ModelInfo> = serde_json::from_str(&content)
+            .map_err(|e| format!("invalid model table {}: {}", path.display(), e))?;
+        Ok(self.with_overrides(overrides))
+    }
+
+    /// Bundled entry, then the provider metadata, then the user override
+    pub fn resolve(&self, model: &str, provided: Option<ModelInfo>) -> ModelInfo {
+        let mut info = lookup(&self.bundled, model).cloned().unwrap_or_default();
+        info.id = model.to_string();
+        if let Some(provided) = provided {
+            info = info.merge(provided);
+        }
+        if let Some(overridden) = lookup(&self.overrides, model) {
+            info = info.merge(overridden.clone());
+        }
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_and_merge() {
+        let registry = ModelRegistry::bundled().with_overrides(vec![ModelInfo {
+            context_window: Some(64000),
+            ..ModelInfo::new("gpt-4o-mini")
+        }]);
+
+        // longest prefix wins, vendor prefix ignored
+        let info = registry.resolve("openai/gpt-4o-mini-2024-07-18", None);
+        assert_eq!(info.id, "openai/gpt-4o-mini-2024-07-18");
+        assert_eq!(info.context_window, Some(64000));
+        assert_eq!(info.pricing.as_ref().unwrap().input, 0.15);
+
+        let info = registry.resolve("claude-sonnet-4-20250514", Some(ModelInfo {
+            context_window: Some(1_000_000),
+            ..ModelInfo::new("claude-sonnet-4-20250514")
+        }));
+        assert_eq!(info.context_window, Some(1_000_000));
+        assert!(info.supports_vision() && info.supports_reasoning());
+
+        assert_eq!(registry.resolve("some-local-model", None), ModelInfo::new("some-local-model"));
+    }
+
+    #[test]
+    fn test_usage_and_cost() {
+        let info = ModelInfo {
+            context_window: Some(200_000),
+            pricing: Some(ModelPricing { input: 3.0, output: 15.0, cache_read: Some(0.3) }),
+            ..ModelInfo::new("claude-sonnet-4")
+        };
+        assert_eq!(info.context_usage(50_000), Some(25.0));
+        // 100k uncached + 900k cached + 100k output
+        let cost = info.cost(1_000_000, 100_000, 900_000).unwrap();
+        assert!((cost - (0.3 + 0.27 + 1.5)).abs() < 1e-9, "{}", cost);
+        assert_eq!(ModelInfo::new("free").cost(10, 10, 0), None);
+    }
+}
@@ -2,6 +2,9 @@ use std::fmt::Debug;
 use async_trait::async_trait;
 use futures::Stream;
 use std::error::Error;
+use crate::reasoning::ReasoningRequest;
+use crate::model_info::ModelInfo;
+use crate::hooks::JsonHooksConfig;
 use openai_dive::v1::endpoints::chat::Chat;
 use openai_dive::v1::resources::{
     chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse},
@@ -55,6 +58,12 @@ pub trait LlmProvider: Send + Sync {
             .ok_or_else(|| "no model available".into())
     }
 
+    /// Context window, capabilities and pricing from the provider `/models` metadata,
+    /// empty when the provider does not expose them (see ModelRegistry for the bundled table)
+    async fn models_info(&self) -> Result<Vec<ModelInfo>, LlmError> {
+        Ok(vec![])
+    }
+
     async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError>;
     
     async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError>;
@@ -63,6 +72,23 @@ pub trait LlmProvider: Send + Sync {
     
     fn supports_structured_output(&self, model: String) -> bool;
     
+    /// Translate the provider agnostic reasoning config of the request (see ReasoningRequest),
+    /// OpenAI compatible APIs take `reasoning_effort` and have no use for the reasoning state
+    fn adapt_reasoning(&self, request: ChatCompletionParameters) -> ChatCompletionParameters {
+        request.reasoning_as_effort().strip_reasoning_state()
+    }
+
+    /// Install declarative request tweaks (see JsonHooksConfig), only providers talking to
+    /// arbitrary OpenAI compatible servers support them
+    fn set_json_hooks(&mut self, hooks: JsonHooksConfig) -> Result<(), LlmError> {
+        Err(format!("provider {} does not support json hooks", self.name()).into())
+    }
+
+    /// Whether `route` is a route name this provider dispatches on (see RouterProvider)
+    fn has_route(&self, route: &str) -> bool {
+        false
+    }
+
     fn name(&self) -> &'static str;
     
     /// Returns provider information including environment variables
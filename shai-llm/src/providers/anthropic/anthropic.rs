@@ -1,4 +1,6 @@
 use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use crate::retry::HttpError;
+use crate::reasoning::{ReasoningRequest, ReasoningState};
 use super::api::*;
 use async_trait::async_trait;
 use reqwest::Client;
@@ -101,7 +103,9 @@ impl AnthropicProvider {
             AnthropicStreamEvent::ContentBlockDelta { delta, .. } => {
                 let content = match delta {
                     AnthropicDelta::TextDelta { text } => Some(ChatMessageContent::Text(text)),
-                    AnthropicDelta::ThinkingDelta { thinking: _ } => return Ok(None), // Skip thinking content
+                    AnthropicDelta::ThinkingDelta { thinking } => return Ok(Some(Self::reasoning_chunk(thinking))),
+                    // signed blocks are only needed back with tool_use blocks, which the stream does not emit
+                    AnthropicDelta::SignatureDelta { .. } => return Ok(None),
                     AnthropicDelta::InputJsonDelta { partial_json } => Some(ChatMessageContent::Text(partial_json)),
                 };
 
@@ -185,27 +189,100 @@ impl AnthropicProvider {
         }
     }
 
+    /// Stream chunk carrying a piece of thinking text
+    fn reasoning_chunk(reasoning_content: String) -> ChatCompletionChunkResponse {
+        ChatCompletionChunkResponse {
+            id: Some(format!("anthropic-{}", uuid::Uuid::new_v4())),
+            object: "chat.completion.chunk".to_string(),
+            created: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as u32,
+            model: "claude".to_string(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: Some(0),
+                delta: DeltaChatMessage::Assistant {
+                    content: None,
+                    reasoning_content: Some(reasoning_content),
+                    refusal: None,
+                    name: None,
+                    tool_calls: None,
+                },
+                finish_reason: None,
+                logprobs: None,
+            }],
+            usage: None,
+            system_fingerprint: None,
+        }
+    }
+
     pub(crate) fn convert_to_anthropic_format(&self, request: &ChatCompletionParameters) -> serde_json::Value {
-        let (system_messages, messages) = self.convert_messages(&request.messages);
+        let thinking = request.reasoning();
+        let (system_messages, messages) = self.convert_messages(&request.messages, thinking.is_some());
+
+        let mut max_tokens = request.max_tokens.unwrap_or(1000);
+        if let Some(reasoning) = &thinking {
+            // the budget is part of max_tokens, keep room for the answer
+            max_tokens = max_tokens.max(reasoning.budget_tokens() + 4096);
+        }
 
         let mut anthropic_request = json!({
             "model": request.model,
-            "max_tokens": request.max_tokens.unwrap_or(1000),
+            "max_tokens": max_tokens,
             "messages": messages
         });
 
+        if let Some(reasoning) = &thinking {
+            anthropic_request["thinking"] = json!({
+                "type": "enabled",
+                "budget_tokens": reasoning.budget_tokens()
+            });
+        }
+
+        // cache breakpoints: the prefix (tools, system, trace up to the last message) is
+        // identical on the next step, Anthropic bills it at the cache read price
         if !system_messages.is_empty() {
-            anthropic_request["system"] = json!(system_messages.join("\n\n"));
+            anthropic_request["system"] = json!([{
+                "type": "text",
+                "text": system_messages.join("\n\n"),
+                "cache_control": { "type": "ephemeral" }
+            }]);
         }
 
         if let Some(tools) = &request.tools {
-            anthropic_request["tools"] = json!(self.convert_tools(tools));
+            let mut tools = self.convert_tools(tools);
+            if let Some(last) = tools.last_mut() {
+                last["cache_control"] = json!({ "type": "ephemeral" });
+            }
+            anthropic_request["tools"] = json!(tools);
+        }
+
+        if let Some(last) = anthropic_request["messages"].as_array_mut().and_then(|m| m.last_mut()) {
+            Self::add_cache_breakpoint(last);
         }
 
         anthropic_request
     }
 
-    fn convert_messages(&self, messages: &[ChatMessage]) -> (Vec<String>, Vec<serde_json::Value>) {
+    /// Mark the last content block of a message as a cache breakpoint
+    fn add_cache_breakpoint(message: &mut serde_json::Value) {
+        if let Some(text) = message["content"].as_str() {
+            if text.is_empty() {
+                return; // empty text blocks are rejected
+            }
+            message["content"] = json!([{ "type": "text", "text": text }]);
+        }
+        if let Some(block) = message["content"].as_array_mut().and_then(|blocks| blocks.last_mut()) {
+            if matches!(block["type"].as_str(), Some("thinking") | Some("redacted_thinking")) {
+                return; // thinking blocks cannot carry cache_control
+            }
+            block["cache_control"] = json!({ "type": "ephemeral" });
+        }
+    }
+
+    /// With thinking enabled, the signed thinking blocks kept in the reasoning state of an
+    /// assistant turn are sent back before its content
+    fn convert_messages(&self, messages: &[ChatMessage], thinking: bool) -> (Vec<String>, Vec<serde_json::Value>) {
         let mut system_messages = Vec::new();
         let mut converted_messages = Vec::new();
 
@@ -217,12 +294,19 @@ impl AnthropicProvider {
                 ChatMessage::User { content, .. } => {
                     converted_messages.push(json!({
                         "role": "user",
-                        "content": self.extract_content_text(content)
+                        "content": self.convert_user_content(content)
                     }));
                 }
-                ChatMessage::Assistant { content, tool_calls, .. } => {
+                ChatMessage::Assistant { .. } => {
                     let is_final = i == messages.len() - 1;
-                    if let Some(assistant_content) = self.build_assistant_content(content, tool_calls, is_final) {
+                    let blocks: Vec<serde_json::Value> = msg.reasoning_state(self.name())
+                        .filter(|_| thinking)
+                        .and_then(|state| serde_json::from_value(state).ok())
+                        .unwrap_or_default();
+                    let ChatMessage::Assistant { content, tool_calls, .. } = msg.clone().without_reasoning_state() else {
+                        continue;
+                    };
+                    if let Some(assistant_content) = self.build_assistant_content(&content, &tool_calls, &blocks, is_final) {
                         converted_messages.push(json!({
                             "role": "assistant",
                             "content": assistant_content
@@ -251,10 +335,11 @@ impl AnthropicProvider {
         (system_messages, converted_messages)
     }
 
-    fn build_assistant_content(&self, content: &Option<ChatMessageContent>, tool_calls: &Option<Vec<ToolCall>>, is_final: bool) -> Option<serde_json::Value> {
+    fn build_assistant_content(&self, content: &Option<ChatMessageContent>, tool_calls: &Option<Vec<ToolCall>>, thinking: &[serde_json::Value], is_final: bool) -> Option<serde_json::Value> {
+        let mut blocks = thinking.to_vec();
+
         match tool_calls {
             Some(calls) => {
-                let mut blocks = Vec::new();
                 
                 // Add text content if present
                 if let Some(text_content) = content {
@@ -283,8 +368,13 @@ impl AnthropicProvider {
                 // Only allow empty content if this is the final assistant message
                 if text.is_empty() && !is_final {
                     None // Skip this empty assistant message
-                } else {
+                } else if blocks.is_empty() {
                     Some(json!(text))
+                } else {
+                    if !text.is_empty() {
+                        blocks.push(json!({"type": "text", "text": text}));
+                    }
+                    Some(json!(blocks))
                 }
             }
         }
@@ -300,6 +390,26 @@ impl AnthropicProvider {
         }).collect()
     }
 
+    /// Text, or text and image blocks when the message carries images
+    fn convert_user_content(&self, content: &ChatMessageContent) -> serde_json::Value {
+        let ChatMessageContent::ContentPart(parts) = content else {
+            return json!(self.extract_content_text(content));
+        };
+        let blocks: Vec<serde_json::Value> = parts.iter().filter_map(|part| match part {
+            openai_dive::v1::resources::chat::ChatMessageContentPart::Text(text_part) => {
+                Some(json!({ "type": "text", "text": text_part.text }))
+            }
+            openai_dive::v1::resources::chat::ChatMessageContentPart::Image(image_part) => {
+                Some(convert_image(&image_part.image_url.url))
+            }
+            _ => None, // audio is not supported
+        }).collect();
+        if blocks.iter().all(|block| block["type"] == "text") {
+            return json!(self.extract_content_text(content));
+        }
+        json!(blocks)
+    }
+
     fn extract_content_text(&self, content: &ChatMessageContent) -> String {
         match content {
             ChatMessageContent::Text(text) => text.clone(),
@@ -317,8 +427,31 @@ impl AnthropicProvider {
         }
     }
 
-    fn convert_from_anthropic_format(&self, response: serde_json::Value) -> Result<ChatCompletionResponse, LlmError> {
+    /// Anthropic `input_tokens` excludes the cache, prompt_tokens counts it like OpenAI does
+    /// with the cache reads reported as `prompt_tokens_details.cached_tokens`
+    fn convert_usage(usage: &serde_json::Value) -> Usage {
+        let tokens = |key: &str| usage[key].as_u64().unwrap_or(0) as u32;
+        let cache_read = tokens("cache_read_input_tokens");
+        let prompt_tokens = tokens("input_tokens") + cache_read + tokens("cache_creation_input_tokens");
+        let completion_tokens = tokens("output_tokens");
+
+        Usage {
+            input_tokens: None,
+            input_tokens_details: None,
+            output_tokens: None,
+            output_tokens_details: None,
+            prompt_tokens: Some(prompt_tokens),
+            completion_tokens: Some(completion_tokens),
+            total_tokens: prompt_tokens + completion_tokens,
+            prompt_tokens_details: serde_json::from_value(json!({ "cached_tokens": cache_read })).ok().flatten(),
+            completion_tokens_details: None,
+        }
+    }
+
+    pub(crate) fn convert_from_anthropic_format(&self, response: serde_json::Value) -> Result<ChatCompletionResponse, LlmError> {
         let mut text_content = Vec::new();
+        let mut reasoning_content = Vec::new();
+        let mut thinking_blocks = Vec::new();
         let mut tool_calls = Vec::new();
         
         // Parse content array
@@ -330,6 +463,13 @@ impl AnthropicProvider {
                             text_content.push(text.to_string());
                         }
                     }
+                    Some("thinking") => {
+                        reasoning_content.push(content_block["thinking"].as_str().unwrap_or("").to_string());
+                        thinking_blocks.push(content_block.clone());
+                    }
+                    Some("redacted_thinking") => {
+                        thinking_blocks.push(content_block.clone());
+                    }
                     Some("tool_use") => {
                         if let (Some(id), Some(name), Some(input)) = (
                             content_block["id"].as_str(),
@@ -359,9 +499,24 @@ impl AnthropicProvider {
             Some(ChatMessageContent::Text(combined_text))
         };
         
+        let reasoning_content = reasoning_content.join("\n\n");
+
         // Convert tool_calls to Option
         let tool_calls_option = if tool_calls.is_empty() { None } else { Some(tool_calls) };
-        
+
+        let mut message = ChatMessage::Assistant {
+            content,
+            reasoning_content: (!reasoning_content.is_empty()).then_some(reasoning_content),
+            refusal: None,
+            name: None,
+            audio: None,
+            tool_calls: tool_calls_option,
+        };
+        // Anthropic wants the signed thinking blocks back in the following turns
+        if !thinking_blocks.is_empty() {
+            message = message.with_reasoning_state(self.name(), json!(thinking_blocks));
+        }
+
         Ok(ChatCompletionResponse {
             id: Some(response["id"].as_str().unwrap_or("").to_string()),
             object: "chat.completion".to_string(),
@@ -369,28 +524,11 @@ impl AnthropicProvider {
             model: response["model"].as_str().unwrap_or("").to_string(),
             choices: vec![ChatCompletionChoice {
                 index: 0,
-                message: ChatMessage::Assistant {
-                    content,
-                    reasoning_content: None,
-                    refusal: None,
-                    name: None,
-                    audio: None,
-                    tool_calls: tool_calls_option,
-                },
+                message,
                 finish_reason: Some(FinishReason::StopSequenceReached),
                 logprobs: None,
             }],
-            usage: Some(Usage {
-                input_tokens: None,
-                input_tokens_details: None,
-                output_tokens: None,
-                output_tokens_details: None,
-                prompt_tokens: Some(response["usage"]["input_tokens"].as_u64().unwrap_or(0) as u32),
-                completion_tokens: Some(response["usage"]["output_tokens"].as_u64().unwrap_or(0) as u32),
-                total_tokens: response["usage"]["input_tokens"].as_u64().unwrap_or(0) as u32 + response["usage"]["output_tokens"].as_u64().unwrap_or(0) as u32,
-                prompt_tokens_details: None,
-                completion_tokens_details: None,
-            }),
+            usage: Some(Self::convert_usage(&response["usage"])),
             service_tier: None,
             system_fingerprint: None,
         })
@@ -455,8 +593,7 @@ impl LlmProvider for AnthropicProvider {
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(format!("Anthropic API error: {}", error_text).into());
+            return Err(HttpError::from_response(response, "Anthropic API error").await.into());
         }
 
         let anthropic_response: serde_json::Value = response.json().await?;
@@ -478,8 +615,7 @@ impl LlmProvider for AnthropicProvider {
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(format!("Anthropic API streaming error: {}", error_text).into());
+            return Err(HttpError::from_response(response, "Anthropic API streaming error").await.into());
         }
 
         Self::parse_anthropic_stream(response).await
@@ -489,6 +625,11 @@ impl LlmProvider for AnthropicProvider {
         true // Anthropic supports function calling via tool use
     }
 
+    /// The reasoning config becomes a `thinking` budget in convert_to_anthropic_format
+    fn adapt_reasoning(&self, request: ChatCompletionParameters) -> ChatCompletionParameters {
+        request
+    }
+
     fn supports_structured_output(&self, model: String) -> bool {
         false
     }
@@ -507,4 +648,13 @@ impl LlmProvider for AnthropicProvider {
         }
     }
     
-}
\ No newline at end of file
+}
+
+/// Image block from a data URL (base64) or a plain URL
+fn convert_image(url: &str) -> serde_json::Value {
+    if let Some((header, data)) = url.strip_prefix("data:").and_then(|rest| rest.split_once(',')) {
+        let media_type = header.trim_end_matches(";base64");
+        return json!({ "type": "image", "source": { "type": "base64", "media_type": media_type, "data": data } });
+    }
+    json!({ "type": "image", "source": { "type": "url", "url": url } })
+}
@@ -5,6 +5,7 @@ mod tests {
     use crate::provider::LlmProvider;
     use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, ChatCompletionParametersBuilder};
     use serde_json::json;
+    use crate::reasoning::{ReasoningConfig, ReasoningRequest};
 
     fn setup_provider() -> AnthropicProvider {
         // Assume ANTHROPIC_API_KEY exists in environment
@@ -36,13 +37,15 @@ mod tests {
         
         // Check that system message is extracted to top-level system parameter
         assert!(anthropic_format.get("system").is_some());
-        assert_eq!(anthropic_format["system"].as_str().unwrap(), "You are a helpful assistant.");
+        assert_eq!(anthropic_format["system"][0]["text"].as_str().unwrap(), "You are a helpful assistant.");
+        assert_eq!(anthropic_format["system"][0]["cache_control"], json!({"type": "ephemeral"}));
         
         // Check that messages array only contains non-system messages
         let messages = anthropic_format["messages"].as_array().unwrap();
         assert_eq!(messages.len(), 1);
         assert_eq!(messages[0]["role"].as_str().unwrap(), "user");
-        assert_eq!(messages[0]["content"].as_str().unwrap(), "Hello!");
+        // the last message is a cache breakpoint
+        assert_eq!(messages[0]["content"], json!([{"type": "text", "text": "Hello!", "cache_control": {"type": "ephemeral"}}]));
     }
 
     #[tokio::test]
@@ -74,7 +77,7 @@ mod tests {
         
         // Check that system messages are combined
         assert!(anthropic_format.get("system").is_some());
-        let system_content = anthropic_format["system"].as_str().unwrap();
+        let system_content = anthropic_format["system"][0]["text"].as_str().unwrap();
         assert!(system_content.contains("You are a helpful assistant."));
         assert!(system_content.contains("Always be concise."));
         
@@ -173,5 +176,128 @@ mod tests {
         assert_eq!(tool_result_content[0]["type"].as_str().unwrap(), "tool_result");
         assert_eq!(tool_result_content[0]["tool_use_id"].as_str().unwrap(), "toolu_018qHepKa8d4rbZ9qskd2vqw");
         assert_eq!(tool_result_content[0]["content"].as_str().unwrap(), "Successfully updated file '/Users/lloiseau/Work/test/main.py' with 22 bytes");
+        assert_eq!(tool_result_content[0]["cache_control"], json!({"type": "ephemeral"}));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_cache_breakpoints_and_cached_usage() {
+        let provider = AnthropicProvider::new("test".to_string());
+        let tool = |name: &str| openai_dive::v1::resources::chat::ChatCompletionTool {
+            r#type: openai_dive::v1::resources::chat::ChatCompletionToolType::Function,
+            function: openai_dive::v1::resources::chat::ChatCompletionFunction {
+                name: name.to_string(),
+                description: None,
+                parameters: json!({"type": "object"}),
+            },
+        };
+        let request = ChatCompletionParametersBuilder::default()
+            .model("claude-sonnet-4".to_string())
+            .messages(vec![
+                ChatMessage::System { content: ChatMessageContent::Text("system".to_string()), name: None },
+                ChatMessage::User { content: ChatMessageContent::Text("first".to_string()), name: None },
+                ChatMessage::Assistant { content: Some(ChatMessageContent::Text("answer".to_string())), reasoning_content: None, refusal: None, name: None, audio: None, tool_calls: None },
+                ChatMessage::User { content: ChatMessageContent::Text("second".to_string()), name: None },
+            ])
+            .tools(vec![tool("read"), tool("write")])
+            .build()
+            .unwrap();
+
+        let anthropic_format = provider.convert_to_anthropic_format(&request);
+        let tools = anthropic_format["tools"].as_array().unwrap();
+        assert!(tools[0].get("cache_control").is_none());
+        assert_eq!(tools[1]["cache_control"], json!({"type": "ephemeral"}));
+
+        // only the latest message is a breakpoint
+        let messages = anthropic_format["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["content"], json!("first"));
+        assert_eq!(messages[2]["content"][0]["cache_control"], json!({"type": "ephemeral"}));
+
+        let response = provider.convert_from_anthropic_format(json!({
+            "id": "msg_1",
+            "model": "claude-sonnet-4",
+            "content": [{"type": "text", "text": "hi"}],
+            "usage": {"input_tokens": 10, "cache_read_input_tokens": 2000, "cache_creation_input_tokens": 100, "output_tokens": 5}
+        })).unwrap();
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, Some(2110));
+        assert_eq!(crate::CachedTokens::cached_tokens(&usage), 2000);
+    }
+
+    #[test]
+    fn test_signed_thinking_round_trip() {
+        let provider = AnthropicProvider::new("test".to_string());
+
+        // the thinking text lands in reasoning_content, the signed blocks in the reasoning state
+        let response = provider.convert_from_anthropic_format(json!({
+            "id": "msg_1",
+            "model": "claude-sonnet-4",
+            "content": [
+                {"type": "thinking", "thinking": "I should write the file", "signature": "c2lnbmVk"},
+                {"type": "redacted_thinking", "data": "ZW5jcnlwdGVk"},
+                {"type": "tool_use", "id": "toolu_signed_1", "name": "write", "input": {"path": "main.py"}}
+            ],
+            "usage": {"input_tokens": 10, "output_tokens": 20}
+        })).unwrap();
+        let assistant = response.choices[0].message.clone();
+        let ChatMessage::Assistant { reasoning_content: Some(reasoning), .. } = &assistant else {
+            panic!("missing reasoning_content");
+        };
+        assert_eq!(reasoning, "I should write the file");
+
+        // the state is saved and reloaded with the trace
+        let trace = vec![
+            ChatMessage::User { content: ChatMessageContent::Text("write main.py".to_string()), name: None },
+            assistant,
+            ChatMessage::Tool { content: ChatMessageContent::Text("done".to_string()), tool_call_id: "toolu_signed_1".to_string() },
+        ];
+        let trace: Vec<ChatMessage> = serde_json::from_str(&serde_json::to_string(&trace).unwrap()).unwrap();
+
+        // and the blocks are sent back first in the assistant turn when thinking is enabled
+        let request = ChatCompletionParametersBuilder::default()
+            .model("claude-sonnet-4".to_string())
+            .messages(trace)
+            .build()
+            .unwrap()
+            .with_reasoning(ReasoningConfig::budget(2048));
+
+        let anthropic_format = provider.convert_to_anthropic_format(&request);
+        assert_eq!(anthropic_format["thinking"], json!({"type": "enabled", "budget_tokens": 2048}));
+        assert!(anthropic_format["max_tokens"].as_u64().unwrap() > 2048);
+
+        let blocks = anthropic_format["messages"][1]["content"].as_array().unwrap();
+        assert_eq!(blocks[0], json!({"type": "thinking", "thinking": "I should write the file", "signature": "c2lnbmVk"}));
+        assert_eq!(blocks[1], json!({"type": "redacted_thinking", "data": "ZW5jcnlwdGVk"}));
+        assert_eq!(blocks[2]["type"], "tool_use");
+        assert_eq!(blocks.len(), 3);
+    }
+
+    #[test]
+    fn test_image_conversion() {
+        use openai_dive::v1::resources::chat::{ChatMessageContentPart, ChatMessageImageContentPart, ChatMessageTextContentPart, ImageUrlType};
+        let provider = AnthropicProvider::new("test".to_string());
+        let image = |url: &str| ChatMessageContentPart::Image(ChatMessageImageContentPart {
+            r#type: "image_url".to_string(),
+            image_url: ImageUrlType { url: url.to_string(), detail: None },
+        });
+        let request = ChatCompletionParametersBuilder::default()
+            .model("claude-sonnet-4".to_string())
+            .messages(vec![
+                ChatMessage::User {
+                    content: ChatMessageContent::ContentPart(vec![
+                        ChatMessageContentPart::Text(ChatMessageTextContentPart { r#type: "text".to_string(), text: "Compare these".to_string() }),
+                        image("data:image/png;base64,iVBORw0KGgo="),
+                        image("https://example.com/cat.jpg"),
+                    ]),
+                    name: None,
+                },
+            ])
+            .build()
+            .unwrap();
+
+        let anthropic_format = provider.convert_to_anthropic_format(&request);
+        let content = anthropic_format["messages"][0]["content"].as_array().unwrap();
+        assert_eq!(content[0], json!({"type": "text", "text": "Compare these"}));
+        assert_eq!(content[1], json!({"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo="}}));
+        assert_eq!(content[2]["source"], json!({"type": "url", "url": "https://example.com/cat.jpg"}));
+    }
+}
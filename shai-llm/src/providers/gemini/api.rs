@@ -0,0 +1,29 @@
+use serde::{Serialize, Deserialize};
+
+pub const GEMINI_API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta";
+pub const GEMINI_DEFAULT_MODEL: &str = "gemini-2.5-flash";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiModel {
+    pub name: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub input_token_limit: Option<u32>,
+    #[serde(default)]
+    pub output_token_limit: Option<u32>,
+    #[serde(default)]
+    pub supported_generation_methods: Vec<String>,
+    #[serde(default)]
+    pub thinking: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiListModelsResponse {
+    #[serde(default)]
+    pub models: Vec<GeminiModel>,
+    #[serde(default)]
+    pub next_page_token: Option<String>,
+}
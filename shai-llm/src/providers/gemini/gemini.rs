@@ -0,0 +1,607 @@
+use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use crate::retry::HttpError;
+use crate::reasoning::{ReasoningRequest, ReasoningState};
+use crate::model_info::ModelInfo;
+use super::api::*;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use reqwest::{Client, RequestBuilder};
+use serde_json::{json, Map, Value};
+use futures::{StreamExt, stream};
+use openai_dive::v1::resources::{
+    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse, ChatMessage, ChatMessageContent, ChatMessageContentPart, ChatCompletionChoice, ToolCall, Function},
+    model::{ListModelResponse, Model},
+    shared::{FinishReason, Usage},
+};
+
+/// Key of the thought signatures in the reasoning state of an assistant turn (see ReasoningState)
+const SIGNATURES_STATE: &str = "gemini";
+
+pub struct GeminiProvider {
+    api_key: String,
+    base_url: String,
+    client: Client,
+}
+
+impl GeminiProvider {
+    pub fn new(api_key: String, base_url: Option<String>) -> Self {
+        Self {
+            api_key,
+            base_url: base_url.unwrap_or_else(|| GEMINI_API_BASE.to_string()).trim_end_matches('/').to_string(),
+            client: Client::new(),
+        }
+    }
+
+    /// Create Gemini provider from environment variables
+    /// Returns None if required environment variables are not set
+    pub fn from_env() -> Option<Self> {
+        std::env::var("GEMINI_API_KEY").ok().map(|api_key| {
+            Self::new(api_key, std::env::var("GEMINI_BASE_URL").ok())
+        })
+    }
+
+    /// Models able to generateContent
+    async fn list_models(&self) -> Result<Vec<GeminiModel>, LlmError> {
+        let response = self.authenticate(self.client.get(format!("{}/models", self.base_url)))
+            .query(&[("pageSize", "1000")])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(HttpError::from_response(response, "Gemini API error").await.into());
+        }
+
+        let models: GeminiListModelsResponse = response.json().await?;
+        Ok(models.models.into_iter()
+            .filter(|m| m.supported_generation_methods.iter().any(|method| method == "generateContent"))
+            .collect())
+    }
+
+    /// Vertex AI endpoints take an OAuth access token, the Gemini API an API key
+    fn authenticate(&self, request: RequestBuilder) -> RequestBuilder {
+        if self.base_url.contains("aiplatform.googleapis.com") {
+            request.bearer_auth(&self.api_key)
+        } else {
+            request.header("x-goog-api-key", &self.api_key)
+        }
+    }
+
+    fn model_url(&self, model: &str, method: &str) -> String {
+        format!("{}/models/{}:{}", self.base_url, model.trim_start_matches("models/"), method)
+    }
+
+    pub(crate) fn convert_to_gemini_format(&self, request: &ChatCompletionParameters) -> Value {
+        let (system, contents) = self.convert_messages(&request.messages);
+
+        let mut gemini_request = json!({ "contents": contents });
+
+        if !system.is_empty() {
+            gemini_request["systemInstruction"] = json!({ "parts": [{ "text": system.join("\n\n") }] });
+        }
+
+        if let Some(tools) = &request.tools {
+            let declarations: Vec<Value> = tools.iter().map(|tool| {
+                let mut declaration = json!({
+                    "name": tool.function.name,
+                    "description": tool.function.description.as_ref().unwrap_or(&tool.function.name),
+                });
+                let parameters = sanitize_schema(&tool.function.parameters);
+                if parameters["properties"].as_object().is_some_and(|p| !p.is_empty()) {
+                    declaration["parameters"] = parameters;
+                }
+                declaration
+            }).collect();
+            gemini_request["tools"] = json!([{ "functionDeclarations": declarations }]);
+        }
+
+        if let Some(choice) = request.tool_choice.as_ref().and_then(|c| serde_json::to_value(c).ok()) {
+            let config = match choice.as_str() {
+                Some("none") => json!({ "mode": "NONE" }),
+                Some("required") => json!({ "mode": "ANY" }),
+                Some(_) => json!({ "mode": "AUTO" }),
+                None => json!({ "mode": "ANY", "allowedFunctionNames": [choice["function"]["name"]] }),
+            };
+            gemini_request["toolConfig"] = json!({ "functionCallingConfig": config });
+        }
+
+        let mut generation = Map::new();
+        if let Some(temperature) = request.temperature {
+            generation.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(top_p) = request.top_p {
+            generation.insert("topP".to_string(), json!(top_p));
+        }
+        if let Some(max_tokens) = request.max_completion_tokens.or(request.max_tokens) {
+            generation.insert("maxOutputTokens".to_string(), json!(max_tokens));
+        }
+        match request.stop.as_ref().and_then(|s| serde_json::to_value(s).ok()) {
+            Some(Value::String(stop)) => { generation.insert("stopSequences".to_string(), json!([stop])); }
+            Some(stop @ Value::Array(_)) => { generation.insert("stopSequences".to_string(), stop); }
+            _ => {}
+        }
+        if let Some(format) = request.response_format.as_ref().and_then(|f| serde_json::to_value(f).ok()) {
+            match format["type"].as_str() {
+                Some("json_schema") => {
+                    generation.insert("responseMimeType".to_string(), json!("application/json"));
+                    generation.insert("responseSchema".to_string(), sanitize_schema(&format["json_schema"]["schema"]));
+                }
+                Some("json_object") => {
+                    generation.insert("responseMimeType".to_string(), json!("application/json"));
+                }
+                _ => {}
+            }
+        }
+        if let Some(reasoning) = request.reasoning() {
+            generation.insert("thinkingConfig".to_string(), json!({
+                "thinkingBudget": reasoning.budget_tokens(),
+                "includeThoughts": true
+            }));
+        }
+        if !generation.is_empty() {
+            gemini_request["generationConfig"] = Value::Object(generation);
+        }
+
+        gemini_request
+    }
+
+    /// Gemini only knows `user` and `model` turns: tool results are `functionResponse` parts of a
+    /// user turn and consecutive turns of the same role are merged
+    fn convert_messages(&self, messages: &[ChatMessage]) -> (Vec<String>, Vec<Value>) {
+        let mut system = Vec::new();
+        let mut contents: Vec<Value> = Vec::new();
+        let mut call_names: HashMap<String, String> = HashMap::new();
+
+        for msg in messages {
+            let (role, parts) = match msg {
+                ChatMessage::System { content, .. } => {
+                    system.push(self.extract_content_text(content));
+                    continue;
+                }
+                ChatMessage::User { content, .. } | ChatMessage::Developer { content, .. } => {
+                    ("user", self.convert_content(content))
+                }
+                ChatMessage::Assistant { .. } => {
+                    let signatures = msg.reasoning_state(SIGNATURES_STATE).unwrap_or_default();
+                    let ChatMessage::Assistant { content, tool_calls, .. } = msg.clone().without_reasoning_state() else {
+                        continue;
+                    };
+                    let mut parts = content.as_ref().map(|c| self.convert_content(c)).unwrap_or_default();
+                    for call in tool_calls.iter().flatten() {
+                        call_names.insert(call.id.clone(), call.function.name.clone());
+                        let args: Value = serde_json::from_str(&call.function.arguments).unwrap_or_else(|_| json!({}));
+                        let mut part = json!({ "functionCall": { "name": call.function.name, "args": args } });
+                        // thinking models reject a function call turn without its signature
+                        if let Some(signature) = signatures.get(&call.id) {
+                            part["thoughtSignature"] = signature.clone();
+                        }
+                        parts.push(part);
+                    }
+                    ("model", parts)
+                }
+                ChatMessage::Tool { content, tool_call_id } => {
+                    let name = call_names.get(tool_call_id).cloned().unwrap_or_else(|| tool_call_id.clone());
+                    ("user", vec![json!({
+                        "functionResponse": {
+                            "name": name,
+                            "response": { "content": self.extract_content_text(content) }
+                        }
+                    })])
+                }
+            };
+
+            if parts.is_empty() {
+                continue;
+            }
+            match contents.last_mut() {
+                Some(last) if last["role"] == role => {
+                    if let Some(previous) = last["parts"].as_array_mut() {
+                        previous.extend(parts);
+                    }
+                }
+                _ => contents.push(json!({ "role": role, "parts": parts })),
+            }
+        }
+
+        (system, contents)
+    }
+
+    fn convert_content(&self, content: &ChatMessageContent) -> Vec<Value> {
+        match content {
+            ChatMessageContent::Text(text) if text.is_empty() => vec![],
+            ChatMessageContent::Text(text) => vec![json!({ "text": text })],
+            ChatMessageContent::ContentPart(parts) => {
+                parts.iter().filter_map(|part| match part {
+                    ChatMessageContentPart::Text(text_part) => Some(json!({ "text": text_part.text })),
+                    ChatMessageContentPart::Image(image_part) => Some(convert_image(&image_part.image_url.url)),
+                    _ => None, // Skip audio, etc.
+                }).collect()
+            }
+            ChatMessageContent::None => vec![],
+        }
+    }
+
+    fn extract_content_text(&self, content: &ChatMessageContent) -> String {
+        match content {
+            ChatMessageContent::Text(text) => text.clone(),
+            ChatMessageContent::ContentPart(parts) => {
+                parts.iter().filter_map(|part| match part {
+                    ChatMessageContentPart::Text(text_part) => Some(text_part.text.clone()),
+                    _ => None,
+                }).collect::<Vec<_>>().join(" ")
+            }
+            ChatMessageContent::None => String::new(),
+        }
+    }
+
+    fn convert_usage(usage: &Value) -> Usage {
+        let tokens = |key: &str| usage[key].as_u64().unwrap_or(0) as u32;
+        let prompt_tokens = tokens("promptTokenCount");
+        // thinking tokens are billed as output
+        let completion_tokens = tokens("candidatesTokenCount") + tokens("thoughtsTokenCount");
+
+        Usage {
+            input_tokens: None,
+            input_tokens_details: None,
+            output_tokens: None,
+            output_tokens_details: None,
+            prompt_tokens: Some(prompt_tokens),
+            completion_tokens: Some(completion_tokens),
+            total_tokens: prompt_tokens + completion_tokens,
+            prompt_tokens_details: serde_json::from_value(json!({ "cached_tokens": tokens("cachedContentTokenCount") })).ok().flatten(),
+            completion_tokens_details: None,
+        }
+    }
+
+    /// Split the parts of a candidate into text, thoughts and tool calls, with the thought
+    /// signatures of the function calls by tool call id to be sent back with them
+    fn convert_parts(parts: &[Value]) -> (String, String, Vec<ToolCall>, Map<String, Value>) {
+        let mut text = String::new();
+        let mut reasoning = String::new();
+        let mut tool_calls = Vec::new();
+        let mut signatures = Map::new();
+
+        for part in parts {
+            if let Some(call) = part.get("functionCall") {
+                let id = call["id"].as_str().map(str::to_string).unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple()));
+                if let Some(signature) = part.get("thoughtSignature") {
+                    signatures.insert(id.clone(), signature.clone());
+                }
+                tool_calls.push(ToolCall {
+                    id,
+                    r#type: "function".to_string(),
+                    function: Function {
+                        name: call["name"].as_str().unwrap_or_default().to_string(),
+                        arguments: call.get("args").map(|a| a.to_string()).unwrap_or_else(|| "{}".to_string()),
+                    },
+                });
+            } else if let Some(t) = part["text"].as_str() {
+                if part["thought"].as_bool().unwrap_or(false) {
+                    reasoning.push_str(t);
+                } else {
+                    text.push_str(t);
+                }
+            }
+        }
+        (text, reasoning, tool_calls, signatures)
+    }
+
+    /// Assistant message of the parts, the signatures kept as its reasoning state
+    fn assistant_message(text: String, reasoning: String, tool_calls: Vec<ToolCall>, signatures: Map<String, Value>) -> ChatMessage {
+        let message = ChatMessage::Assistant {
+            content: (!text.is_empty()).then_some(ChatMessageContent::Text(text)),
+            reasoning_content: (!reasoning.is_empty()).then_some(reasoning),
+            refusal: None,
+            name: None,
+            audio: None,
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+        };
+        if signatures.is_empty() {
+            message
+        } else {
+            message.with_reasoning_state(SIGNATURES_STATE, Value::Object(signatures))
+        }
+    }
+
+    fn convert_finish_reason(reason: &str, has_tool_calls: bool) -> &'static str {
+        match reason {
+            _ if has_tool_calls => "tool_calls",
+            "MAX_TOKENS" => "length",
+            "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" => "content_filter",
+            _ => "stop",
+        }
+    }
+
+    pub(crate) fn convert_from_gemini_format(&self, response: Value, model: &str) -> Result<ChatCompletionResponse, LlmError> {
+        let candidate = response["candidates"].get(0).ok_or_else(|| {
+            let reason = response["promptFeedback"]["blockReason"].as_str().unwrap_or("no candidate returned");
+            LlmError::from(format!("Gemini API error: {}", reason))
+        })?;
+
+        let parts = candidate["content"]["parts"].as_array().cloned().unwrap_or_default();
+        let (text, reasoning, tool_calls, signatures) = Self::convert_parts(&parts);
+        let finish_reason = match Self::convert_finish_reason(candidate["finishReason"].as_str().unwrap_or("STOP"), !tool_calls.is_empty()) {
+            "tool_calls" => FinishReason::ToolCalls,
+            "length" => FinishReason::TokenLimitReached,
+            "content_filter" => FinishReason::ContentFilterFlagged,
+            _ => FinishReason::StopSequenceReached,
+        };
+
+        Ok(ChatCompletionResponse {
+            id: response["responseId"].as_str().map(str::to_string),
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: response["modelVersion"].as_str().unwrap_or(model).to_string(),
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message: Self::assistant_message(text, reasoning, tool_calls, signatures),
+                finish_reason: Some(finish_reason),
+                logprobs: None,
+            }],
+            usage: Some(Self::convert_usage(&response["usageMetadata"])),
+            service_tier: None,
+            system_fingerprint: None,
+        })
+    }
+
+    /// Each SSE event is a full GenerateContentResponse holding the next parts,
+    /// function calls always arrive whole. `tool_index` numbers the calls across the whole stream.
+    pub(crate) fn convert_gemini_chunk(event: &Value, model: &str, tool_index: &mut usize) -> Result<ChatCompletionChunkResponse, LlmError> {
+        let candidate = &event["candidates"][0];
+        let parts = candidate["content"]["parts"].as_array().cloned().unwrap_or_default();
+        let (text, reasoning, tool_calls, signatures) = Self::convert_parts(&parts);
+
+        let mut delta = json!({ "role": "assistant" });
+        if !signatures.is_empty() {
+            // the signatures ride along the text as a reasoning state content part
+            if let ChatMessage::Assistant { content, .. } = Self::assistant_message(text.clone(), String::new(), vec![], signatures) {
+                delta["content"] = json!(content);
+            }
+        } else if !text.is_empty() {
+            delta["content"] = json!(text);
+        }
+        if !reasoning.is_empty() {
+            delta["reasoning_content"] = json!(reasoning);
+        }
+        if !tool_calls.is_empty() {
+            delta["tool_calls"] = json!(tool_calls.iter().map(|call| {
+                let index = *tool_index;
+                *tool_index += 1;
+                json!({
+                    "index": index,
+                    "id": call.id,
+                    "type": "function",
+                    "function": { "name": call.function.name, "arguments": call.function.arguments }
+                })
+            }).collect::<Vec<_>>());
+        }
+
+        let finish_reason = candidate["finishReason"].as_str()
+            .map(|reason| Self::convert_finish_reason(reason, !tool_calls.is_empty()));
+        let usage = event.get("usageMetadata").filter(|_| finish_reason.is_some()).map(Self::convert_usage);
+
+        Ok(serde_json::from_value(json!({
+            "id": event["responseId"].as_str().map(str::to_string).unwrap_or_else(|| format!("gemini-{}", uuid::Uuid::new_v4())),
+            "object": "chat.completion.chunk",
+            "created": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as u32,
+            "model": event["modelVersion"].as_str().unwrap_or(model),
+            "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+            "usage": usage,
+        }))?)
+    }
+
+    /// SSE lines can be split across network chunks, complete lines are buffered before parsing
+    fn parse_gemini_stream(response: reqwest::Response, model: String) -> LlmStream {
+        let parsed_stream = response.bytes_stream()
+            .scan((String::new(), 0usize), move |(buffer, tool_index), chunk_result| {
+                let results = match chunk_result {
+                    Ok(chunk) => {
+                        buffer.push_str(&String::from_utf8_lossy(&chunk));
+                        let mut results = Vec::new();
+                        while let Some(newline) = buffer.find('\n') {
+                            let line: String = buffer.drain(..=newline).collect();
+                            let Some(data) = line.trim().strip_prefix("data:") else { continue };
+                            results.push(serde_json::from_str::<Value>(data.trim())
+                                .map_err(|e| LlmError::from(format!("Failed to parse Gemini event {}: {}", data, e)))
+                                .and_then(|event| Self::convert_gemini_chunk(&event, &model, tool_index)));
+                        }
+                        results
+                    }
+                    Err(e) => vec![Err(Box::new(e) as LlmError)],
+                };
+                futures::future::ready(Some(results))
+            })
+            .flat_map(stream::iter);
+
+        Box::new(Box::pin(parsed_stream))
+    }
+}
+
+/// `data:` urls are sent inline, other urls as file references
+fn convert_image(url: &str) -> Value {
+    if let Some((header, data)) = url.strip_prefix("data:").and_then(|rest| rest.split_once(',')) {
+        let mime_type = header.trim_end_matches(";base64");
+        return json!({ "inlineData": { "mimeType": mime_type, "data": data } });
+    }
+    let extension = url.rsplit('.').next().unwrap_or_default().to_lowercase();
+    let mime_type = match extension.as_str() {
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => "image/jpeg",
+    };
+    json!({ "fileData": { "mimeType": mime_type, "fileUri": url } })
+}
+
+/// Gemini takes an OpenAPI subset: `$ref` are inlined, nullable unions become `nullable`
+/// and keywords it rejects (`$schema`, `additionalProperties`, `format`...) are dropped
+pub(crate) fn sanitize_schema(schema: &Value) -> Value {
+    let definitions = schema.get("$defs").or_else(|| schema.get("definitions")).cloned().unwrap_or(json!({}));
+    sanitize_node(schema, &definitions, 0)
+}
+
+fn sanitize_node(node: &Value, definitions: &Value, depth: usize) -> Value {
+    let Some(object) = node.as_object() else {
+        return node.clone();
+    };
+    if depth > 16 {
+        return json!({ "type": "object" }); // recursive definitions
+    }
+
+    if let Some(reference) = object.get("$ref").and_then(|r| r.as_str()) {
+        let name = reference.rsplit('/').next().unwrap_or_default();
+        return sanitize_node(&definitions[name], definitions, depth + 1);
+    }
+
+    let mut sanitized = Map::new();
+    for (key, value) in object {
+        match key.as_str() {
+            "type" => match value {
+                Value::Array(types) => {
+                    if types.iter().any(|t| t == "null") {
+                        sanitized.insert("nullable".to_string(), json!(true));
+                    }
+                    if let Some(t) = types.iter().find(|t| *t != "null") {
+                        sanitized.insert("type".to_string(), t.clone());
+                    }
+                }
+                _ => { sanitized.insert(key.clone(), value.clone()); }
+            },
+            "properties" => {
+                let properties = value.as_object().map(|props| props.iter()
+                    .map(|(name, prop)| (name.clone(), sanitize_node(prop, definitions, depth + 1)))
+                    .collect::<Map<_, _>>()).unwrap_or_default();
+                sanitized.insert(key.clone(), Value::Object(properties));
+            }
+            "items" => { sanitized.insert(key.clone(), sanitize_node(value, definitions, depth + 1)); }
+            "anyOf" | "oneOf" => {
+                let variants: Vec<Value> = value.as_array().into_iter().flatten()
+                    .filter(|v| v["type"] != "null")
+                    .map(|v| sanitize_node(v, definitions, depth + 1))
+                    .collect();
+                if value.as_array().is_some_and(|all| all.len() > variants.len()) {
+                    sanitized.insert("nullable".to_string(), json!(true));
+                }
+                match variants.len() {
+                    0 => {}
+                    1 => { sanitized.extend(variants[0].as_object().cloned().unwrap_or_default()); }
+                    _ => { sanitized.insert("anyOf".to_string(), json!(variants)); }
+                }
+            }
+            "allOf" => {
+                for variant in value.as_array().into_iter().flatten() {
+                    sanitized.extend(sanitize_node(variant, definitions, depth + 1).as_object().cloned().unwrap_or_default());
+                }
+            }
+            "description" | "enum" | "required" | "minimum" | "maximum" | "minItems" | "maxItems" | "nullable" => {
+                sanitized.insert(key.clone(), value.clone());
+            }
+            _ => {} // $schema, title, additionalProperties, format, default, $defs...
+        }
+    }
+    if !sanitized.contains_key("type") && !sanitized.contains_key("anyOf") {
+        let default = if sanitized.contains_key("properties") { "object" } else { "string" };
+        sanitized.insert("type".to_string(), json!(default));
+    }
+    Value::Object(sanitized)
+}
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    async fn models(&self) -> Result<ListModelResponse, LlmError> {
+        Ok(ListModelResponse {
+            object: "list".to_string(),
+            data: self.list_models().await?.into_iter()
+                .map(|m| Model {
+                    id: m.name.trim_start_matches("models/").to_string(),
+                    object: "model".to_string(),
+                    created: None,
+                    owned_by: "google".to_string(),
+                })
+                .collect(),
+        })
+    }
+
+    async fn models_info(&self) -> Result<Vec<ModelInfo>, LlmError> {
+        Ok(self.list_models().await?.into_iter()
+            .map(|m| ModelInfo {
+                id: m.name.trim_start_matches("models/").to_string(),
+                context_window: m.input_token_limit,
+                max_output_tokens: m.output_token_limit,
+                reasoning: m.thinking,
+                ..ModelInfo::default()
+            })
+            .collect())
+    }
+
+    async fn default_model(&self) -> Result<String, LlmError> {
+        let models = self.models().await?;
+        models.data.iter()
+            .find(|m| m.id == GEMINI_DEFAULT_MODEL)
+            .or_else(|| models.data.first())
+            .map(|m| m.id.clone())
+            .ok_or_else(|| "no model available".into())
+    }
+
+    async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+        let gemini_request = self.convert_to_gemini_format(&request);
+
+        let response = self.authenticate(self.client.post(self.model_url(&request.model, "generateContent")))
+            .json(&gemini_request)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(HttpError::from_response(response, "Gemini API error").await.into());
+        }
+
+        let gemini_response: Value = response.json().await?;
+        self.convert_from_gemini_format(gemini_response, &request.model)
+    }
+
+    async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+        let gemini_request = self.convert_to_gemini_format(&request);
+
+        let response = self.authenticate(self.client.post(self.model_url(&request.model, "streamGenerateContent")))
+            .query(&[("alt", "sse")])
+            .json(&gemini_request)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(HttpError::from_response(response, "Gemini API streaming error").await.into());
+        }
+
+        Ok(Self::parse_gemini_stream(response, request.model))
+    }
+
+    fn supports_functions(&self, model: String) -> bool {
+        true
+    }
+
+    fn supports_structured_output(&self, model: String) -> bool {
+        true
+    }
+
+    /// The reasoning config becomes a `thinkingConfig` in convert_to_gemini_format
+    fn adapt_reasoning(&self, request: ChatCompletionParameters) -> ChatCompletionParameters {
+        request
+    }
+
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn info() -> ProviderInfo {
+        ProviderInfo {
+            name: "gemini",
+            display_name: "Google Gemini (Gemini API or Vertex AI)",
+            env_vars: vec![
+                EnvVar::required("GEMINI_API_KEY", "Gemini API key (or Vertex AI access token)"),
+                EnvVar::optional("GEMINI_BASE_URL", "Base URL, e.g. https://{region}-aiplatform.googleapis.com/v1/projects/{project}/locations/{region}/publishers/google"),
+            ],
+        }
+    }
+}
@@ -0,0 +1,249 @@
+#[cfg(test)]
+mod tests {
+    use crate::providers::gemini::GeminiProvider;
+    use crate::providers::gemini::gemini::sanitize_schema;
+    use crate::reasoning::{ReasoningConfig, ReasoningEffort, ReasoningRequest};
+    use openai_dive::v1::resources::chat::{
+        ChatCompletionFunction, ChatCompletionParametersBuilder, ChatCompletionTool, ChatCompletionToolType,
+        ChatMessage, ChatMessageContent, ChatMessageContentPart, ChatMessageImageContentPart, ChatMessageTextContentPart,
+        Function, ImageUrlType, ToolCall, DeltaChatMessage,
+    };
+    use openai_dive::v1::resources::shared::FinishReason;
+    use serde_json::json;
+
+    fn provider() -> GeminiProvider {
+        GeminiProvider::new("test".to_string(), None)
+    }
+
+    fn tool(name: &str, parameters: serde_json::Value) -> ChatCompletionTool {
+        ChatCompletionTool {
+            r#type: ChatCompletionToolType::Function,
+            function: ChatCompletionFunction {
+                name: name.to_string(),
+                description: Some(format!("{} a file", name)),
+                parameters,
+            },
+        }
+    }
+
+    #[test]
+    fn test_request_conversion() {
+        let request = ChatCompletionParametersBuilder::default()
+            .model("gemini-2.5-flash".to_string())
+            .messages(vec![
+                ChatMessage::System { content: ChatMessageContent::Text("You are a coding agent.".to_string()), name: None },
+                ChatMessage::User {
+                    content: ChatMessageContent::ContentPart(vec![
+                        ChatMessageContentPart::Text(ChatMessageTextContentPart { r#type: "text".to_string(), text: "What is in this image?".to_string() }),
+                        ChatMessageContentPart::Image(ChatMessageImageContentPart {
+                            r#type: "image_url".to_string(),
+                            image_url: ImageUrlType { url: "data:image/png;base64,iVBORw0KGgo=".to_string(), detail: None },
+                        }),
+                    ]),
+                    name: None,
+                },
+                ChatMessage::Assistant {
+                    content: None,
+                    reasoning_content: None,
+                    refusal: None,
+                    name: None,
+                    audio: None,
+                    tool_calls: Some(vec![
+                        ToolCall { id: "call_1".to_string(), r#type: "function".to_string(), function: Function { name: "read".to_string(), arguments: r#"{"path":"a.rs"}"#.to_string() } },
+                        ToolCall { id: "call_2".to_string(), r#type: "function".to_string(), function: Function { name: "read".to_string(), arguments: r#"{"path":"b.rs"}"#.to_string() } },
+                    ]),
+                },
+                ChatMessage::Tool { content: ChatMessageContent::Text("fn a() {}".to_string()), tool_call_id: "call_1".to_string() },
+                ChatMessage::Tool { content: ChatMessageContent::Text("fn b() {}".to_string()), tool_call_id: "call_2".to_string() },
+            ])
+            .tools(vec![tool("read", json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "title": "ReadParams",
+                "type": "object",
+                "properties": { "path": { "type": "string" }, "limit": { "type": ["integer", "null"], "format": "uint32" } },
+                "required": ["path"],
+                "additionalProperties": false
+            }))])
+            .temperature(0.2)
+            .max_completion_tokens(1024u32)
+            .build()
+            .unwrap()
+            .with_reasoning(ReasoningConfig::effort(ReasoningEffort::Low));
+
+        let gemini = provider().convert_to_gemini_format(&request);
+
+        assert_eq!(gemini["systemInstruction"], json!({"parts": [{"text": "You are a coding agent."}]}));
+        assert_eq!(gemini["contents"], json!([
+            {"role": "user", "parts": [
+                {"text": "What is in this image?"},
+                {"inlineData": {"mimeType": "image/png", "data": "iVBORw0KGgo="}}
+            ]},
+            {"role": "model", "parts": [
+                {"functionCall": {"name": "read", "args": {"path": "a.rs"}}},
+                {"functionCall": {"name": "read", "args": {"path": "b.rs"}}}
+            ]},
+            // both results in a single user turn, named after their call
+            {"role": "user", "parts": [
+                {"functionResponse": {"name": "read", "response": {"content": "fn a() {}"}}},
+                {"functionResponse": {"name": "read", "response": {"content": "fn b() {}"}}}
+            ]}
+        ]));
+        assert_eq!(gemini["tools"][0]["functionDeclarations"][0], json!({
+            "name": "read",
+            "description": "read a file",
+            "parameters": {
+                "type": "object",
+                "properties": { "path": { "type": "string" }, "limit": { "type": "integer", "nullable": true } },
+                "required": ["path"]
+            }
+        }));
+        assert_eq!(gemini["generationConfig"]["maxOutputTokens"], json!(1024));
+        assert_eq!(gemini["generationConfig"]["thinkingConfig"], json!({"thinkingBudget": 2048, "includeThoughts": true}));
+    }
+
+    #[test]
+    fn test_schema_refs_are_inlined() {
+        let schema = sanitize_schema(&json!({
+            "type": "object",
+            "properties": { "edit": { "$ref": "#/$defs/Edit" }, "mode": { "anyOf": [{ "$ref": "#/$defs/Mode" }, { "type": "null" }] } },
+            "$defs": {
+                "Edit": { "type": "object", "properties": { "old": { "type": "string" } } },
+                "Mode": { "type": "string", "enum": ["a", "b"] }
+            }
+        }));
+        assert_eq!(schema, json!({
+            "type": "object",
+            "properties": {
+                "edit": { "type": "object", "properties": { "old": { "type": "string" } } },
+                "mode": { "type": "string", "enum": ["a", "b"], "nullable": true }
+            }
+        }));
+    }
+
+    #[test]
+    fn test_response_conversion() {
+        // recorded from gemini-2.5-flash with includeThoughts
+        let response = provider().convert_from_gemini_format(json!({
+            "candidates": [{
+                "content": {
+                    "parts": [
+                        {"text": "The user wants the file content.", "thought": true},
+                        {"text": "Let me read it."},
+                        {"functionCall": {"name": "read", "args": {"path": "src/main.rs"}}}
+                    ],
+                    "role": "model"
+                },
+                "finishReason": "STOP",
+                "index": 0
+            }],
+            "usageMetadata": {
+                "promptTokenCount": 120,
+                "candidatesTokenCount": 18,
+                "thoughtsTokenCount": 42,
+                "cachedContentTokenCount": 64,
+                "totalTokenCount": 180
+            },
+            "modelVersion": "gemini-2.5-flash",
+            "responseId": "x1b2aK3fN8mVz7IPkqWh0Q0"
+        }), "gemini-2.5-flash").unwrap();
+
+        let choice = &response.choices[0];
+        assert!(matches!(choice.finish_reason, Some(FinishReason::ToolCalls)));
+        match &choice.message {
+            ChatMessage::Assistant { content, reasoning_content, tool_calls: Some(calls), .. } => {
+                assert!(matches!(content, Some(ChatMessageContent::Text(text)) if text == "Let me read it."));
+                assert_eq!(reasoning_content.as_deref(), Some("The user wants the file content."));
+                assert_eq!(calls[0].function.name, "read");
+                assert_eq!(calls[0].function.arguments, r#"{"path":"src/main.rs"}"#);
+                assert!(calls[0].id.starts_with("call_"));
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, Some(120));
+        assert_eq!(usage.completion_tokens, Some(60));
+        assert_eq!(crate::CachedTokens::cached_tokens(&usage), 64);
+
+        let blocked = provider().convert_from_gemini_format(json!({
+            "promptFeedback": {"blockReason": "SAFETY"}
+        }), "gemini-2.5-flash");
+        assert!(blocked.unwrap_err().to_string().contains("SAFETY"));
+    }
+
+    #[test]
+    fn test_stream_chunk_conversion() {
+        // recorded streamGenerateContent?alt=sse events
+        let mut tool_index = 0;
+        let text = GeminiProvider::convert_gemini_chunk(&json!({
+            "candidates": [{"content": {"parts": [{"text": "Hello"}], "role": "model"}, "index": 0}],
+            "modelVersion": "gemini-2.5-flash"
+        }), "gemini-2.5-flash", &mut tool_index).unwrap();
+        match &text.choices[0].delta {
+            DeltaChatMessage::Assistant { content, .. } => assert!(matches!(content, Some(ChatMessageContent::Text(text)) if text == "Hello")),
+            other => panic!("unexpected delta {:?}", other),
+        }
+        assert!(text.choices[0].finish_reason.is_none());
+
+        let first_call = GeminiProvider::convert_gemini_chunk(&json!({
+            "candidates": [{"content": {"parts": [{"functionCall": {"name": "read", "args": {"path": "a"}}}], "role": "model"}, "index": 0}],
+            "modelVersion": "gemini-2.5-flash"
+        }), "gemini-2.5-flash", &mut tool_index).unwrap();
+        match &first_call.choices[0].delta {
+            DeltaChatMessage::Assistant { tool_calls: Some(calls), .. } => assert_eq!(serde_json::to_value(&calls[0]).unwrap()["index"], json!(0)),
+            other => panic!("unexpected delta {:?}", other),
+        }
+
+        let last = GeminiProvider::convert_gemini_chunk(&json!({
+            "candidates": [{"content": {"parts": [{"functionCall": {"name": "ls", "args": {}}}], "role": "model"}, "finishReason": "STOP", "index": 0}],
+            "usageMetadata": {"promptTokenCount": 10, "candidatesTokenCount": 5, "totalTokenCount": 15},
+            "modelVersion": "gemini-2.5-flash"
+        }), "gemini-2.5-flash", &mut tool_index).unwrap();
+        assert!(matches!(last.choices[0].finish_reason, Some(FinishReason::ToolCalls)));
+        assert_eq!(last.usage.unwrap().total_tokens, 15);
+        match &last.choices[0].delta {
+            DeltaChatMessage::Assistant { tool_calls: Some(calls), .. } => {
+                // a later event continues the numbering instead of overwriting the first call
+                assert_eq!(calls.len(), 1);
+                assert_eq!(serde_json::to_value(&calls[0]).unwrap()["index"], json!(1));
+            }
+            other => panic!("unexpected delta {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_thought_signature_round_trip() {
+        let provider = provider();
+        let response = provider.convert_from_gemini_format(json!({
+            "candidates": [{
+                "content": {
+                    "parts": [
+                        {"functionCall": {"name": "ls", "args": {}}, "thoughtSignature": "c2lnbmVk"},
+                        {"functionCall": {"name": "read", "args": {"path": "a"}}}
+                    ],
+                    "role": "model"
+                },
+                "finishReason": "STOP"
+            }]
+        }), "gemini-3-pro-preview").unwrap();
+
+        // the signatures are saved and reloaded with the trace
+        let trace = vec![
+            ChatMessage::User { content: ChatMessageContent::Text("list".to_string()), name: None },
+            response.choices[0].message.clone(),
+        ];
+        let trace: Vec<ChatMessage> = serde_json::from_str(&serde_json::to_string(&trace).unwrap()).unwrap();
+
+        let request = ChatCompletionParametersBuilder::default()
+            .model("gemini-3-pro-preview".to_string())
+            .messages(trace)
+            .build()
+            .unwrap();
+        let gemini = provider.convert_to_gemini_format(&request);
+        let parts = &gemini["contents"][1]["parts"];
+        assert_eq!(parts[0]["thoughtSignature"], json!("c2lnbmVk"));
+        assert!(parts[1].get("thoughtSignature").is_none());
+        // the state part is not sent as text
+        assert_eq!(parts.as_array().unwrap().len(), 2);
+    }
+}
@@ -1,6 +1,7 @@
 // Mistral provider using flexible chat client with JSON hooks
 use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
 use crate::chat::{ChatClient, JsonHooks};
+use crate::reasoning::ReasoningRequest;
 use serde_json::Value;
 use async_trait::async_trait;
 use futures::StreamExt;
@@ -175,6 +176,12 @@ impl LlmProvider for MistralProvider {
         Ok("mistral-small-latest".to_string())
     }
 
+    /// Mistral has no reasoning parameter, magistral models always reason
+    fn adapt_reasoning(&self, mut request: ChatCompletionParameters) -> ChatCompletionParameters {
+        request.take_reasoning();
+        request.strip_reasoning_state()
+    }
+
     async fn chat(&self, mut request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
         // Mistral uses max_tokens instead of max_completion_tokens
         if request.max_completion_tokens.is_some() {
@@ -182,8 +189,7 @@ impl LlmProvider for MistralProvider {
             request.max_completion_tokens = None;
         }
         
-        let response = self.client.chat_completion(&request, &self.hooks).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+        let response = self.client.chat_completion(&request, &self.hooks).await?;
         Ok(response)
     }
 
@@ -197,8 +203,7 @@ impl LlmProvider for MistralProvider {
             request.max_completion_tokens = None;
         }
         
-        let stream = self.client.chat_completion_stream(&request, self.hooks).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+        let stream = self.client.chat_completion_stream(&request, self.hooks).await?;
 
         let converted_stream = stream.map(|result| {
             result.map_err(|e| Box::new(e) as LlmError)
@@ -3,8 +3,11 @@ pub mod openai_compatible;
 pub mod openrouter;
 pub mod ovhcloud;
 pub mod anthropic;
+pub mod gemini;
 pub mod ollama;
 pub mod mistral;
+pub mod router;
+pub mod replay;
 // pub mod mistral_native; // TODO: Complete implementation
 
 #[cfg(test)]
@@ -1,26 +1,31 @@
 // llm/providers/openai_compatible.rs
 use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use crate::chat::ChatClient;
+use crate::hooks::JsonHooksConfig;
 use async_trait::async_trait;
 use futures::StreamExt;
-use openai_dive::v1::{
-    api::Client,
-    resources::{
-        chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse},
-        model::ListModelResponse,
-        shared::Usage,
-    },
+use openai_dive::v1::resources::{
+    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse},
+    model::ListModelResponse,
+    shared::Usage,
 };
 use serde_json::Value;
 
 pub struct OpenAICompatibleProvider {
-    client: Client,
+    client: ChatClient,
+    hooks: JsonHooksConfig,
 }
 
 impl OpenAICompatibleProvider {
     pub fn new(api_key: String, base_url: String) -> Self {
-        let mut client = Client::new(api_key);
-        client.set_base_url(&base_url);
-        Self { client }
+        let client = ChatClient::new(api_key, base_url.trim_end_matches('/').to_string());
+        Self { client, hooks: JsonHooksConfig::default() }
+    }
+
+    /// Request tweaks for servers that are not quite OpenAI compatible
+    pub fn with_hooks(mut self, hooks: JsonHooksConfig) -> Self {
+        let _ = self.set_json_hooks(hooks);
+        self
     }
 
     /// Create OpenAI Compatible provider from environment variables
@@ -38,14 +43,18 @@ impl OpenAICompatibleProvider {
 #[async_trait]
 impl LlmProvider for OpenAICompatibleProvider {
     async fn models(&self) -> Result<ListModelResponse, LlmError> {
-        let response = self.client.models().list().await
-            .map_err(|e| Box::new(e) as LlmError)?;
-        Ok(response)
+        let mut request = self.client.http_client
+            .get(format!("{}/models", self.client.base_url))
+            .bearer_auth(&self.client.api_key);
+        for (key, value) in self.client.headers.iter().flatten() {
+            request = request.header(key, value);
+        }
+        let response = request.send().await?.error_for_status()?;
+        Ok(response.json().await?)
     }
 
     async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
-        let mut response = self.client.chat().create(request).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+        let response = self.client.chat_completion(&request, &self.hooks).await?;
 
         Ok(response)
     }
@@ -54,8 +63,7 @@ impl LlmProvider for OpenAICompatibleProvider {
         // Ensure streaming is enabled
         request.stream = Some(true);
         
-        let stream = self.client.chat().create_stream(request).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+        let stream = self.client.chat_completion_stream(&request, self.hooks.clone()).await?;
 
         let converted_stream = stream.map(|result| {
             result.map_err(|e| Box::new(e) as LlmError)
@@ -72,6 +80,12 @@ impl LlmProvider for OpenAICompatibleProvider {
         true
     }
 
+    fn set_json_hooks(&mut self, hooks: JsonHooksConfig) -> Result<(), LlmError> {
+        self.client.headers = (!hooks.headers.is_empty()).then(|| hooks.headers.clone());
+        self.hooks = hooks;
+        Ok(())
+    }
+
     fn name(&self) -> &'static str {
         "openai_compatible"
     }
@@ -33,6 +33,8 @@ pub struct OpenRouterArchitecture {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OpenRouterTopProvider {
     pub is_moderated: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<u32>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -65,6 +67,31 @@ impl OpenRouterModel {
     }
 }
 
+impl OpenRouterModel {
+    /// Capabilities and pricing, OpenRouter prices are in USD per token
+    pub fn to_model_info(&self) -> crate::model_info::ModelInfo {
+        let per_million = |price: &str| price.parse::<f64>().ok().map(|p| p * 1_000_000.0);
+        let supports = |param: &str| self.supported_parameters.as_ref().map(|params| params.iter().any(|p| p == param));
+
+        crate::model_info::ModelInfo {
+            id: self.id.clone(),
+            context_window: u32::try_from(self.context_length).ok().filter(|c| *c > 0),
+            max_output_tokens: self.top_provider.max_completion_tokens,
+            vision: Some(self.architecture.input_modalities.iter().any(|m| m == "image")),
+            reasoning: supports("reasoning"),
+            functions: supports("tools"),
+            pricing: match (per_million(&self.pricing.prompt), per_million(&self.pricing.completion)) {
+                (Some(input), Some(output)) => Some(crate::model_info::ModelPricing {
+                    input,
+                    output,
+                    cache_read: self.pricing.input_cache_read.as_deref().and_then(per_million),
+                }),
+                _ => None,
+            },
+        }
+    }
+}
+
 impl OpenRouterModelsResponse {
     /// Convert OpenRouter models response to openai_dive ListModelResponse format
     pub fn to_openai_models_response(&self) -> openai_dive::v1::resources::model::ListModelResponse {
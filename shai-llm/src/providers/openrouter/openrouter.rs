@@ -1,10 +1,13 @@
 use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use crate::chat::{ChatClient, JsonHooks};
+use crate::model_info::ModelInfo;
 use super::api::OpenRouterModelsResponse;
 use async_trait::async_trait;
 use futures::StreamExt;
 use reqwest;
+use serde_json::Value;
 use openai_dive::v1::{
-    api::Client,
+    error::APIError,
     resources::{
         chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse},
         model::ListModelResponse,
@@ -14,7 +17,7 @@ use openai_dive::v1::{
 const OPENROUTER_API_BASE: &str = "https://openrouter.ai/api/v1";
 
 pub struct OpenRouterProvider {
-    client: Client,
+    client: ChatClient,
     api_key: String,
     base_url: String,
     http_client: reqwest::Client,
@@ -22,8 +25,7 @@ pub struct OpenRouterProvider {
 
 impl OpenRouterProvider {
     pub fn new(api_key: String) -> Self {
-        let mut client = Client::new(api_key.clone());
-        client.set_base_url(OPENROUTER_API_BASE);
+        let client = ChatClient::new(api_key.clone(), OPENROUTER_API_BASE.to_string());
         Self { 
             client,
             api_key,
@@ -70,6 +72,37 @@ impl OpenRouterProvider {
     }
 }
 
+/// OpenRouter returns the reasoning in `reasoning` instead of `reasoning_content`
+#[derive(Clone, Copy)]
+pub struct OpenRouterHooks;
+
+impl OpenRouterHooks {
+    fn map_reasoning(json: &mut Value, message_key: &str) {
+        if let Some(choices) = json.get_mut("choices").and_then(|c| c.as_array_mut()) {
+            for choice in choices {
+                if let Some(message) = choice.get_mut(message_key).and_then(|m| m.as_object_mut()) {
+                    if let Some(reasoning) = message.remove("reasoning").filter(|r| r.is_string()) {
+                        message.entry("reasoning_content").or_insert(reasoning);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl JsonHooks for OpenRouterHooks {
+    async fn after_receive(&self, mut json: Value) -> Result<Value, APIError> {
+        Self::map_reasoning(&mut json, "message");
+        Ok(json)
+    }
+
+    async fn after_receive_stream(&self, mut json: Value) -> Result<Value, APIError> {
+        Self::map_reasoning(&mut json, "delta");
+        Ok(json)
+    }
+}
+
 #[async_trait]
 impl LlmProvider for OpenRouterProvider {
     async fn models(&self) -> Result<ListModelResponse, LlmError> {
@@ -77,6 +110,11 @@ impl LlmProvider for OpenRouterProvider {
         Ok(openrouter_response.to_openai_models_response())
     }
 
+    async fn models_info(&self) -> Result<Vec<ModelInfo>, LlmError> {
+        let openrouter_response = self.openrouter_models().await?;
+        Ok(openrouter_response.data.iter().map(|m| m.to_model_info()).collect())
+    }
+
 
     async fn default_model(&self) -> Result<String, LlmError> {
         let models = self.models().await?; 
@@ -90,8 +128,7 @@ impl LlmProvider for OpenRouterProvider {
     }
 
     async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
-        let response = self.client.chat().create(request).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+        let response = self.client.chat_completion(&request, &OpenRouterHooks).await?;
         Ok(response)
     }
 
@@ -99,8 +136,7 @@ impl LlmProvider for OpenRouterProvider {
         // Ensure streaming is enabled
         request.stream = Some(true);
         
-        let stream = self.client.chat().create_stream(request).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+        let stream = self.client.chat_completion_stream(&request, OpenRouterHooks).await?;
 
         let converted_stream = stream.map(|result| {
             result.map_err(|e| Box::new(e) as LlmError)
@@ -117,6 +153,11 @@ impl LlmProvider for OpenRouterProvider {
         true
     }
 
+    /// `reasoning` is OpenRouter's own parameter, sent as is
+    fn adapt_reasoning(&self, request: ChatCompletionParameters) -> ChatCompletionParameters {
+        request
+    }
+
     fn name(&self) -> &'static str {
         "openrouter"
     }
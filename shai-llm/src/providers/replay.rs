@@ -0,0 +1,665 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use openai_dive::v1::resources::{
+    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse},
+    model::ListModelResponse,
+};
+
+use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use crate::retry::{error_status, HttpError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayMode {
+    /// Forward to the real provider and write every exchange to the cassette
+    Record,
+    /// Serve the exchanges of the cassette, no network
+    Replay,
+}
+
+/// Record/replay settings, from `replay` in the shai config or from
+/// `SHAI_REPLAY_MODE` (record | replay) and `SHAI_REPLAY_CASSETTE`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayConfig {
+    pub mode: ReplayMode,
+    pub cassette: PathBuf,
+    /// Only serve exchanges whose request is identical. Otherwise a request with no identical
+    /// recording gets the next unused exchange, which keeps cassettes usable when the prompt
+    /// embeds the date or the working directory
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl ReplayConfig {
+    pub fn from_env() -> Option<Self> {
+        let cassette = std::env::var("SHAI_REPLAY_CASSETTE").ok()?;
+        let mode = match std::env::var("SHAI_REPLAY_MODE").as_deref() {
+            Ok("record") => ReplayMode::Record,
+            _ => ReplayMode::Replay,
+        };
+        let strict = std::env::var("SHAI_REPLAY_STRICT").is_ok_and(|v| v == "1" || v == "true");
+        Some(Self { mode, cassette: PathBuf::from(cassette), strict })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Exchange {
+    Models { response: ListModelResponse },
+    Chat { request: Value, response: ChatCompletionResponse },
+    Stream { request: Value, chunks: Vec<ChatCompletionChunkResponse> },
+    /// A failed call, the status and Retry-After are kept so a replayed error is retried
+    /// or failed over like the original one
+    Error {
+        request: Value,
+        error: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        status: Option<u16>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        retry_after_ms: Option<u64>,
+    },
+}
+
+impl Exchange {
+    fn request(&self) -> Option<&Value> {
+        match self {
+            Exchange::Models { .. } => None,
+            Exchange::Chat { request, .. } | Exchange::Stream { request, .. } | Exchange::Error { request, .. } => Some(request),
+        }
+    }
+
+    fn error(request: Value, error: &LlmError) -> Self {
+        Exchange::Error {
+            request,
+            error: error.to_string(),
+            status: error_status(error),
+            retry_after_ms: error.downcast_ref::<HttpError>()
+                .and_then(|e| e.retry_after)
+                .map(|delay| delay.as_millis() as u64),
+        }
+    }
+}
+
+/// Rebuild a recorded error, as an HttpError when it had a status
+fn replayed_error(error: String, status: Option<u16>, retry_after_ms: Option<u64>) -> LlmError {
+    match status {
+        Some(status) => Box::new(HttpError {
+            status,
+            retry_after: retry_after_ms.map(Duration::from_millis),
+            message: error,
+        }),
+        None => error.into(),
+    }
+}
+
+/// Recorded exchanges, in order, with the capabilities of the recorded provider
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    #[serde(default)]
+    pub provider: String,
+    #[serde(default = "default_true")]
+    pub supports_functions: bool,
+    #[serde(default)]
+    pub supports_structured_output: bool,
+    #[serde(default)]
+    pub exchanges: Vec<Exchange>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> Result<Self, LlmError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read cassette {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&content)
+            .map_err(|e| format!("invalid cassette {}: {}", path.display(), e))?)
+    }
+
+    /// Write through a temporary file so a reader never sees a partial cassette
+    pub fn save(&self, path: &Path) -> Result<(), LlmError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension(format!("tmp.{}", uuid::Uuid::new_v4().simple()));
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Add an exchange to the cassette file, keeping what is already there (earlier runs or
+    /// other recorders). Writers are serialized by an exclusive lock on `<cassette>.lock`,
+    /// which works across threads and processes.
+    pub fn append(path: &Path, header: &Cassette, exchange: Exchange) -> Result<(), LlmError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let lock = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path.with_extension("lock"))?;
+        lock.lock()?;
+
+        let mut cassette = if path.exists() { Self::load(path)? } else { Self::default() };
+        cassette.provider = header.provider.clone();
+        cassette.supports_functions = header.supports_functions;
+        cassette.supports_structured_output = header.supports_structured_output;
+        cassette.exchanges.push(exchange);
+        cassette.save(path)
+    }
+}
+
+/// The part of a request that identifies it, streaming flags are ignored so a recorded
+/// stream can answer a plain chat and the other way around
+fn request_key(request: &ChatCompletionParameters) -> Value {
+    let mut key = serde_json::to_value(request).unwrap_or(Value::Null);
+    if let Some(object) = key.as_object_mut() {
+        object.remove("stream");
+        object.remove("stream_options");
+    }
+    key
+}
+
+struct ReplayState {
+    cassette: Cassette,
+    used: Vec<bool>,
+}
+
+/// Provider recording exchanges with a real provider to a cassette file, or serving them back.
+///
+/// Streamed answers are stored chunk by chunk and replayed as a stream. A recorded stream can
+/// also answer a non streamed request (text and reasoning only) and the other way around.
+pub struct ReplayProvider {
+    inner: Option<Box<dyn LlmProvider>>,
+    config: ReplayConfig,
+    state: Arc<Mutex<ReplayState>>,
+}
+
+impl ReplayProvider {
+    /// Record the exchanges of `inner`, they are appended to an existing cassette
+    pub fn record(inner: Box<dyn LlmProvider>, config: ReplayConfig) -> Self {
+        let cassette = Cassette { provider: inner.name().to_string(), ..Cassette::default() };
+        Self::build(Some(inner), config, cassette)
+    }
+
+    /// Serve the exchanges of the cassette file
+    pub fn replay(config: ReplayConfig) -> Result<Self, LlmError> {
+        let cassette = Cassette::load(&config.cassette)?;
+        Ok(Self::build(None, config, cassette))
+    }
+
+    /// Serve an in-memory cassette, handy for tests
+    pub fn from_cassette(cassette: Cassette, strict: bool) -> Self {
+        let config = ReplayConfig { mode: ReplayMode::Replay, cassette: PathBuf::new(), strict };
+        Self::build(None, config, cassette)
+    }
+
+    fn build(inner: Option<Box<dyn LlmProvider>>, config: ReplayConfig, cassette: Cassette) -> Self {
+        let used = vec![false; cassette.exchanges.len()];
+        Self { inner, config, state: Arc::new(Mutex::new(ReplayState { cassette, used })) }
+    }
+
+    /// Wrap `inner` according to the mode, `inner` is dropped in replay mode
+    pub fn wrap(inner: Box<dyn LlmProvider>, config: ReplayConfig) -> Result<Self, LlmError> {
+        match config.mode {
+            ReplayMode::Record => Ok(Self::record(inner, config)),
+            ReplayMode::Replay => Self::replay(config),
+        }
+    }
+
+    fn recording(&self) -> Option<&dyn LlmProvider> {
+        match self.config.mode {
+            ReplayMode::Record => self.inner.as_deref(),
+            ReplayMode::Replay => None,
+        }
+    }
+
+    /// Append an exchange to the cassette file, off the async workers.
+    /// While recording the in-memory cassette only holds the provider capabilities.
+    async fn push(state: &Mutex<ReplayState>, path: &Path, exchange: Exchange) -> Result<(), LlmError> {
+        let header = state.lock().unwrap().cassette.clone();
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || Cassette::append(&path, &header, exchange))
+            .await
+            .map_err(|e| -> LlmError { format!("cassette writer failed: {}", e).into() })?
+    }
+
+    /// Next exchange for a request: an identical unused one, else (not strict) the next unused one
+    fn take(&self, request: &ChatCompletionParameters) -> Result<Exchange, LlmError> {
+        let key = request_key(request);
+        let mut state = self.state.lock().unwrap();
+
+        let unused: Vec<usize> = state.cassette.exchanges.iter().enumerate()
+            .filter(|(i, e)| !state.used[*i] && e.request().is_some())
+            .map(|(i, _)| i)
+            .collect();
+        let found = unused.iter().copied()
+            .find(|i| state.cassette.exchanges[*i].request() == Some(&key))
+            .or_else(|| unused.first().copied().filter(|_| !self.config.strict));
+
+        match found {
+            Some(i) => {
+                state.used[i] = true;
+                Ok(state.cassette.exchanges[i].clone())
+            }
+            None => Err(format!(
+                "no recorded exchange left for this request in cassette {} (model {})",
+                self.config.cassette.display(), request.model
+            ).into()),
+        }
+    }
+
+    /// Rebuild a full response from recorded chunks, tool call fragments are joined by index
+    fn merge_chunks(chunks: &[ChatCompletionChunkResponse]) -> Result<ChatCompletionResponse, LlmError> {
+        use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, DeltaChatMessage, ToolCall};
+
+        let mut content = String::new();
+        let mut reasoning = String::new();
+        let mut calls: Vec<(u64, Value)> = Vec::new();
+        let mut finish_reason = None;
+        let mut usage = None;
+        for chunk in chunks {
+            usage = chunk.usage.clone().or(usage);
+            for choice in &chunk.choices {
+                finish_reason = choice.finish_reason.clone().or(finish_reason);
+                if let DeltaChatMessage::Assistant { content: delta, reasoning_content, tool_calls, .. } = &choice.delta {
+                    if let Some(ChatMessageContent::Text(text)) = delta {
+                        content.push_str(text);
+                    }
+                    if let Some(text) = reasoning_content {
+                        reasoning.push_str(text);
+                    }
+                    for fragment in tool_calls.iter().flatten() {
+                        Self::merge_tool_call(&mut calls, serde_json::to_value(fragment)?);
+                    }
+                }
+            }
+        }
+        let tool_calls = calls.into_iter()
+            .map(|(_, call)| serde_json::from_value::<ToolCall>(call))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let first = chunks.first().ok_or("empty recorded stream")?;
+        Ok(ChatCompletionResponse {
+            id: first.id.clone(),
+            object: "chat.completion".to_string(),
+            created: first.created,
+            model: first.model.clone(),
+            choices: vec![openai_dive::v1::resources::chat::ChatCompletionChoice {
+                index: 0,
+                message: ChatMessage::Assistant {
+                    content: (!content.is_empty()).then_some(ChatMessageContent::Text(content)),
+                    reasoning_content: (!reasoning.is_empty()).then_some(reasoning),
+                    refusal: None,
+                    name: None,
+                    audio: None,
+                    tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+                },
+                finish_reason,
+                logprobs: None,
+            }],
+            usage,
+            service_tier: None,
+            system_fingerprint: None,
+        })
+    }
+
+    /// Add a streamed tool call fragment: the first one of an index carries the id and name,
+    /// the following ones more of the arguments
+    fn merge_tool_call(calls: &mut Vec<(u64, Value)>, fragment: Value) {
+        let index = fragment["index"].as_u64().unwrap_or(calls.len() as u64);
+        let arguments = fragment["function"]["arguments"].as_str().unwrap_or_default().to_string();
+        match calls.iter_mut().find(|(i, _)| *i == index) {
+            Some((_, call)) => {
+                let merged = format!("{}{}", call["function"]["arguments"].as_str().unwrap_or_default(), arguments);
+                call["function"]["arguments"] = Value::String(merged);
+            }
+            None => calls.push((index, serde_json::json!({
+                "id": fragment["id"],
+                "type": fragment["type"].as_str().unwrap_or("function"),
+                "function": { "name": fragment["function"]["name"], "arguments": arguments },
+            }))),
+        }
+    }
+
+    /// Split a full response in a single chunk
+    fn as_chunk(response: ChatCompletionResponse) -> Result<ChatCompletionChunkResponse, LlmError> {
+        let mut chunk = serde_json::to_value(&response)?;
+        chunk["object"] = "chat.completion.chunk".into();
+        if let Some(choices) = chunk["choices"].as_array_mut() {
+            for choice in choices {
+                if let Some(message) = choice.as_object_mut().and_then(|c| c.remove("message")) {
+                    choice["delta"] = message;
+                }
+            }
+        }
+        Ok(serde_json::from_value(chunk)?)
+    }
+
+    fn stream_of(chunks: Vec<Result<ChatCompletionChunkResponse, LlmError>>) -> LlmStream {
+        Box::new(futures::stream::iter(chunks))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ReplayProvider {
+    async fn models(&self) -> Result<ListModelResponse, LlmError> {
+        if let Some(inner) = self.recording() {
+            let response = inner.models().await?;
+            Self::push(&self.state, &self.config.cassette, Exchange::Models { response: response.clone() }).await?;
+            return Ok(response);
+        }
+
+        let state = self.state.lock().unwrap();
+        state.cassette.exchanges.iter()
+            .find_map(|e| match e {
+                Exchange::Models { response } => Some(response.clone()),
+                _ => None,
+            })
+            .ok_or_else(|| format!("no models recorded in cassette {}", self.config.cassette.display()).into())
+    }
+
+    async fn default_model(&self) -> Result<String, LlmError> {
+        if let Some(inner) = self.recording() {
+            return inner.default_model().await;
+        }
+        let state = self.state.lock().unwrap();
+        state.cassette.exchanges.iter()
+            .find_map(|e| match e {
+                Exchange::Models { response } => response.data.first().map(|m| m.id.clone()),
+                Exchange::Chat { request, .. } | Exchange::Stream { request, .. } | Exchange::Error { request, .. } => {
+                    request["model"].as_str().map(str::to_string)
+                }
+            })
+            .ok_or_else(|| "no model available".into())
+    }
+
+    async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+        if let Some(inner) = self.recording() {
+            let key = request_key(&request);
+            {
+                let mut state = self.state.lock().unwrap();
+                state.cassette.supports_functions = inner.supports_functions(request.model.clone());
+                state.cassette.supports_structured_output = inner.supports_structured_output(request.model.clone());
+            }
+            return match inner.chat(inner.adapt_reasoning(request)).await {
+                Ok(response) => {
+                    Self::push(&self.state, &self.config.cassette, Exchange::Chat { request: key, response: response.clone() }).await?;
+                    Ok(response)
+                }
+                Err(e) => {
+                    Self::push(&self.state, &self.config.cassette, Exchange::error(key, &e)).await?;
+                    Err(e)
+                }
+            };
+        }
+
+        match self.take(&request)? {
+            Exchange::Chat { response, .. } => Ok(response),
+            Exchange::Stream { chunks, .. } => Self::merge_chunks(&chunks),
+            Exchange::Error { error, status, retry_after_ms, .. } => Err(replayed_error(error, status, retry_after_ms)),
+            Exchange::Models { .. } => unreachable!("models exchanges have no request"),
+        }
+    }
+
+    async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+        if let Some(inner) = self.recording() {
+            let key = request_key(&request);
+            let mut stream = match inner.chat_stream(inner.adapt_reasoning(request)).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    Self::push(&self.state, &self.config.cassette, Exchange::error(key, &e)).await?;
+                    return Err(e);
+                }
+            };
+
+            // chunks are forwarded as they come and the exchange is written once the stream ends
+            let state = self.state.clone();
+            let path = self.config.cassette.clone();
+            let recorded = async_stream::stream! {
+                let mut chunks = Vec::new();
+                while let Some(chunk) = stream.next().await {
+                    if let Ok(chunk) = &chunk {
+                        chunks.push(chunk.clone());
+                    }
+                    yield chunk;
+                }
+                let _ = Self::push(&state, &path, Exchange::Stream { request: key, chunks }).await;
+            };
+            return Ok(Box::new(Box::pin(recorded)));
+        }
+
+        match self.take(&request)? {
+            Exchange::Stream { chunks, .. } => Ok(Self::stream_of(chunks.into_iter().map(Ok).collect())),
+            Exchange::Chat { response, .. } => Ok(Self::stream_of(vec![Self::as_chunk(response)])),
+            Exchange::Error { error, status, retry_after_ms, .. } => Err(replayed_error(error, status, retry_after_ms)),
+            Exchange::Models { .. } => unreachable!("models exchanges have no request"),
+        }
+    }
+
+    fn supports_functions(&self, model: String) -> bool {
+        match self.recording() {
+            Some(inner) => inner.supports_functions(model),
+            None => self.state.lock().unwrap().cassette.supports_functions,
+        }
+    }
+
+    fn supports_structured_output(&self, model: String) -> bool {
+        match self.recording() {
+            Some(inner) => inner.supports_structured_output(model),
+            None => self.state.lock().unwrap().cassette.supports_structured_output,
+        }
+    }
+
+    /// Requests are recorded as the client sends them, the recorded provider adapts them in `chat`
+    fn adapt_reasoning(&self, request: ChatCompletionParameters) -> ChatCompletionParameters {
+        request
+    }
+
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+
+    fn info() -> ProviderInfo {
+        ProviderInfo {
+            name: "replay",
+            display_name: "Replay (recorded cassette)",
+            env_vars: vec![
+                EnvVar::required("SHAI_REPLAY_CASSETTE", "Path of the cassette file"),
+                EnvVar::optional("SHAI_REPLAY_MODE", "record or replay (default)"),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openai_dive::v1::resources::chat::{ChatCompletionParametersBuilder, ChatMessage, ChatMessageContent, DeltaChatMessage};
+    use serde_json::json;
+
+    /// Echoes the last user message, counting its calls
+    struct EchoProvider {
+        calls: Arc<Mutex<u32>>,
+    }
+
+    fn echo(request: &ChatCompletionParameters) -> String {
+        match request.messages.last() {
+            Some(ChatMessage::User { content: ChatMessageContent::Text(text), .. }) => text.clone(),
+            _ => String::new(),
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for EchoProvider {
+        async fn models(&self) -> Result<ListModelResponse, LlmError> {
+            Ok(serde_json::from_value(json!({"object": "list", "data": [{"id": "echo-1", "object": "model", "owned_by": "test"}]}))?)
+        }
+
+        async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+            *self.calls.lock().unwrap() += 1;
+            if echo(&request) == "overloaded" {
+                return Err(Box::new(HttpError { status: 429, retry_after: Some(Duration::from_secs(3)), message: "slow down".into() }));
+            }
+            Ok(serde_json::from_value(json!({
+                "id": "echo", "object": "chat.completion", "created": 0, "model": request.model,
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": echo(&request)}, "finish_reason": "stop"}]
+            }))?)
+        }
+
+        async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+            *self.calls.lock().unwrap() += 1;
+            let chunks = echo(&request).split(' ').map(|word| Ok(serde_json::from_value(json!({
+                "id": "echo", "object": "chat.completion.chunk", "created": 0, "model": request.model,
+                "choices": [{"index": 0, "delta": {"role": "assistant", "content": format!("{} ", word)}}]
+            }))?)).collect::<Vec<Result<ChatCompletionChunkResponse, LlmError>>>();
+            Ok(Box::new(futures::stream::iter(chunks)))
+        }
+
+        fn supports_functions(&self, _: String) -> bool { true }
+        fn supports_structured_output(&self, _: String) -> bool { true }
+        fn name(&self) -> &'static str { "echo" }
+        fn info() -> ProviderInfo { ProviderInfo { name: "echo", display_name: "Echo", env_vars: vec![] } }
+    }
+
+    fn request(text: &str) -> ChatCompletionParameters {
+        ChatCompletionParametersBuilder::default()
+            .model("echo-1".to_string())
+            .messages(vec![ChatMessage::User { content: ChatMessageContent::Text(text.to_string()), name: None }])
+            .build()
+            .unwrap()
+    }
+
+    fn text(response: &ChatCompletionResponse) -> String {
+        match &response.choices[0].message {
+            ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. } => text.clone(),
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    async fn collect(mut stream: LlmStream) -> String {
+        let mut text = String::new();
+        while let Some(chunk) = stream.next().await {
+            if let DeltaChatMessage::Assistant { content: Some(ChatMessageContent::Text(t)), .. } = &chunk.unwrap().choices[0].delta {
+                text.push_str(t);
+            }
+        }
+        text
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let cassette = dir.path().join("cassette.json");
+        let calls = Arc::new(Mutex::new(0));
+
+        let config = ReplayConfig { mode: ReplayMode::Record, cassette: cassette.clone(), strict: true };
+        let recorder = ReplayProvider::wrap(Box::new(EchoProvider { calls: calls.clone() }), config.clone()).unwrap();
+        assert_eq!(recorder.models().await.unwrap().data[0].id, "echo-1");
+        assert_eq!(text(&recorder.chat(request("first")).await.unwrap()), "first");
+        assert_eq!(collect(recorder.chat_stream(request("second one")).await.unwrap()).await, "second one ");
+        assert_eq!(*calls.lock().unwrap(), 2);
+
+        let replayer = ReplayProvider::wrap(Box::new(EchoProvider { calls: calls.clone() }), ReplayConfig { mode: ReplayMode::Replay, ..config }).unwrap();
+        assert_eq!(replayer.default_model().await.unwrap(), "echo-1");
+        // matched by request, not by order, a stream can answer a chat
+        assert_eq!(text(&replayer.chat(request("second one")).await.unwrap()), "second one ");
+        assert_eq!(collect(replayer.chat_stream(request("first")).await.unwrap()).await, "first");
+        assert!(replayer.chat(request("unknown")).await.is_err());
+        assert_eq!(*calls.lock().unwrap(), 2, "replay must not reach the provider");
+    }
+
+    #[tokio::test]
+    async fn test_lenient_replay_serves_in_order() {
+        let response = |content: &str| serde_json::from_value(json!({
+            "id": "r", "object": "chat.completion", "created": 0, "model": "m",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": content}, "finish_reason": "stop"}]
+        })).unwrap();
+        let cassette = Cassette {
+            exchanges: vec![
+                Exchange::Chat { request: json!({"recorded": "elsewhere"}), response: response("one") },
+                Exchange::Chat { request: json!({"recorded": "elsewhere"}), response: response("two") },
+            ],
+            ..Cassette::default()
+        };
+
+        let lenient = ReplayProvider::from_cassette(cassette.clone(), false);
+        assert_eq!(text(&lenient.chat(request("a")).await.unwrap()), "one");
+        assert_eq!(text(&lenient.chat(request("b")).await.unwrap()), "two");
+        assert!(lenient.chat(request("c")).await.is_err());
+
+        let strict = ReplayProvider::from_cassette(cassette, true);
+        assert!(strict.chat(request("a")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_recorders_append_to_the_cassette() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ReplayConfig { mode: ReplayMode::Record, cassette: dir.path().join("cassette.json"), strict: true };
+        let recorder = || ReplayProvider::record(Box::new(EchoProvider { calls: Arc::new(Mutex::new(0)) }), config.clone());
+
+        recorder().chat(request("earlier run")).await.unwrap();
+        let (a, b) = (recorder(), recorder());
+        tokio::join!(
+            async { for i in 0..5 { a.chat(request(&format!("a{}", i))).await.unwrap(); } },
+            async { for i in 0..5 { b.chat(request(&format!("b{}", i))).await.unwrap(); } },
+        );
+
+        let cassette = Cassette::load(&config.cassette).unwrap();
+        assert_eq!(cassette.exchanges.len(), 11);
+        assert_eq!(cassette.provider, "echo");
+    }
+
+    #[tokio::test]
+    async fn test_replayed_error_keeps_its_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ReplayConfig { mode: ReplayMode::Record, cassette: dir.path().join("cassette.json"), strict: true };
+        let recorder = ReplayProvider::record(Box::new(EchoProvider { calls: Arc::new(Mutex::new(0)) }), config.clone());
+        assert!(recorder.chat(request("overloaded")).await.is_err());
+
+        let replayer = ReplayProvider::replay(ReplayConfig { mode: ReplayMode::Replay, ..config }).unwrap();
+        let error = replayer.chat(request("overloaded")).await.unwrap_err();
+        assert_eq!(
+            crate::retry::classify(&error),
+            Some((crate::retry::RetryReason::RateLimited, Some(Duration::from_secs(3))))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recorded_stream_keeps_tool_calls() {
+        let chunk = |delta: Value| serde_json::from_value(json!({
+            "id": "s", "object": "chat.completion.chunk", "created": 0, "model": "m",
+            "choices": [{"index": 0, "delta": delta}]
+        })).unwrap();
+        let cassette = Cassette {
+            exchanges: vec![Exchange::Stream {
+                request: json!({}),
+                chunks: vec![
+                    chunk(json!({"role": "assistant", "tool_calls": [{"index": 0, "id": "call_1", "type": "function", "function": {"name": "read", "arguments": "{\"path\":"}}]})),
+                    chunk(json!({"role": "assistant", "tool_calls": [{"index": 0, "function": {"arguments": "\"a\"}"}}]})),
+                    chunk(json!({"role": "assistant", "tool_calls": [{"index": 1, "id": "call_2", "type": "function", "function": {"name": "ls", "arguments": "{}"}}]})),
+                ],
+            }],
+            ..Cassette::default()
+        };
+
+        let response = ReplayProvider::from_cassette(cassette, false).chat(request("a")).await.unwrap();
+        match &response.choices[0].message {
+            ChatMessage::Assistant { tool_calls: Some(calls), .. } => {
+                assert_eq!(calls.len(), 2);
+                assert_eq!(calls[0].id, "call_1");
+                assert_eq!(calls[0].function.arguments, r#"{"path":"a"}"#);
+                assert_eq!(calls[1].function.name, "ls");
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use async_trait::async_trait;
+use openai_dive::v1::resources::{
+    chat::{ChatCompletionParameters, ChatCompletionResponse},
+    model::{ListModelResponse, Model},
+};
+
+use crate::client::LlmClient;
+use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo};
+use crate::retry::is_failover_error;
+
+/// Route used for requests whose model is not a route name (the main brain)
+pub const ROUTE_DEFAULT: &str = "default";
+/// Route for small side requests: status lines (gerund), command fixes...
+pub const ROUTE_FAST: &str = "fast";
+
+/// One step of a fallback chain, the request model is replaced by `model`
+#[derive(Debug)]
+pub struct RouteTarget {
+    pub client: LlmClient,
+    pub model: String,
+}
+
+impl RouteTarget {
+    pub fn new(client: LlmClient, model: String) -> Self {
+        Self { client, model }
+    }
+}
+
+/// Provider routing requests to ordered fallback chains.
+///
+/// The request model selects the chain: a route name (e.g. `fast`) picks that route,
+/// anything else goes to the `default` route. Targets are tried in order, each with its
+/// own retry policy, and the next one is used when a target is rate limited, fails with a
+/// server error or cannot be reached. Other errors are returned right away.
+pub struct RouterProvider {
+    routes: HashMap<String, Vec<RouteTarget>>,
+}
+
+impl RouterProvider {
+    pub fn new(default: Vec<RouteTarget>) -> Self {
+        Self {
+            routes: HashMap::from([(ROUTE_DEFAULT.to_string(), default)]),
+        }
+    }
+
+    pub fn with_route(mut self, name: impl Into<String>, targets: Vec<RouteTarget>) -> Self {
+        self.routes.insert(name.into(), targets);
+        self
+    }
+
+    fn chain(&self, model: &str) -> Result<&[RouteTarget], LlmError> {
+        self.routes.get(model)
+            .or_else(|| self.routes.get(ROUTE_DEFAULT))
+            .filter(|targets| !targets.is_empty())
+            .map(|targets| targets.as_slice())
+            .ok_or_else(|| format!("no provider configured for route '{}'", model).into())
+    }
+
+    fn failed(model: &str, errors: Vec<String>) -> LlmError {
+        format!("all providers failed for '{}': {}", model, errors.join(" | ")).into()
+    }
+}
+
+#[async_trait]
+impl LlmProvider for RouterProvider {
+    async fn models(&self) -> Result<ListModelResponse, LlmError> {
+        let mut names: Vec<&String> = self.routes.keys().collect();
+        names.sort();
+        Ok(ListModelResponse {
+            object: "list".to_string(),
+            data: names.into_iter().map(|name| Model {
+                id: name.clone(),
+                object: "model".to_string(),
+                created: None,
+                owned_by: "router".to_string(),
+            }).collect(),
+        })
+    }
+
+    async fn default_model(&self) -> Result<String, LlmError> {
+        Ok(ROUTE_DEFAULT.to_string())
+    }
+
+    async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+        let mut errors = Vec::new();
+        for target in self.chain(&request.model)? {
+            let mut request = request.clone();
+            request.model = target.model.clone();
+            match target.client.chat(request).await {
+                Ok(response) => return Ok(response),
+                Err(e) if is_failover_error(&e) => {
+                    errors.push(format!("{}/{}: {}", target.client.provider_name(), target.model, e));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(Self::failed(&request.model, errors))
+    }
+
+    /// Failover only happens while establishing the stream
+    async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+        let mut errors = Vec::new();
+        for target in self.chain(&request.model)? {
+            let mut request = request.clone();
+            request.model = target.model.clone();
+            match target.client.chat_stream(request).await {
+                Ok(stream) => return Ok(stream),
+                Err(e) if is_failover_error(&e) => {
+                    errors.push(format!("{}/{}: {}", target.client.provider_name(), target.model, e));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(Self::failed(&request.model, errors))
+    }
+
+    fn supports_functions(&self, model: String) -> bool {
+        self.chain(&model)
+            .map(|targets| targets.iter().all(|t| t.client.provider().supports_functions(t.model.clone())))
+            .unwrap_or(false)
+    }
+
+    fn supports_structured_output(&self, model: String) -> bool {
+        self.chain(&model)
+            .map(|targets| targets.iter().all(|t| t.client.provider().supports_structured_output(t.model.clone())))
+            .unwrap_or(false)
+    }
+
+    /// Each target client translates the reasoning config for its own provider
+    fn adapt_reasoning(&self, request: ChatCompletionParameters) -> ChatCompletionParameters {
+        request
+    }
+
+    fn has_route(&self, route: &str) -> bool {
+        self.routes.contains_key(route)
+    }
+
+    fn name(&self) -> &'static str {
+        "router"
+    }
+
+    fn info() -> ProviderInfo {
+        ProviderInfo {
+            name: "router",
+            display_name: "Router",
+            env_vars: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::{HttpError, RetryConfig};
+    use openai_dive::v1::resources::chat::{ChatCompletionParametersBuilder, ChatMessage, ChatMessageContent};
+    use serde_json::json;
+
+    /// Answers with the requested model, or fails with the `error` status
+    struct StubProvider {
+        error: Option<u16>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for StubProvider {
+        async fn models(&self) -> Result<ListModelResponse, LlmError> {
+            Ok(ListModelResponse { object: "list".to_string(), data: vec![] })
+        }
+
+        async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+            if let Some(status) = self.error {
+                return Err(Box::new(HttpError { status, retry_after: None, message: format!("status {}", status) }));
+            }
+            Ok(serde_json::from_value(json!({
+                "id": "stub",
+                "object": "chat.completion",
+                "created": 0,
+                "model": request.model,
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": request.model },
+                    "finish_reason": "stop"
+                }]
+            }))?)
+        }
+
+        async fn chat_stream(&self, _: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+            Err("not supported".into())
+        }
+
+        fn supports_functions(&self, _: String) -> bool { true }
+        fn supports_structured_output(&self, _: String) -> bool { false }
+        fn name(&self) -> &'static str { "stub" }
+        fn info() -> ProviderInfo { ProviderInfo { name: "stub", display_name: "Stub", env_vars: vec![] } }
+    }
+
+    fn target(error: Option<u16>, model: &str) -> RouteTarget {
+        let client = LlmClient::from_provider(Box::new(StubProvider { error })).with_retry(RetryConfig::disabled());
+        RouteTarget::new(client, model.to_string())
+    }
+
+    fn request(model: &str) -> ChatCompletionParameters {
+        ChatCompletionParametersBuilder::default()
+            .model(model.to_string())
+            .messages(vec![ChatMessage::User { content: ChatMessageContent::Text("hi".to_string()), name: None }])
+            .build()
+            .unwrap()
+    }
+
+    fn answered_by(response: &ChatCompletionResponse) -> String {
+        match &response.choices[0].message {
+            ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. } => text.clone(),
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_router_fails_over_and_routes() {
+        let router = RouterProvider::new(vec![target(Some(503), "strong-primary"), target(None, "strong-backup")])
+            .with_route(ROUTE_FAST, vec![target(None, "cheap")]);
+        let llm = LlmClient::router(router);
+
+        let response = llm.chat(request("whatever-the-brain-asks")).await.unwrap();
+        assert_eq!(answered_by(&response), "strong-backup");
+
+        let fast = llm.route_model(ROUTE_FAST, "strong");
+        assert_eq!(fast, ROUTE_FAST);
+        let response = llm.chat(request(&fast)).await.unwrap();
+        assert_eq!(answered_by(&response), "cheap");
+
+        assert_eq!(llm.route_model("unknown", "strong"), "strong");
+    }
+
+    #[tokio::test]
+    async fn test_router_reports_every_failure() {
+        let llm = LlmClient::router(RouterProvider::new(vec![target(Some(429), "a"), target(Some(502), "b")]));
+        let error = llm.chat(request("model")).await.unwrap_err().to_string();
+        assert!(error.contains("stub/a") && error.contains("stub/b"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_router_does_not_fail_over_on_client_error() {
+        let llm = LlmClient::router(RouterProvider::new(vec![target(Some(400), "a"), target(None, "b")]));
+        let error = llm.chat(request("model")).await.unwrap_err().to_string();
+        assert_eq!(error, "status 400");
+    }
+}
@@ -0,0 +1,257 @@
+use openai_dive::v1::resources::chat::{ChatCompletionParameters, ChatMessage, ChatMessageContent, ChatMessageContentPart, ChatMessageTextContentPart};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Key of the reasoning config in `ChatCompletionParameters::extra_body`
+const REASONING_KEY: &str = "reasoning";
+
+/// Type of the assistant content part holding the reasoning state of the turn (see ReasoningState)
+const REASONING_STATE_PART: &str = "reasoning_state";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReasoningEffort::Low => "low",
+            ReasoningEffort::Medium => "medium",
+            ReasoningEffort::High => "high",
+        }
+    }
+}
+
+/// Provider agnostic reasoning config, either an effort level or a token budget.
+/// Same shape as OpenRouter's `reasoning` parameter, other providers translate it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReasoningConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effort: Option<ReasoningEffort>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+impl ReasoningConfig {
+    pub fn effort(effort: ReasoningEffort) -> Self {
+        Self { effort: Some(effort), max_tokens: None }
+    }
+
+    pub fn budget(max_tokens: u32) -> Self {
+        Self { effort: None, max_tokens: Some(max_tokens) }
+    }
+
+    /// Effort level, derived from the budget when only a budget is set
+    pub fn effort_level(&self) -> ReasoningEffort {
+        match (self.effort, self.max_tokens) {
+            (Some(effort), _) => effort,
+            (None, Some(budget)) if budget < 4096 => ReasoningEffort::Low,
+            (None, Some(budget)) if budget < 16384 => ReasoningEffort::Medium,
+            (None, Some(_)) => ReasoningEffort::High,
+            (None, None) => ReasoningEffort::Medium,
+        }
+    }
+
+    /// Token budget, derived from the effort when only an effort is set
+    pub fn budget_tokens(&self) -> u32 {
+        self.max_tokens.unwrap_or(match self.effort_level() {
+            ReasoningEffort::Low => 2048,
+            ReasoningEffort::Medium => 8192,
+            ReasoningEffort::High => 24576,
+        })
+    }
+}
+
+/// Carry a reasoning config on a request, providers translate it to their own parameter
+pub trait ReasoningRequest: Sized {
+    fn with_reasoning(self, reasoning: ReasoningConfig) -> Self;
+    fn reasoning(&self) -> Option<ReasoningConfig>;
+    /// Remove the reasoning config from the request
+    fn take_reasoning(&mut self) -> Option<ReasoningConfig>;
+    /// Translate the reasoning config to OpenAI's `reasoning_effort`
+    fn reasoning_as_effort(self) -> Self;
+    /// Drop the reasoning state of the assistant turns, for providers that do not read it back
+    fn strip_reasoning_state(self) -> Self;
+}
+
+impl ReasoningRequest for ChatCompletionParameters {
+    fn with_reasoning(mut self, reasoning: ReasoningConfig) -> Self {
+        let mut extra = match self.extra_body.take() {
+            Some(Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        extra.insert(REASONING_KEY.to_string(), json!(reasoning));
+        self.extra_body = Some(Value::Object(extra));
+        self
+    }
+
+    fn reasoning(&self) -> Option<ReasoningConfig> {
+        self.extra_body.as_ref()
+            .and_then(|extra| extra.get(REASONING_KEY))
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    fn take_reasoning(&mut self) -> Option<ReasoningConfig> {
+        let Some(Value::Object(extra)) = &mut self.extra_body else {
+            return None;
+        };
+        let reasoning = extra.remove(REASONING_KEY)
+            .and_then(|value| serde_json::from_value(value).ok());
+        if extra.is_empty() {
+            self.extra_body = None;
+        }
+        reasoning
+    }
+
+    fn reasoning_as_effort(mut self) -> Self {
+        if let Some(reasoning) = self.take_reasoning() {
+            let mut extra = match self.extra_body.take() {
+                Some(Value::Object(map)) => map,
+                _ => serde_json::Map::new(),
+            };
+            extra.insert("reasoning_effort".to_string(), json!(reasoning.effort_level().as_str()));
+            self.extra_body = Some(Value::Object(extra));
+        }
+        self
+    }
+
+    fn strip_reasoning_state(mut self) -> Self {
+        self.messages = self.messages.into_iter().map(ReasoningState::without_reasoning_state).collect();
+        self
+    }
+}
+
+/// Opaque reasoning state a provider needs back with an assistant turn (Anthropic signed thinking
+/// blocks, Gemini thought signatures). openai_dive messages have no field for it, so it rides in
+/// the trace as an extra `reasoning_state` content part of the assistant message, keyed by
+/// provider name, and is saved, forked and reloaded with the trace. Providers that do not read it
+/// drop it in `adapt_reasoning`, the agent leaves it out of the messages of its events.
+pub trait ReasoningState: Sized {
+    /// Attach the state of `provider`, replacing the previous one
+    fn with_reasoning_state(self, provider: &str, state: Value) -> Self;
+    fn reasoning_state(&self, provider: &str) -> Option<Value>;
+    fn without_reasoning_state(self) -> Self;
+}
+
+impl ReasoningState for ChatMessage {
+    fn with_reasoning_state(mut self, provider: &str, state: Value) -> Self {
+        let ChatMessage::Assistant { content, .. } = &mut self else {
+            return self;
+        };
+        let mut states = reasoning_states(content);
+        states.insert(provider.to_string(), state);
+
+        let mut parts = match visible_content(content.take()) {
+            Some(ChatMessageContent::Text(text)) => vec![ChatMessageContentPart::Text(ChatMessageTextContentPart { r#type: "text".to_string(), text })],
+            Some(ChatMessageContent::ContentPart(parts)) => parts,
+            _ => vec![],
+        };
+        parts.push(ChatMessageContentPart::Text(ChatMessageTextContentPart {
+            r#type: REASONING_STATE_PART.to_string(),
+            text: Value::Object(states).to_string(),
+        }));
+        *content = Some(ChatMessageContent::ContentPart(parts));
+        self
+    }
+
+    fn reasoning_state(&self, provider: &str) -> Option<Value> {
+        let ChatMessage::Assistant { content, .. } = self else {
+            return None;
+        };
+        reasoning_states(content).remove(provider)
+    }
+
+    fn without_reasoning_state(mut self) -> Self {
+        if let ChatMessage::Assistant { content, .. } = &mut self {
+            *content = visible_content(content.take());
+        }
+        self
+    }
+}
+
+fn is_reasoning_state(part: &ChatMessageContentPart) -> bool {
+    matches!(part, ChatMessageContentPart::Text(text) if text.r#type == REASONING_STATE_PART)
+}
+
+fn reasoning_states(content: &Option<ChatMessageContent>) -> serde_json::Map<String, Value> {
+    let Some(ChatMessageContent::ContentPart(parts)) = content else {
+        return serde_json::Map::new();
+    };
+    parts.iter()
+        .find_map(|part| match part {
+            ChatMessageContentPart::Text(text) if text.r#type == REASONING_STATE_PART => serde_json::from_str(&text.text).ok(),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Content without the reasoning state part, back to plain text when only text is left
+fn visible_content(content: Option<ChatMessageContent>) -> Option<ChatMessageContent> {
+    let Some(ChatMessageContent::ContentPart(parts)) = content else {
+        return content;
+    };
+    if !parts.iter().any(is_reasoning_state) {
+        return Some(ChatMessageContent::ContentPart(parts));
+    }
+    let mut parts: Vec<_> = parts.into_iter().filter(|part| !is_reasoning_state(part)).collect();
+    match parts.as_slice() {
+        [] => None,
+        [ChatMessageContentPart::Text(_)] => match parts.pop() {
+            Some(ChatMessageContentPart::Text(text)) => Some(ChatMessageContent::Text(text.text)),
+            _ => None,
+        },
+        _ => Some(ChatMessageContent::ContentPart(parts)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reasoning_config_conversions() {
+        assert_eq!(ReasoningConfig::effort(ReasoningEffort::High).budget_tokens(), 24576);
+        assert_eq!(ReasoningConfig::budget(1024).effort_level(), ReasoningEffort::Low);
+        assert_eq!(ReasoningConfig::budget(10000).budget_tokens(), 10000);
+
+        let config: ReasoningConfig = serde_json::from_str(r#"{"effort":"medium"}"#).unwrap();
+        assert_eq!(config, ReasoningConfig::effort(ReasoningEffort::Medium));
+    }
+
+    #[test]
+    fn test_reasoning_on_request() {
+        let request = ChatCompletionParameters::default()
+            .with_reasoning(ReasoningConfig::budget(20000));
+        assert_eq!(request.reasoning(), Some(ReasoningConfig::budget(20000)));
+
+        let request = request.reasoning_as_effort();
+        assert_eq!(request.reasoning(), None);
+        assert_eq!(request.extra_body, Some(json!({"reasoning_effort": "high"})));
+    }
+
+    #[test]
+    fn test_reasoning_state_survives_serialization() {
+        let message = ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text("let me look".to_string())),
+            reasoning_content: None,
+            refusal: None,
+            name: None,
+            audio: None,
+            tool_calls: None,
+        }
+        .with_reasoning_state("anthropic", json!([{"type": "redacted_thinking", "data": "b3BhcXVl"}]))
+        .with_reasoning_state("gemini", json!({"call_1": "c2ln"}));
+
+        let reloaded: ChatMessage = serde_json::from_str(&serde_json::to_string(&message).unwrap()).unwrap();
+        assert_eq!(reloaded.reasoning_state("anthropic"), Some(json!([{"type": "redacted_thinking", "data": "b3BhcXVl"}])));
+        assert_eq!(reloaded.reasoning_state("gemini"), Some(json!({"call_1": "c2ln"})));
+        assert_eq!(reloaded.reasoning_state("openai"), None);
+
+        let ChatMessage::Assistant { content, .. } = reloaded.without_reasoning_state() else { unreachable!() };
+        assert!(matches!(content, Some(ChatMessageContent::Text(text)) if text == "let me look"));
+    }
+}
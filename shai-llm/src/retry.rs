@@ -0,0 +1,456 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use openai_dive::v1::error::APIError;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::provider::LlmError;
+
+/// Retry policy applied by LlmClient around chat and chat_stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// Number of retries after the first attempt, 0 disables retrying
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on each following retry
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Upper bound of a single wait, a longer Retry-After gives up with RetryAfterTooLong
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Timeout of a single attempt (time to first byte for streams)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+fn default_max_retries() -> u32 { 3 }
+fn default_initial_backoff_ms() -> u64 { 1000 }
+fn default_max_backoff_ms() -> u64 { 60_000 }
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: default_max_retries(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            timeout_secs: None,
+        }
+    }
+}
+
+impl RetryConfig {
+    pub fn disabled() -> Self {
+        Self { max_retries: 0, ..Self::default() }
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_secs.map(Duration::from_secs)
+    }
+
+    /// Delay before retry number `attempt` (starting at 1), None if we should give up.
+    /// A server provided Retry-After wins over the exponential backoff.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if attempt > self.max_retries {
+            return None;
+        }
+        let max = Duration::from_millis(self.max_backoff_ms);
+        if let Some(retry_after) = retry_after {
+            return if retry_after <= max { Some(retry_after) } else { None };
+        }
+
+        let exp = self.initial_backoff_ms.saturating_mul(1u64 << (attempt - 1).min(32));
+        let base = exp.min(self.max_backoff_ms);
+        // equal jitter: half fixed, half random so concurrent agents do not retry in lockstep
+        let half = base / 2;
+        let jitter = if half > 0 { fastrand::u64(0..=half) } else { 0 };
+        Some(Duration::from_millis(half + jitter))
+    }
+}
+
+/// Error carrying the HTTP status and Retry-After of a failed provider call
+#[derive(Debug)]
+pub struct HttpError {
+    pub status: u16,
+    pub retry_after: Option<Duration>,
+    pub message: String,
+}
+
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+impl HttpError {
+    /// Build the error from a non successful response, consuming its body
+    pub async fn from_response(response: reqwest::Response, context: &str) -> Self {
+        let status = response.status().as_u16();
+        let retry_after = parse_retry_after(response.headers());
+        let text = response.text().await.unwrap_or_default();
+        Self {
+            status,
+            retry_after,
+            message: format!("{}: {}", context, text),
+        }
+    }
+}
+
+/// Transient error given up on because the server asks to wait longer than `max_backoff_ms`
+#[derive(Debug)]
+pub struct RetryAfterTooLong {
+    pub retry_after: Duration,
+    pub max_backoff: Duration,
+    pub source: LlmError,
+}
+
+impl std::fmt::Display for RetryAfterTooLong {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (the server asks to retry after {}s, longer than the {}s max backoff)",
+            self.source,
+            self.retry_after.as_secs_f64(),
+            self.max_backoff.as_secs_f64(),
+        )
+    }
+}
+
+impl std::error::Error for RetryAfterTooLong {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+/// Parse a Retry-After header, either delay-seconds or an HTTP date
+pub fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        return (secs >= 0.0).then(|| Duration::from_secs_f64(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delta = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(delta.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Why a call is worth retrying
+#[derive(Debug, Clone, PartialEq)]
+pub enum RetryReason {
+    RateLimited,
+    Overloaded,
+    ServerError(u16),
+    Network,
+    Timeout,
+}
+
+impl std::fmt::Display for RetryReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RetryReason::RateLimited => write!(f, "rate limited"),
+            RetryReason::Overloaded => write!(f, "provider overloaded"),
+            RetryReason::ServerError(code) => write!(f, "server error {}", code),
+            RetryReason::Network => write!(f, "connection error"),
+            RetryReason::Timeout => write!(f, "request timed out"),
+        }
+    }
+}
+
+fn status_reason(status: u16) -> Option<RetryReason> {
+    match status {
+        429 => Some(RetryReason::RateLimited),
+        529 => Some(RetryReason::Overloaded),
+        500 | 502 | 503 | 504 => Some(RetryReason::ServerError(status)),
+        _ => None,
+    }
+}
+
+/// Providers going through openai_dive only give us the body, look for a hint like "try again in 8s"
+fn retry_after_from_text(text: &str) -> Option<Duration> {
+    let re = Regex::new(r"(?i)(?:try again in|retry after|retry-after:?)\s*(\d+(?:\.\d+)?)\s*(ms|s|sec|seconds?)?").ok()?;
+    let caps = re.captures(text)?;
+    let value: f64 = caps.get(1)?.as_str().parse().ok()?;
+    match caps.get(2).map(|m| m.as_str().to_lowercase()) {
+        Some(unit) if unit == "ms" => Some(Duration::from_secs_f64(value / 1000.0)),
+        _ => Some(Duration::from_secs_f64(value)),
+    }
+}
+
+/// Decide whether an error is transient, with the delay the server asked for if any
+pub fn classify(error: &LlmError) -> Option<(RetryReason, Option<Duration>)> {
+    if let Some(e) = error.downcast_ref::<RetryAfterTooLong>() {
+        return classify(&e.source);
+    }
+    if let Some(e) = error.downcast_ref::<HttpError>() {
+        return status_reason(e.status)
+            .map(|reason| (reason, e.retry_after.or_else(|| retry_after_from_text(&e.message))));
+    }
+    if let Some(e) = error.downcast_ref::<APIError>() {
+        return match e {
+            APIError::RateLimitError(text) => Some((RetryReason::RateLimited, retry_after_from_text(text))),
+            APIError::UnknownError(code, text) => status_reason(*code).map(|r| (r, retry_after_from_text(text))),
+            _ => None,
+        };
+    }
+    if error.downcast_ref::<tokio::time::error::Elapsed>().is_some() {
+        return Some((RetryReason::Timeout, None));
+    }
+    if let Some(e) = error.downcast_ref::<reqwest::Error>() {
+        if e.is_timeout() {
+            return Some((RetryReason::Timeout, None));
+        }
+        if e.is_connect() {
+            return Some((RetryReason::Network, None));
+        }
+        return e.status().and_then(|s| status_reason(s.as_u16())).map(|r| (r, None));
+    }
+
+    let text = error.to_string().to_lowercase();
+    if text.contains("rate limit") || text.contains("too many requests") {
+        Some((RetryReason::RateLimited, retry_after_from_text(&text)))
+    } else if text.contains("overloaded") {
+        Some((RetryReason::Overloaded, retry_after_from_text(&text)))
+    } else if text.contains("error sending request") {
+        // reqwest send failure flattened into a string by openai_dive
+        Some((RetryReason::Network, None))
+    } else {
+        None
+    }
+}
+
+/// HTTP status carried by a provider error, if any
+pub fn error_status(error: &LlmError) -> Option<u16> {
+    if let Some(e) = error.downcast_ref::<RetryAfterTooLong>() {
+        return error_status(&e.source);
+    }
+    if let Some(e) = error.downcast_ref::<HttpError>() {
+        return Some(e.status);
+    }
+    if let Some(e) = error.downcast_ref::<APIError>() {
+        return match e {
+            APIError::InvalidRequestError(_) => Some(400),
+            APIError::AuthenticationError(_) => Some(401),
+            APIError::PermissionError(_) => Some(403),
+            APIError::NotFoundError(_) => Some(404),
+            APIError::RateLimitError(_) => Some(429),
+            APIError::UnknownError(code, _) => Some(*code),
+            _ => None,
+        };
+    }
+    error.downcast_ref::<reqwest::Error>()
+        .and_then(|e| e.status())
+        .map(|s| s.as_u16())
+}
+
+/// Whether another provider may succeed where this one failed: rate limits, server errors
+/// and transport failures. Other client errors (bad request, auth...) would fail the same way.
+pub fn is_failover_error(error: &LlmError) -> bool {
+    if classify(error).is_some() {
+        return true;
+    }
+    if error.downcast_ref::<reqwest::Error>().is_some_and(|e| e.is_request()) {
+        return true;
+    }
+    error_status(error).is_some_and(|status| (500..600).contains(&status))
+}
+
+/// A retry about to happen, reported to the observer of the current task
+#[derive(Debug, Clone)]
+pub struct RetryNotice {
+    pub provider: String,
+    pub attempt: u32,
+    pub max_retries: u32,
+    pub delay: Duration,
+    pub reason: String,
+}
+
+pub type RetryObserver = Arc<dyn Fn(RetryNotice) + Send + Sync>;
+
+tokio::task_local! {
+    static RETRY_OBSERVER: RetryObserver;
+}
+
+/// Run a future with an observer notified of every retry done by LlmClient calls inside it
+pub async fn observe_retries<F: Future>(observer: RetryObserver, fut: F) -> F::Output {
+    RETRY_OBSERVER.scope(observer, fut).await
+}
+
+pub(crate) fn notify_retry(notice: RetryNotice) {
+    let _ = RETRY_OBSERVER.try_with(|observer| observer(notice));
+}
+
+/// Run `call` until it succeeds, fails with a non transient error or retries are exhausted
+pub(crate) async fn with_retry<T, F, Fut>(config: &RetryConfig, provider: &str, mut call: F) -> Result<T, LlmError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, LlmError>>,
+{
+    let mut attempt = 0;
+    loop {
+        let result = match config.timeout() {
+            Some(timeout) => match tokio::time::timeout(timeout, call()).await {
+                Ok(result) => result,
+                Err(elapsed) => Err(Box::new(elapsed) as LlmError),
+            },
+            None => call().await,
+        };
+
+        let error = match result {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        let Some((reason, retry_after)) = classify(&error) else {
+            return Err(error);
+        };
+
+        attempt += 1;
+        let Some(delay) = config.delay(attempt, retry_after) else {
+            return Err(match retry_after {
+                Some(retry_after) if attempt <= config.max_retries => Box::new(RetryAfterTooLong {
+                    retry_after,
+                    max_backoff: Duration::from_millis(config.max_backoff_ms),
+                    source: error,
+                }) as LlmError,
+                _ => error,
+            });
+        };
+
+        notify_retry(RetryNotice {
+            provider: provider.to_string(),
+            attempt,
+            max_retries: config.max_retries,
+            delay,
+            reason: reason.to_string(),
+        });
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_backoff_is_exponential_with_jitter() {
+        let config = RetryConfig { max_retries: 5, initial_backoff_ms: 1000, max_backoff_ms: 4000, timeout_secs: None };
+        for _ in 0..50 {
+            let first = config.delay(1, None).unwrap();
+            assert!(first >= Duration::from_millis(500) && first <= Duration::from_millis(1000));
+            let capped = config.delay(5, None).unwrap();
+            assert!(capped >= Duration::from_millis(2000) && capped <= Duration::from_millis(4000));
+        }
+        assert_eq!(config.delay(6, None), None);
+    }
+
+    #[test]
+    fn test_retry_after_wins() {
+        let config = RetryConfig::default();
+        assert_eq!(config.delay(1, Some(Duration::from_secs(8))), Some(Duration::from_secs(8)));
+        // longer than what we accept to wait, give up
+        assert_eq!(config.delay(1, Some(Duration::from_secs(3600))), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after_header() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::RETRY_AFTER, "8".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(8)));
+
+        headers.insert(reqwest::header::RETRY_AFTER, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_classify() {
+        let err: LlmError = Box::new(HttpError { status: 429, retry_after: Some(Duration::from_secs(2)), message: "slow down".into() });
+        assert_eq!(classify(&err), Some((RetryReason::RateLimited, Some(Duration::from_secs(2)))));
+
+        let err: LlmError = Box::new(APIError::RateLimitError("Please try again in 1.5s".into()));
+        assert_eq!(classify(&err), Some((RetryReason::RateLimited, Some(Duration::from_millis(1500)))));
+
+        let err: LlmError = Box::new(APIError::UnknownError(503, "unavailable".into()));
+        assert_eq!(classify(&err), Some((RetryReason::ServerError(503), None)));
+
+        let err: LlmError = Box::new(APIError::AuthenticationError("bad key".into()));
+        assert_eq!(classify(&err), None);
+
+        let err: LlmError = "invalid model".into();
+        assert_eq!(classify(&err), None);
+    }
+
+    #[test]
+    fn test_is_failover_error() {
+        let err: LlmError = Box::new(APIError::RateLimitError("busy".into()));
+        assert!(is_failover_error(&err));
+
+        let err: LlmError = Box::new(HttpError { status: 501, retry_after: None, message: "not implemented".into() });
+        assert!(is_failover_error(&err));
+
+        let err: LlmError = Box::new(APIError::ParseError("error sending request for url (https://api.example.com)".into()));
+        assert!(is_failover_error(&err));
+
+        let err: LlmError = Box::new(HttpError { status: 400, retry_after: None, message: "bad request".into() });
+        assert!(!is_failover_error(&err));
+
+        let err: LlmError = Box::new(APIError::AuthenticationError("bad key".into()));
+        assert!(!is_failover_error(&err));
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_notifies_observer() {
+        let config = RetryConfig { max_retries: 3, initial_backoff_ms: 1, max_backoff_ms: 10, timeout_secs: None };
+        let calls = AtomicU32::new(0);
+        let notices = Arc::new(Mutex::new(Vec::new()));
+        let observer: RetryObserver = {
+            let notices = notices.clone();
+            Arc::new(move |n: RetryNotice| notices.lock().unwrap().push(n))
+        };
+
+        let result = observe_retries(observer, with_retry(&config, "test", || async {
+            if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(Box::new(APIError::RateLimitError("busy".into())) as LlmError)
+            } else {
+                Ok(42)
+            }
+        })).await;
+
+        assert_eq!(result.unwrap(), 42);
+        let notices = notices.lock().unwrap();
+        assert_eq!(notices.len(), 2);
+        assert_eq!(notices[0].attempt, 1);
+        assert_eq!(notices[1].reason, "rate limited");
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_stops_on_permanent_error() {
+        let config = RetryConfig { max_retries: 3, initial_backoff_ms: 1, max_backoff_ms: 10, timeout_secs: None };
+        let calls = AtomicU32::new(0);
+        let result: Result<(), LlmError> = with_retry(&config, "test", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(Box::new(APIError::InvalidRequestError("bad".into())) as LlmError)
+        }).await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_reports_too_long_retry_after() {
+        let config = RetryConfig { max_retries: 3, initial_backoff_ms: 1, max_backoff_ms: 1000, timeout_secs: None };
+        let calls = AtomicU32::new(0);
+        let result: Result<(), LlmError> = with_retry(&config, "test", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(Box::new(HttpError { status: 429, retry_after: Some(Duration::from_secs(120)), message: "slow down".into() }) as LlmError)
+        }).await;
+        let error = result.unwrap_err();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(error.to_string().contains("retry after 120s"));
+        // still a rate limit for the router
+        assert_eq!(error_status(&error), Some(429));
+        assert!(is_failover_error(&error));
+    }
+}
@@ -3,7 +3,7 @@ use async_trait::async_trait;
 
 use openai_dive::v1::resources::chat::{ChatCompletionFunction, ChatCompletionParameters, ChatCompletionParametersBuilder, ChatCompletionResponse, ChatCompletionTool, ChatCompletionToolChoice, ChatCompletionToolType, ChatMessage};
 
-use crate::{provider::LlmError, tool::{call_fc_auto::ToolCallFunctionCallingAuto, call_fc_required::ToolCallFunctionCallingRequired, call_structured_output::ToolCallStructuredOutput, ToolBox}, LlmClient, ToolCallMethod, ToolDescription};
+use crate::{provider::LlmError, tool::{call_fc_auto::ToolCallFunctionCallingAuto, call_fc_required::ToolCallFunctionCallingRequired, call_structured_output::ToolCallStructuredOutput, call_parsing::ToolCallParsing, ToolBox}, LlmClient, ToolCallMethod, ToolDescription};
 
 
 #[async_trait]
@@ -38,7 +38,7 @@ impl LlmToolCall for LlmClient {
                 self.chat_with_tools_so(request, tools).await
             }
             ToolCallMethod::Parsing => {
-                Err(LlmError::from("method not supported"))
+                self.chat_with_tools_parsing(request, tools).await
             }
         }
     }
@@ -68,6 +68,10 @@ impl ToolCallAuto for LlmClient {
             return Ok(result);
         }
         
-        self.chat_with_tools_so(request, tools).await
+        if let Ok(result) = self.chat_with_tools_so(request.clone(), tools).await {
+            return Ok(result);
+        }
+
+        self.chat_with_tools_parsing(request, tools).await
     }
 }
\ No newline at end of file
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use openai_dive::v1::resources::chat::{
+    ChatCompletionParameters, ChatCompletionResponse, ChatMessage, ChatMessageContent, Function, ToolCall
+};
+use openai_dive::v1::resources::shared::FinishReason;
+use crate::provider::LlmError;
+use crate::reasoning::ReasoningState;
+use crate::tool::ToolBox;
+use crate::LlmClient;
+
+const TOOL_CALL_OPEN: &str = "<tool_call>";
+const TOOL_CALL_CLOSE: &str = "</tool_call>";
+
+static OPEN_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<tool_call(\s[^>]*)?>").unwrap());
+static NAME_ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"name\s*=\s*["']([^"']+)["']"#).unwrap());
+static TRAILING_COMMA: LazyLock<Regex> = LazyLock::new(|| Regex::new(r",\s*([}\]])").unwrap());
+
+/// Tool documentation and calling protocol appended to the system prompt
+pub fn parsing_tools_prompt(tools: &ToolBox) -> String {
+    let mut doc = String::from("\n\n# Available Tools\n\nYou have access to the following tools:\n\n");
+    for tool in tools {
+        doc.push_str(&format!("## {}\n", tool.name()));
+        doc.push_str(&format!("**Description**: {}\n\n", tool.description()));
+        doc.push_str("**Parameters Schema**:\n```json\n");
+        doc.push_str(&serde_json::to_string_pretty(&tool.parameters_schema()).unwrap_or_default());
+        doc.push_str("\n```\n\n");
+    }
+    doc.push_str(concat!(
+        "# Calling Tools\n\n",
+        "To call a tool, write a tool_call tag containing a JSON object with the tool name and its arguments:\n\n",
+        "<tool_call>\n{\"name\": \"tool_name\", \"arguments\": {\"param\": \"value\"}}\n</tool_call>\n\n",
+        "You may call several tools in one answer, one tag per call. ",
+        "Results come back in <tool_result> tags in the next message. ",
+        "Answer without any tool_call tag when you are done.\n"
+    ));
+    doc
+}
+
+/// Rewrite the trace for models without function calling: assistant tool calls become
+/// tool_call tags and tool messages become user messages holding tool_result tags
+pub fn to_parsing_messages(messages: &[ChatMessage]) -> Vec<ChatMessage> {
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut converted = Vec::with_capacity(messages.len());
+
+    for message in messages.iter().cloned().map(ReasoningState::without_reasoning_state) {
+        match &message {
+            ChatMessage::Assistant { content, reasoning_content, tool_calls: Some(calls), .. } if !calls.is_empty() => {
+                let mut text = match content {
+                    Some(ChatMessageContent::Text(text)) => text.trim().to_string(),
+                    _ => String::new(),
+                };
+                for call in calls {
+                    names.insert(call.id.clone(), call.function.name.clone());
+                    let arguments: Value = serde_json::from_str(&call.function.arguments).unwrap_or(json!({}));
+                    let body = json!({ "name": call.function.name, "arguments": arguments });
+                    text.push_str(&format!("\n{}\n{}\n{}", TOOL_CALL_OPEN, body, TOOL_CALL_CLOSE));
+                }
+                converted.push(ChatMessage::Assistant {
+                    content: Some(ChatMessageContent::Text(text.trim().to_string())),
+                    reasoning_content: reasoning_content.clone(),
+                    tool_calls: None,
+                    refusal: None,
+                    name: None,
+                    audio: None,
+                });
+            }
+            ChatMessage::Tool { content, tool_call_id } => {
+                let result = match content {
+                    ChatMessageContent::Text(text) => text.clone(),
+                    other => serde_json::to_string(other).unwrap_or_default(),
+                };
+                let name = names.get(tool_call_id).map(String::as_str).unwrap_or("unknown");
+                let text = format!("<tool_result name=\"{}\">\n{}\n</tool_result>", name, result);
+
+                // consecutive results are merged in a single user message
+                if let Some(ChatMessage::User { content: ChatMessageContent::Text(previous), .. }) = converted.last_mut() {
+                    if previous.starts_with("<tool_result") {
+                        previous.push_str("\n");
+                        previous.push_str(&text);
+                        continue;
+                    }
+                }
+                converted.push(ChatMessage::User { content: ChatMessageContent::Text(text), name: None });
+            }
+            _ => converted.push(message),
+        }
+    }
+    converted
+}
+
+/// Extract tool calls from a model answer, returns the remaining text and the calls.
+///
+/// Lenient on purpose, small models routinely get the format slightly wrong: a missing closing
+/// tag, a code fence inside the tag, `<name>`/`<arguments>` sub-tags or a name attribute,
+/// alternative keys (`tool`, `parameters`...), arguments given as a JSON string, trailing commas
+/// or a tool name with the wrong case.
+pub fn parse_tool_calls(text: &str, tools: &ToolBox) -> (String, Vec<ToolCall>) {
+    let mut content = String::new();
+    let mut calls = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = find_open_tag(rest) {
+        content.push_str(&rest[..start.0]);
+        let after = &rest[start.1..];
+        let attributes = &rest[start.0..start.1];
+
+        // the body ends at the closing tag, the next opening tag or the end of the answer
+        let end = after.find(TOOL_CALL_CLOSE)
+            .map(|i| (i, i + TOOL_CALL_CLOSE.len()))
+            .or_else(|| find_open_tag(after).map(|(i, _)| (i, i)))
+            .unwrap_or((after.len(), after.len()));
+
+        if let Some((name, arguments)) = parse_invocation(attributes, &after[..end.0]) {
+            calls.push(ToolCall {
+                id: format!("call_{}", random_id()),
+                r#type: "function".to_string(),
+                function: Function {
+                    name: resolve_tool_name(&name, tools),
+                    arguments: arguments.to_string(),
+                },
+            });
+        } else {
+            // not understandable, leave it visible so the model can see what went wrong
+            content.push_str(&rest[start.0..start.1 + end.1]);
+        }
+        rest = &after[end.1..];
+    }
+    content.push_str(rest);
+
+    (content.trim().to_string(), calls)
+}
+
+/// Position of the next `<tool_call ...>` opening tag (start, end)
+fn find_open_tag(text: &str) -> Option<(usize, usize)> {
+    OPEN_TAG.find(text).map(|m| (m.start(), m.end()))
+}
+
+fn parse_invocation(open_tag: &str, body: &str) -> Option<(String, Value)> {
+    let attr_name = NAME_ATTRIBUTE
+        .captures(open_tag)
+        .map(|c| c[1].to_string());
+
+    let body = strip_code_fence(body.trim());
+
+    // <name>x</name><arguments>{...}</arguments>
+    let tag_name = sub_tag(body, "name").map(str::to_string);
+    let tag_args = sub_tag(body, "arguments").or_else(|| sub_tag(body, "parameters"));
+    if let Some(name) = tag_name.clone().or(attr_name.clone()) {
+        if let Some(args) = tag_args {
+            return Some((name, parse_json(strip_code_fence(args)).map(normalize_arguments).unwrap_or(json!({}))));
+        }
+    }
+
+    let value = parse_json(body);
+    match (attr_name.or(tag_name), value) {
+        // <tool_call name="x">{arguments}</tool_call>
+        (Some(name), Some(Value::Object(args))) if !args.contains_key("name") && !args.contains_key("tool") => {
+            Some((name, Value::Object(args)))
+        }
+        (Some(name), None) if body.is_empty() => Some((name, json!({}))),
+        // <tool_call>{"name": "x", "arguments": {...}}</tool_call>
+        (_, Some(Value::Object(object))) => {
+            let name = ["name", "tool", "tool_name", "function"].iter()
+                .find_map(|key| object.get(*key))
+                .and_then(|v| v.as_str().map(str::to_string).or_else(|| v["name"].as_str().map(str::to_string)))?;
+            let arguments = ["arguments", "parameters", "args", "tool_parameter", "input"].iter()
+                .find_map(|key| object.get(*key).cloned())
+                .or_else(|| object.get("function").and_then(|f| f.get("arguments")).cloned())
+                .map(normalize_arguments)
+                .unwrap_or(json!({}));
+            Some((name, arguments))
+        }
+        _ => None,
+    }
+}
+
+/// Content of `<tag>...</tag>` inside a body
+fn sub_tag<'a>(body: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = body.find(&open)? + open.len();
+    let end = body[start..].find(&close).map(|i| start + i).unwrap_or(body.len());
+    Some(body[start..end].trim())
+}
+
+fn strip_code_fence(text: &str) -> &str {
+    let text = text.trim();
+    let Some(inner) = text.strip_prefix("```") else {
+        return text;
+    };
+    let inner = inner.find('\n').map(|i| &inner[i + 1..]).unwrap_or(inner);
+    inner.trim_end().strip_suffix("```").unwrap_or(inner).trim()
+}
+
+/// Parse JSON, retrying without trailing commas and on the outermost object only
+fn parse_json(text: &str) -> Option<Value> {
+    if text.is_empty() {
+        return None;
+    }
+    if let Ok(value) = serde_json::from_str(text) {
+        return Some(value);
+    }
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    if end <= start {
+        return None;
+    }
+    let candidate = &text[start..=end];
+    let no_trailing_commas = TRAILING_COMMA.replace_all(candidate, "$1");
+    serde_json::from_str(&no_trailing_commas).ok()
+}
+
+/// Arguments must be an object, some models send them as a JSON string
+fn normalize_arguments(arguments: Value) -> Value {
+    match arguments {
+        Value::String(s) => parse_json(&s).filter(Value::is_object).unwrap_or(json!({})),
+        Value::Object(_) => arguments,
+        Value::Null => Value::Object(Map::new()),
+        other => json!({ "value": other }),
+    }
+}
+
+/// Map a name to the toolbox ignoring case and separators, unknown names are kept as is
+fn resolve_tool_name(name: &str, tools: &ToolBox) -> String {
+    let name = name.trim();
+    let simplify = |s: &str| s.to_lowercase().replace(['-', ' '], "_");
+    tools.iter()
+        .map(|t| t.name())
+        .find(|t| t == name)
+        .or_else(|| tools.iter().map(|t| t.name()).find(|t| simplify(t) == simplify(name)))
+        .unwrap_or_else(|| name.to_string())
+}
+
+fn random_id() -> String {
+    (0..9)
+        .map(|_| {
+            let chars = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+            chars[fastrand::usize(..chars.len())] as char
+        })
+        .collect()
+}
+
+#[async_trait]
+pub trait ToolCallParsing {
+    async fn chat_with_tools_parsing(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox
+    ) -> Result<ChatCompletionResponse, LlmError>;
+}
+
+#[async_trait]
+impl ToolCallParsing for LlmClient {
+    async fn chat_with_tools_parsing(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox
+    ) -> Result<ChatCompletionResponse, LlmError> {
+        let mut messages = to_parsing_messages(&request.messages);
+        let tools_doc = parsing_tools_prompt(tools);
+        match messages.iter_mut().find(|m| matches!(m, ChatMessage::System { .. })) {
+            Some(ChatMessage::System { content: ChatMessageContent::Text(system_text), .. }) => {
+                system_text.push_str(&tools_doc);
+            }
+            _ => messages.insert(0, ChatMessage::System { content: ChatMessageContent::Text(tools_doc), name: None }),
+        }
+
+        let mut request = request;
+        request.messages = messages;
+        request.tools = None;
+        request.tool_choice = None;
+        request.response_format = None;
+        request.temperature = Some(0.3);
+
+        let mut response = self
+            .chat(request)
+            .await
+            .map_err(|e| LlmError::from(e.to_string()))?;
+
+        for choice in &mut response.choices {
+            if let ChatMessage::Assistant { content, tool_calls, .. } = &mut choice.message {
+                if let Some(ChatMessageContent::Text(text)) = content {
+                    let (remaining, calls) = parse_tool_calls(text, tools);
+                    if !calls.is_empty() {
+                        *content = Some(ChatMessageContent::Text(remaining));
+                        *tool_calls = Some(calls);
+                        choice.finish_reason = Some(FinishReason::ToolCalls);
+                    }
+                }
+            }
+        }
+        Ok(response)
+    }
+}
@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod parsing_tests {
+    use std::sync::Arc;
+    use serde_json::{json, Value};
+    use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, Function, ToolCall};
+    use crate::tool::{ToolBox, ToolDescription};
+    use crate::tool::call_parsing::{parse_tool_calls, to_parsing_messages};
+
+    struct NamedTool(&'static str);
+
+    impl ToolDescription for NamedTool {
+        fn name(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn description(&self) -> String {
+            format!("the {} tool", self.0)
+        }
+
+        fn parameters_schema(&self) -> Value {
+            json!({ "type": "object", "properties": { "path": { "type": "string" } } })
+        }
+    }
+
+    fn toolbox() -> ToolBox {
+        vec![Arc::new(NamedTool("read_file")), Arc::new(NamedTool("write_file"))]
+    }
+
+    fn call(text: &str) -> (String, Vec<(String, Value)>) {
+        let (content, calls) = parse_tool_calls(text, &toolbox());
+        let calls = calls.into_iter()
+            .map(|c| (c.function.name, serde_json::from_str(&c.function.arguments).unwrap()))
+            .collect();
+        (content, calls)
+    }
+
+    #[test]
+    fn test_well_formed_calls() {
+        let (content, calls) = call(concat!(
+            "Let me look.\n",
+            "<tool_call>\n{\"name\": \"read_file\", \"arguments\": {\"path\": \"a.rs\"}}\n</tool_call>\n",
+            "<tool_call>{\"name\": \"write_file\", \"arguments\": {\"path\": \"b.rs\"}}</tool_call>"
+        ));
+        assert_eq!(content, "Let me look.");
+        assert_eq!(calls, vec![
+            ("read_file".to_string(), json!({"path": "a.rs"})),
+            ("write_file".to_string(), json!({"path": "b.rs"})),
+        ]);
+    }
+
+    #[test]
+    fn test_malformed_calls() {
+        // missing closing tag, code fence and trailing comma
+        let (_, calls) = call("<tool_call>\n```json\n{\"name\": \"read_file\", \"arguments\": {\"path\": \"a.rs\",},}\n```");
+        assert_eq!(calls, vec![("read_file".to_string(), json!({"path": "a.rs"}))]);
+
+        // alternative keys, arguments as a string and wrong case
+        let (_, calls) = call("<tool_call>{\"tool\": \"Read-File\", \"parameters\": \"{\\\"path\\\": \\\"a.rs\\\"}\"}</tool_call>");
+        assert_eq!(calls, vec![("read_file".to_string(), json!({"path": "a.rs"}))]);
+
+        // name attribute
+        let (_, calls) = call("<tool_call name=\"read_file\">{\"path\": \"a.rs\"}</tool_call>");
+        assert_eq!(calls, vec![("read_file".to_string(), json!({"path": "a.rs"}))]);
+
+        // sub tags
+        let (_, calls) = call("<tool_call><name>read_file</name><arguments>{\"path\": \"a.rs\"}</arguments></tool_call>");
+        assert_eq!(calls, vec![("read_file".to_string(), json!({"path": "a.rs"}))]);
+    }
+
+    #[test]
+    fn test_unparsable_call_is_kept_as_text() {
+        let (content, calls) = call("before <tool_call>not json at all</tool_call> after");
+        assert!(calls.is_empty());
+        assert_eq!(content, "before <tool_call>not json at all</tool_call> after");
+
+        let (content, calls) = call("just an answer");
+        assert!(calls.is_empty());
+        assert_eq!(content, "just an answer");
+    }
+
+    #[test]
+    fn test_history_is_rewritten_as_tags() {
+        let messages = vec![
+            ChatMessage::Assistant {
+                content: None,
+                reasoning_content: None,
+                tool_calls: Some(vec![ToolCall {
+                    id: "call_1".to_string(),
+                    r#type: "function".to_string(),
+                    function: Function { name: "read_file".to_string(), arguments: "{\"path\":\"a.rs\"}".to_string() },
+                }]),
+                refusal: None,
+                name: None,
+                audio: None,
+            },
+            ChatMessage::Tool { content: ChatMessageContent::Text("fn main() {}".to_string()), tool_call_id: "call_1".to_string() },
+        ];
+
+        let converted = to_parsing_messages(&messages);
+        match &converted[0] {
+            ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), tool_calls: None, .. } => {
+                let (_, calls) = parse_tool_calls(text, &toolbox());
+                assert_eq!(calls[0].function.name, "read_file");
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+        match &converted[1] {
+            ChatMessage::User { content: ChatMessageContent::Text(text), .. } => {
+                assert_eq!(text, "<tool_result name=\"read_file\">\nfn main() {}\n</tool_result>");
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+}